@@ -1,11 +1,208 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
+use crate::resource_access::string_interop::compiler::{from_string, CompileError};
+use crate::resource_access::{AccessKind, AccessPathSet};
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Claims {
+    /// The identity service environment which issued the token.
+    pub iss: String,
     pub sub: String,
+    /// The service the token is meant for, which rejects tokens minted for others.
+    pub aud: String,
     pub exp: usize,
+    pub nbf: usize,
+    pub iat: usize,
+    /// Unique ID of the token.
+    pub jti: String,
     pub email: String,
     pub first_name: String,
     pub last_name: String,
+
+    /// The session the token was issued in, which outlives the token until it is revoked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scopes: Option<AccessScopes>,
+}
+
+/// The access path sets of the subject, compiled when the token was issued, which allow authorizing
+/// its requests without asking the identity service.
+///
+/// Scopes are not updated when the permissions of the subject change, so they are only trusted for a
+/// short while after `issued_at`, which bounds how long such changes take to apply.
+///
+/// Field names are kept short, since they are part of every request carrying the token.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct AccessScopes {
+    /// When the access path sets were compiled, as a Unix timestamp.
+    #[serde(rename = "iat")]
+    pub issued_at: usize,
+
+    #[serde(rename = "q", default)]
+    pub query: ScopePaths,
+
+    #[serde(rename = "m", default)]
+    pub mutation: ScopePaths,
+
+    #[serde(rename = "s", default)]
+    pub subscription: ScopePaths,
+}
+
+/// An access path set, rendered as resource path strings.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct ScopePaths {
+    #[serde(rename = "a", default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed: Vec<String>,
+
+    #[serde(rename = "d", default, skip_serializing_if = "Vec::is_empty")]
+    pub denied: Vec<String>,
+}
+
+impl Claims {
+    /// The string claims of the token, keyed by their serialized name (e.g. `firstName`).
+    pub fn string_claims(&self) -> HashMap<String, String> {
+        HashMap::from([
+            ("sub".to_owned(), self.sub.clone()),
+            ("email".to_owned(), self.email.clone()),
+            ("firstName".to_owned(), self.first_name.clone()),
+            ("lastName".to_owned(), self.last_name.clone()),
+        ])
+    }
+}
+
+impl AccessScopes {
+    pub fn paths(&self, access_kind: AccessKind) -> &ScopePaths {
+        match access_kind {
+            AccessKind::Query => &self.query,
+            AccessKind::Mutation => &self.mutation,
+            AccessKind::Subscription => &self.subscription,
+        }
+    }
+
+    pub fn paths_mut(&mut self, access_kind: AccessKind) -> &mut ScopePaths {
+        match access_kind {
+            AccessKind::Query => &mut self.query,
+            AccessKind::Mutation => &mut self.mutation,
+            AccessKind::Subscription => &mut self.subscription,
+        }
+    }
+}
+
+impl ScopePaths {
+    /// Compiles the resource path strings back into an access path set.
+    pub fn access_path_set(&self) -> Result<AccessPathSet, CompileError> {
+        let mut access_path_set = AccessPathSet::default();
+        for raw in &self.allowed {
+            from_string(raw)?
+                .into_paths()
+                .into_iter()
+                .for_each(|path| access_path_set.allow(path));
+        }
+        for raw in &self.denied {
+            from_string(raw)?
+                .into_paths()
+                .into_iter()
+                .for_each(|path| access_path_set.deny(path));
+        }
+
+        Ok(access_path_set)
+    }
+}
+
+impl From<&AccessPathSet> for ScopePaths {
+    fn from(access_path_set: &AccessPathSet) -> Self {
+        ScopePaths {
+            allowed: access_path_set
+                .allowed
+                .paths()
+                .into_iter()
+                .map(ToString::to_string)
+                .collect(),
+            denied: access_path_set
+                .denied
+                .paths()
+                .into_iter()
+                .map(ToString::to_string)
+                .collect(),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    fn access_path_set(allowed: &str, denied: &str) -> AccessPathSet {
+        ScopePaths {
+            allowed: vec![allowed.to_owned()],
+            denied: vec![denied.to_owned()],
+        }
+        .access_path_set()
+        .unwrap()
+    }
+
+    #[rstest]
+    #[case("account(id: \"abc\")::{email, firstName}", "account::password")]
+    #[case("courses(filter: {year: 2026})::*", "courses::students")]
+    #[case("accounts::*", "accounts(id: in [\"a\", \"b\"])")]
+    fn scope_paths_roundtrip(#[case] allowed: &str, #[case] denied: &str) {
+        let expected = access_path_set(allowed, denied);
+
+        let scope_paths = ScopePaths::from(&expected);
+        let actual = scope_paths.access_path_set().unwrap();
+
+        assert_eq!(ScopePaths::from(&actual), scope_paths);
+    }
+
+    #[test]
+    fn claims_without_scopes() {
+        let input = serde_json::json!({
+            "iss": "identity-service",
+            "sub": "abc",
+            "aud": "frontend",
+            "exp": 1,
+            "nbf": 0,
+            "iat": 0,
+            "jti": "5d8b1f3e-2f0c-4a4e-9f1a-0c6f3b2a7e10",
+            "email": "jane@uni.edu",
+            "firstName": "Jane",
+            "lastName": "Doe"
+        });
+
+        let claims: Claims = serde_json::from_value(input.clone()).unwrap();
+
+        assert_eq!(claims.scopes, None);
+        assert_eq!(serde_json::to_value(&claims).unwrap(), input);
+    }
+
+    #[test]
+    fn claims_with_scopes() {
+        let input = serde_json::json!({
+            "iss": "identity-service",
+            "sub": "abc",
+            "aud": "frontend",
+            "exp": 1,
+            "nbf": 0,
+            "iat": 0,
+            "jti": "5d8b1f3e-2f0c-4a4e-9f1a-0c6f3b2a7e10",
+            "email": "jane@uni.edu",
+            "firstName": "Jane",
+            "lastName": "Doe",
+            "scopes": {"iat": 1, "q": {"a": ["accounts::*"]}}
+        });
+
+        let claims: Claims = serde_json::from_value(input).unwrap();
+        let scopes = claims.scopes.unwrap();
+
+        assert_eq!(scopes.paths(AccessKind::Query).allowed, vec!["accounts::*"]);
+        assert_eq!(scopes.paths(AccessKind::Mutation), &ScopePaths::default());
+    }
 }
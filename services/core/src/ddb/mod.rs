@@ -1,4 +1,5 @@
 pub mod adapter;
+pub mod delete_item;
 pub mod get_item;
 pub mod put_item;
 pub mod query;
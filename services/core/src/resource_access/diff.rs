@@ -0,0 +1,87 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use super::types::{PathSet, Superset};
+
+/// The semantic difference between two path sets: the paths covered only by the newer set and the
+/// paths covered only by the older one.
+#[derive(Debug, Clone, Default)]
+pub struct PathSetDiff {
+    /// Branches of the newer set which the older set does not cover.
+    pub added: PathSet,
+
+    /// Branches of the older set which the newer set does not cover anymore.
+    pub removed: PathSet,
+}
+
+
+/// Computes what changed between two path sets, in terms of the paths they cover rather than of how
+/// they are written. E.g. going from `accounts::{id, email}` to `accounts::*` removes nothing.
+pub fn diff(before: &PathSet, after: &PathSet) -> PathSetDiff {
+    PathSetDiff {
+        added: after.filter_branches(|branch| !before.is_superset_of(branch)),
+        removed: before.filter_branches(|branch| !after.is_superset_of(branch)),
+    }
+}
+
+impl PathSetDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.paths.is_empty() && self.removed.paths.is_empty()
+    }
+}
+
+/// Renders one path per line, prefixed with `+` when added and with `-` when removed.
+impl Display for PathSetDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let added = self.added.paths().into_iter().map(|path| ('+', path));
+        let removed = self.removed.paths().into_iter().map(|path| ('-', path));
+
+        for (idx, (sign, path)) in added.chain(removed).enumerate() {
+            if idx > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{} {}", sign, path)?;
+        }
+
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+    use crate::resource_access::string_interop::compiler::from_string;
+
+    fn path_set(paths: &[&str]) -> PathSet {
+        let mut path_set = PathSet::default();
+        paths
+            .iter()
+            .for_each(|path| path_set.merge_path_set(from_string(path).unwrap()));
+        path_set
+    }
+
+    #[rstest]
+    #[case(&["accounts::{id, email}"], &["accounts::*"], "+ accounts::*")]
+    #[case(&["accounts::*"], &["accounts::{id, email}"], "- accounts::*")]
+    #[case(&["accounts::id"], &["accounts::{id, email}"], "+ accounts::email")]
+    #[case(&["accounts::{id, email}"], &["accounts::id", "account(id: *)"], "+ account(id: *)\n- accounts::email")]
+    #[case(&["account(id: *)::email"], &["account(id: \"a\")::email"], "- account(id: *)::email")]
+    #[case(&["account(id: \"a\")::email"], &["account(id: *)::email"], "+ account(id: *)::email")]
+    fn renders_changes(#[case] before: &[&str], #[case] after: &[&str], #[case] expected: &str) {
+        let diff = diff(&path_set(before), &path_set(after));
+
+        assert_eq!(diff.to_string(), expected);
+    }
+
+    #[rstest]
+    #[case(&["accounts::{id, email}"], &["accounts::{email, id}"])]
+    #[case(&["accounts::id", "accounts::email"], &["accounts::{id, email}"])]
+    fn equivalent_sets(#[case] before: &[&str], #[case] after: &[&str]) {
+        let diff = diff(&path_set(before), &path_set(after));
+
+        assert!(diff.is_empty());
+        assert_eq!(diff.to_string(), "");
+    }
+}
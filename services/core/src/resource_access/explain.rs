@@ -0,0 +1,185 @@
+use super::types::{AccessPathSet, Intersects, PathNode, PathSet, Segment, Superset};
+
+/// Why a requested path was not granted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DenialReason {
+    /// No allow statement covers the path.
+    NotAllowed,
+
+    /// A deny statement matches the path.
+    Denied,
+}
+
+/// A single requested path, from root to leaf, that was not granted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UncoveredPath {
+    pub path: PathNode,
+    pub reason: DenialReason,
+}
+
+/// The allowed path which came closest to covering a requested path.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClosestPath {
+    /// The allowed path, from the root down to the deepest segment that matched.
+    pub path: PathNode,
+
+    /// How well the allowed path matched. Each segment matched by name scores 1, and each segment
+    /// also matched by arguments scores 2.
+    pub score: usize,
+}
+
+
+impl AccessPathSet {
+    /// Lists each branch of the desired paths which is either not allowed or explicitly denied.
+    ///
+    /// Returns an empty list if and only if `permits` would return true for the same paths.
+    pub fn uncovered_paths(&self, desired: &PathSet) -> Vec<UncoveredPath> {
+        desired
+            .paths()
+            .into_iter()
+            .flat_map(PathNode::branches)
+            .filter_map(|branch| {
+                let branch_set = PathSet::from(branch.clone());
+
+                if self.denied.intersects(&branch_set) {
+                    Some(UncoveredPath {
+                        path: branch,
+                        reason: DenialReason::Denied,
+                    })
+                } else if !self.allowed.is_superset_of(&branch_set) {
+                    Some(UncoveredPath {
+                        path: branch,
+                        reason: DenialReason::NotAllowed,
+                    })
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+impl PathSet {
+    /// Finds the path in this set that matches the most segments of the given branch.
+    ///
+    /// # Arguments
+    ///
+    /// * `branch` - a path with at most one field on each level, as returned by `PathNode::branches`.
+    ///
+    /// # Returns
+    ///
+    /// Returns `None` if no root of this set matches the root of the branch, not even by name.
+    pub fn closest_path(&self, branch: &PathNode) -> Option<ClosestPath> {
+        let chain = branch.chain();
+        closest_in(self.paths.values(), &chain).map(|(score, path)| ClosestPath { path, score })
+    }
+}
+
+impl PathNode {
+    /// Segments of a single-chain path, from root to leaf. Only the first field on each level is
+    /// followed.
+    fn chain(&self) -> Vec<&Segment> {
+        let mut chain = vec![&self.segment];
+        let mut curr = self;
+        while let Some(next) = curr.fields.values().next() {
+            chain.push(&next.segment);
+            curr = next;
+        }
+
+        chain
+    }
+}
+
+
+fn closest_in<'a>(candidates: impl Iterator<Item = &'a PathNode>, chain: &[&Segment]) -> Option<(usize, PathNode)> {
+    let (head, tail) = chain.split_first()?;
+
+    candidates
+        .filter_map(|candidate| {
+            let score = match (&candidate.segment, *head) {
+                (Segment::Any, _) => 2,
+                (l, r) if l.is_superset_of(r) => 2,
+                (Segment::Named(lname, _), Segment::Named(rname, _)) if lname == rname => 1,
+                _ => return None,
+            };
+
+            match closest_in(candidate.fields.values(), tail) {
+                Some((sub_score, sub_path)) => {
+                    let mut path = PathNode::new(candidate.segment.clone());
+                    path.fields.insert(sub_path.segment.clone(), sub_path);
+                    Some((score + sub_score, path))
+                }
+                None => Some((score, candidate.clone())),
+            }
+        })
+        .max_by_key(|(score, _)| *score)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+    use crate::resource_access::string_interop::compiler::from_string;
+
+    fn access_path_set(allowed: &[&str], denied: &[&str]) -> AccessPathSet {
+        let mut access_path_set = AccessPathSet::default();
+        for path in allowed {
+            from_string(path)
+                .unwrap()
+                .into_paths()
+                .into_iter()
+                .for_each(|p| access_path_set.allow(p));
+        }
+        for path in denied {
+            from_string(path)
+                .unwrap()
+                .into_paths()
+                .into_iter()
+                .for_each(|p| access_path_set.deny(p));
+        }
+        access_path_set
+    }
+
+    #[test]
+    fn uncovered_paths() {
+        let access_path_set = access_path_set(&["a::{b, c}"], &["a::c"]);
+        let desired = from_string("a::{b, c, d}").unwrap();
+
+        let uncovered = access_path_set.uncovered_paths(&desired);
+        let rendered: Vec<(String, DenialReason)> =
+            uncovered.into_iter().map(|u| (u.path.to_string(), u.reason)).collect();
+
+        assert_eq!(
+            rendered,
+            vec![
+                ("a::c".to_owned(), DenialReason::Denied),
+                ("a::d".to_owned(), DenialReason::NotAllowed),
+            ]
+        );
+    }
+
+    #[test]
+    fn nothing_uncovered_when_permitted() {
+        let access_path_set = access_path_set(&["a::*"], &[]);
+        let desired = from_string("a::{b, c::d}").unwrap();
+
+        assert!(access_path_set.permits(&desired));
+        assert!(access_path_set.uncovered_paths(&desired).is_empty());
+    }
+
+    #[rstest]
+    #[case("a::{b, c}", "a::d", Some(("a::{b, c}", 2)))]
+    #[case("a::{b::x, c}", "a::b::y", Some(("a::b::x", 4)))]
+    #[case("a(id: 1)::b", "a(id: 2)::b", Some(("a(id: 1)::b", 3)))]
+    #[case("a::*", "a::b", Some(("a::*", 4)))]
+    #[case("x::y", "a::b", None)]
+    fn closest_path(#[case] allowed: &str, #[case] branch: &str, #[case] expected: Option<(&str, usize)>) {
+        let allowed = from_string(allowed).unwrap();
+        let branch = from_string(branch).unwrap().into_paths().pop().unwrap();
+
+        let closest = allowed.closest_path(&branch).map(|c| (c.path.to_string(), c.score));
+        assert_eq!(closest, expected.map(|(path, score)| (path.to_owned(), score)));
+    }
+}
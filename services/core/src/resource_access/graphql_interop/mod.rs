@@ -1 +1,3 @@
 pub mod parser;
+pub mod schema;
+pub mod validation;
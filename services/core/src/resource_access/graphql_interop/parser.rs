@@ -1,11 +1,16 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
 use async_graphql_parser::types::{
-    DocumentOperations, ExecutableDocument, OperationDefinition, OperationType, Selection,
+    Directive, DocumentOperations, ExecutableDocument, Field, FragmentDefinition, InlineFragment, OperationDefinition,
+    Selection, SelectionSet,
 };
-use async_graphql_value::{ConstValue, Value, Variables};
+use async_graphql_parser::{Pos, Positioned};
+use async_graphql_value::{ConstValue, Name, Value, Variables};
 use thiserror::Error;
 
+use crate::resource_access::graphql_interop::schema::{FieldInfo, SchemaInfo};
 use crate::resource_access::types::{
-    AccessKind, AccessRequest, AppendNodeError, Argument, ArgumentValue, PathNode, PathSet, Segment,
+    AccessRequest, AppendNodeError, Argument, ArgumentValue, Float, PathNode, PathSet, Segment,
 };
 
 #[derive(Error, Debug)]
@@ -13,14 +18,14 @@ pub enum CompileError {
     #[error("Unsupported selection kind: {0}")]
     UnsupportedSelectionKind(String),
 
-    #[error("Unsupported numeric literal. Only i64 is supported.")]
+    #[error("Unsupported numeric literal. Only i64 and f64 are supported.")]
     UnsupportedNumericLiteral,
 
-    #[error("Document with multiple operations not supported.")]
-    MultiOperationsNotSupported,
+    #[error("Unknown operation named {0}.")]
+    UnknownOperation(String),
 
-    #[error("Operation {0} not supported.")]
-    UnsupportedOperation(OperationType),
+    #[error("Operation name required for a document with multiple operations.")]
+    OperationNameRequired,
 
     #[error("Argument {0} is not supported: {1}.")]
     UnsupportedArgument(String, String),
@@ -30,77 +35,531 @@ pub enum CompileError {
 
     #[error("Cannot select subfields of a match-any.")]
     CannotAppendToAny,
+
+    #[error("Fragment {0} is unknown.")]
+    UnknownFragment(String),
+
+    #[error("Fragment {0} spreads itself.")]
+    FragmentCycle(String),
+
+    #[error("Condition of directive @{0} must be a boolean.")]
+    InvalidDirectiveCondition(String),
+}
+
+/// Root path under which the introspection fields `__schema` and `__type` are authorized.
+pub const INTROSPECTION_PATH: &str = "__introspection";
+
+const TYPENAME_FIELD: &str = "__typename";
+const SCHEMA_FIELD: &str = "__schema";
+const TYPE_FIELD: &str = "__type";
+
+type Fragments = HashMap<Name, Positioned<FragmentDefinition>>;
+
+/// Everything a selection may refer to, besides the selection itself.
+struct Scope<'a> {
+    fragments: &'a Fragments,
+    schema: &'a SchemaInfo,
+    variables: &'a Variables,
+}
+
+/// A field left out of a document by `prune_document`.
+#[derive(Debug, Clone)]
+pub struct PrunedField {
+    /// Response keys (i.e. aliases or names) from the root of the operation down to the field.
+    pub path: Vec<String>,
+
+    /// Response keys down to the value which is set to `null` in place of the field: the field itself
+    /// when it may be null, otherwise its closest ancestor which may, as null values propagate to it.
+    /// Empty when the whole data of the response is null.
+    ///
+    /// List items are not told apart, so a non-null field of a list item nulls the field holding the
+    /// list, or one of its ancestors.
+    pub null_path: Vec<String>,
+
+    /// Position of the field in the document.
+    pub pos: Pos,
+}
+
+/// Where a selection set is in the response.
+#[derive(Debug, Clone, Default)]
+struct ResponsePath {
+    /// Response keys from the root of the operation down to the selection set.
+    keys: Vec<String>,
+
+    /// Number of leading keys which lead to the closest field that may be null.
+    nullable_len: usize,
+}
+
+/// Compiles one access request for each operation of the document, ordered by operation name.
+pub fn from_document(
+    document: &ExecutableDocument,
+    schema: &SchemaInfo,
+    variables: &Variables,
+) -> Result<Vec<AccessRequest>, CompileError> {
+    let scope = Scope {
+        fragments: &document.fragments,
+        schema,
+        variables,
+    };
+
+    let mut operations: Vec<_> = document.operations.iter().collect();
+    operations.sort_by_key(|(name, _)| *name);
+
+    operations
+        .into_iter()
+        .map(|(_, operation)| compile_operation(&operation.node, &scope))
+        .collect()
+}
+
+/// Compiles the access request for the operation which will be executed for the given operation
+/// name.
+///
+/// The operation is selected the same way the executor does: by name if one is given, or the only
+/// operation of the document otherwise.
+pub fn from_document_operation(
+    document: &ExecutableDocument,
+    schema: &SchemaInfo,
+    variables: &Variables,
+    operation_name: Option<&str>,
+) -> Result<AccessRequest, CompileError> {
+    let scope = Scope {
+        fragments: &document.fragments,
+        schema,
+        variables,
+    };
+
+    let (_, operation) = select_operation(&document.operations, operation_name)?;
+    compile_operation(&operation.node, &scope)
+}
+
+/// Removes every field of the document which is not covered by the granted path set.
+///
+/// # Params
+/// * `document` - the document to be pruned
+/// * `schema` - the schema the document is executed against
+/// * `variables` - the variables known for this GraphQL request
+/// * `operation_name` - the name of the operation to be executed, if any; other operations are left
+///   untouched
+/// * `granted` - the paths allowed for this document, e.g. as computed by `AccessPathSet::prune`
+///   from the access request compiled by `from_document_operation` for the same operation
+///
+/// # Returns
+/// Returns the fields which were removed. Fields under a removed field are not listed.
+pub fn prune_document(
+    document: &mut ExecutableDocument,
+    schema: &SchemaInfo,
+    variables: &Variables,
+    operation_name: Option<&str>,
+    granted: &PathSet,
+) -> Result<Vec<PrunedField>, CompileError> {
+    let selected = select_operation(&document.operations, operation_name)?.0.cloned();
+    let operation = match (&mut document.operations, selected) {
+        (DocumentOperations::Multiple(operations), Some(name)) => operations.get_mut(&name),
+        (DocumentOperations::Single(operation), None) => Some(operation),
+        _ => None,
+    }
+    .expect("selected operation must exist");
+
+    let scope = Scope {
+        fragments: &document.fragments,
+        schema,
+        variables,
+    };
+    let root_type = schema.root_type(operation.node.ty);
+
+    let mut pruned = vec![];
+    let mut inlined = vec![];
+    prune_selection_set(
+        &mut operation.node.selection_set.node,
+        Some(root_type),
+        &granted.paths,
+        ResponsePath::default(),
+        &scope,
+        &mut inlined,
+        &mut pruned,
+    )?;
+    remove_unused_fragments(document);
+
+    Ok(pruned)
 }
 
-pub fn from_document(document: &ExecutableDocument, variables: &Variables) -> Result<Vec<AccessRequest>, CompileError> {
-    match &document.operations {
-        DocumentOperations::Single(operation) => {
-            let operation = &operation.node;
-            let access_kind: AccessKind = operation
-                .ty
-                .try_into()
-                .map_err(|_| CompileError::UnsupportedOperation(operation.ty))?;
-
-            Ok(vec![AccessRequest {
-                kind: access_kind,
-                paths: from_operation(operation, &variables)?,
-            }])
+/// Selects the operation to be executed, along with its name.
+fn select_operation<'a>(
+    operations: &'a DocumentOperations,
+    operation_name: Option<&str>,
+) -> Result<(Option<&'a Name>, &'a Positioned<OperationDefinition>), CompileError> {
+    match (operations, operation_name) {
+        (DocumentOperations::Multiple(operations), Some(operation_name)) => operations
+            .get_key_value(operation_name)
+            .map(|(name, operation)| (Some(name), operation))
+            .ok_or_else(|| CompileError::UnknownOperation(operation_name.to_string())),
+        (DocumentOperations::Single(_), Some(operation_name)) => {
+            Err(CompileError::UnknownOperation(operation_name.to_string()))
+        }
+        (DocumentOperations::Single(operation), None) => Ok((None, operation)),
+        (DocumentOperations::Multiple(operations), None) if operations.len() == 1 => {
+            let (name, operation) = operations.iter().next().unwrap();
+            Ok((Some(name), operation))
         }
-        DocumentOperations::Multiple(_) => Err(CompileError::MultiOperationsNotSupported),
+        (DocumentOperations::Multiple(_), None) => Err(CompileError::OperationNameRequired),
     }
 }
 
-fn from_operation(operation: &OperationDefinition, variables: &Variables) -> Result<Vec<PathNode>, CompileError> {
+fn compile_operation(operation: &OperationDefinition, scope: &Scope) -> Result<AccessRequest, CompileError> {
+    Ok(AccessRequest {
+        kind: operation.ty.into(),
+        paths: from_operation(operation, scope)?,
+    })
+}
+
+fn from_operation(operation: &OperationDefinition, scope: &Scope) -> Result<Vec<PathNode>, CompileError> {
+    let root_type = scope.schema.root_type(operation.ty);
+
     let mut path_set = PathSet::default();
+    let mut spreads = vec![];
     operation
         .selection_set
         .node
         .items
         .iter()
         .map(|i| &i.node)
-        .try_for_each(|node| append_path(&mut path_set, vec![], node, &variables))?;
+        .try_for_each(|node| append_path(&mut path_set, vec![], Some(root_type), node, scope, &mut spreads))?;
     Ok(path_set.into_paths())
 }
 
+/// Appends the paths of a selection to the tree.
+///
+/// # Params
+/// * `stack` - the segments from the root of the operation down to the parent of the selection
+/// * `parent_type` - the type the selection is made on, if known
+/// * `spreads` - the names of the fragments being spread, from the outermost to the innermost one;
+///   used to detect cycles
 fn append_path(
     tree: &mut PathSet,
     mut stack: Vec<Segment>,
+    parent_type: Option<&str>,
     selection: &Selection,
-    variables: &Variables,
+    scope: &Scope,
+    spreads: &mut Vec<Name>,
 ) -> Result<(), CompileError> {
+    if is_skipped(selection.directives(), scope.variables)? {
+        return Ok(());
+    }
+
     match selection {
         Selection::Field(field) => {
             let field = &field.node;
-            let segment = Segment::with_args(
-                field.name.clone().into_inner().as_str(),
-                field
-                    .arguments
-                    .iter()
-                    .map(|(name, val)| {
-                        let name = name.node.to_string();
-                        let value = parse_argument_value(&name, &val.node, variables)?;
-
-                        Ok(Argument { name, value })
-                    })
-                    .collect::<Result<Vec<_>, CompileError>>()?,
-            );
-            stack.push(segment);
+            match field.name.node.as_str() {
+                // The type name of an object is as visible as the object itself.
+                TYPENAME_FIELD if stack.is_empty() => return Ok(()),
+                TYPENAME_FIELD => return extend_path(tree, stack),
+                SCHEMA_FIELD | TYPE_FIELD => return extend_path(tree, [Segment::no_args(INTROSPECTION_PATH)]),
+                _ => {}
+            }
+
+            let field_info = scope.schema.field(parent_type, field.name.node.as_str());
+            stack.push(field_segment(field, field_info, scope.variables)?);
 
             let sub_fields = &field.selection_set.node.items;
             if sub_fields.is_empty() {
-                tree.extend(stack.clone()).map_err(|e| match e {
-                    AppendNodeError::CannotAppendToAny => CompileError::CannotAppendToAny,
-                })?;
+                extend_path(tree, stack.clone())?;
             }
 
+            let field_type = field_info.map(|info| info.type_name.as_str());
             for sub_field in sub_fields {
-                append_path(tree, stack.clone(), &sub_field.node, &variables)?;
+                append_path(tree, stack.clone(), field_type, &sub_field.node, scope, spreads)?;
             }
 
             Ok(())
         }
-        _ => Err(CompileError::unsupported_selection_kind(".")),
+        Selection::InlineFragment(fragment) => {
+            let fragment = &fragment.node;
+            let fragment_type = fragment
+                .type_condition
+                .as_ref()
+                .map_or(parent_type, |condition| Some(condition.node.on.node.as_str()));
+
+            fragment
+                .selection_set
+                .node
+                .items
+                .iter()
+                .try_for_each(|item| append_path(tree, stack.clone(), fragment_type, &item.node, scope, spreads))
+        }
+        Selection::FragmentSpread(spread) => {
+            let name = &spread.node.fragment_name.node;
+            let fragment = resolve_fragment(scope.fragments, spreads, name)?;
+            let fragment_type = Some(fragment.node.type_condition.node.on.node.as_str());
+
+            spreads.push(name.clone());
+            fragment
+                .node
+                .selection_set
+                .node
+                .items
+                .iter()
+                .try_for_each(|item| append_path(tree, stack.clone(), fragment_type, &item.node, scope, spreads))?;
+            spreads.pop();
+
+            Ok(())
+        }
+    }
+}
+
+fn extend_path(tree: &mut PathSet, path: impl IntoIterator<Item = Segment>) -> Result<(), CompileError> {
+    tree.extend(path).map_err(|e| match e {
+        AppendNodeError::CannotAppendToAny => CompileError::CannotAppendToAny,
+    })
+}
+
+fn prune_selection_set(
+    selection_set: &mut SelectionSet,
+    parent_type: Option<&str>,
+    granted: &BTreeMap<Segment, PathNode>,
+    response_path: ResponsePath,
+    scope: &Scope,
+    spreads: &mut Vec<Name>,
+    pruned: &mut Vec<PrunedField>,
+) -> Result<(), CompileError> {
+    let mut retained = Vec::with_capacity(selection_set.items.len());
+    for mut item in std::mem::take(&mut selection_set.items) {
+        // Skipped selections are not part of the access request, and are removed before execution.
+        if is_skipped(item.node.directives(), scope.variables)? {
+            retained.push(item);
+            continue;
+        }
+
+        match &mut item.node {
+            Selection::Field(field) => {
+                let field = &mut field.node;
+                let response_key = field.response_key().node.to_string();
+
+                match field.name.node.as_str() {
+                    TYPENAME_FIELD => retained.push(item),
+                    SCHEMA_FIELD | TYPE_FIELD if granted.contains_key(&Segment::no_args(INTROSPECTION_PATH)) => {
+                        retained.push(item)
+                    }
+                    // `__schema` is non-null, while `__type` is not.
+                    field_name @ (SCHEMA_FIELD | TYPE_FIELD) => {
+                        let path = response_path.child(response_key, field_name == TYPE_FIELD);
+                        pruned.push(path.pruned_field(item.pos));
+                    }
+                    field_name => {
+                        let field_info = scope.schema.field(parent_type, field_name);
+                        // Fields unknown to the schema are assumed to be nullable.
+                        let path = response_path.child(response_key, field_info.map_or(true, |info| info.nullable));
+                        match granted.get(&field_segment(field, field_info, scope.variables)?) {
+                            Some(node) => {
+                                prune_selection_set(
+                                    &mut field.selection_set.node,
+                                    field_info.map(|info| info.type_name.as_str()),
+                                    &node.fields,
+                                    path,
+                                    scope,
+                                    spreads,
+                                    pruned,
+                                )?;
+                                retained.push(item);
+                            }
+                            None => pruned.push(path.pruned_field(item.pos)),
+                        }
+                    }
+                }
+            }
+            Selection::InlineFragment(fragment) => {
+                let fragment = &mut fragment.node;
+                let fragment_type = fragment
+                    .type_condition
+                    .as_ref()
+                    .map_or(parent_type, |condition| Some(condition.node.on.node.as_str()));
+
+                prune_selection_set(
+                    &mut fragment.selection_set.node,
+                    fragment_type,
+                    granted,
+                    response_path.clone(),
+                    scope,
+                    spreads,
+                    pruned,
+                )?;
+                retained.push(item);
+            }
+            Selection::FragmentSpread(spread) => {
+                let name = spread.node.fragment_name.node.clone();
+                let fragment = resolve_fragment(scope.fragments, spreads, &name)?;
+
+                // Fragment definitions may be shared with other spreads, so the spread is pruned as
+                // an inline copy of the fragment.
+                let mut selection_set = fragment.node.selection_set.clone();
+                let pruned_before = pruned.len();
+                spreads.push(name);
+                prune_selection_set(
+                    &mut selection_set.node,
+                    Some(fragment.node.type_condition.node.on.node.as_str()),
+                    granted,
+                    response_path.clone(),
+                    scope,
+                    spreads,
+                    pruned,
+                )?;
+                spreads.pop();
+
+                if pruned.len() == pruned_before {
+                    retained.push(item);
+                } else {
+                    let inline_fragment = InlineFragment {
+                        type_condition: Some(fragment.node.type_condition.clone()),
+                        directives: std::mem::take(&mut spread.node.directives),
+                        selection_set,
+                    };
+                    retained.push(Positioned::new(
+                        Selection::InlineFragment(Positioned::new(inline_fragment, spread.pos)),
+                        item.pos,
+                    ));
+                }
+            }
+        }
+    }
+
+    selection_set.items = retained;
+    Ok(())
+}
+
+impl ResponsePath {
+    /// The path of a field of the selection set.
+    fn child(&self, response_key: String, nullable: bool) -> Self {
+        let mut keys = self.keys.clone();
+        keys.push(response_key);
+        let nullable_len = if nullable { keys.len() } else { self.nullable_len };
+
+        ResponsePath { keys, nullable_len }
+    }
+
+    /// Reports the field at this path as pruned.
+    fn pruned_field(self, pos: Pos) -> PrunedField {
+        PrunedField {
+            null_path: self.keys[..self.nullable_len].to_vec(),
+            path: self.keys,
+            pos,
+        }
+    }
+}
+
+/// Looks up the definition of a spread fragment, unless spreading it would create a cycle.
+fn resolve_fragment<'a>(
+    fragments: &'a Fragments,
+    spreads: &[Name],
+    name: &Name,
+) -> Result<&'a Positioned<FragmentDefinition>, CompileError> {
+    if spreads.contains(name) {
+        return Err(CompileError::FragmentCycle(name.to_string()));
+    }
+
+    fragments
+        .get(name)
+        .ok_or_else(|| CompileError::UnknownFragment(name.to_string()))
+}
+
+/// Removes the fragment definitions which are no longer spread anywhere in the document, e.g. after
+/// their spreads were inlined by pruning. Unused fragments would fail the validation of the document.
+fn remove_unused_fragments(document: &mut ExecutableDocument) {
+    loop {
+        let mut used = HashSet::new();
+        document
+            .operations
+            .iter()
+            .for_each(|(_, operation)| collect_spreads(&operation.node.selection_set.node, &mut used));
+        document
+            .fragments
+            .values()
+            .for_each(|fragment| collect_spreads(&fragment.node.selection_set.node, &mut used));
+
+        let count = document.fragments.len();
+        document.fragments.retain(|name, _| used.contains(name));
+        if document.fragments.len() == count {
+            return;
+        }
+    }
+}
+
+fn collect_spreads(selection_set: &SelectionSet, used: &mut HashSet<Name>) {
+    for item in &selection_set.items {
+        match &item.node {
+            Selection::Field(field) => collect_spreads(&field.node.selection_set.node, used),
+            Selection::InlineFragment(fragment) => collect_spreads(&fragment.node.selection_set.node, used),
+            Selection::FragmentSpread(spread) => {
+                used.insert(spread.node.fragment_name.node.clone());
+            }
+        }
+    }
+}
+
+/// Tells whether a selection is excluded by a `@skip` or `@include` directive.
+fn is_skipped(directives: &[Positioned<Directive>], variables: &Variables) -> Result<bool, CompileError> {
+    for directive in directives {
+        let directive = &directive.node;
+        let skip_when = match directive.name.node.as_str() {
+            "skip" => true,
+            "include" => false,
+            _ => continue,
+        };
+
+        let condition = match directive.get_argument("if").map(|v| &v.node) {
+            Some(Value::Boolean(b)) => *b,
+            Some(Value::Variable(var_name)) => match variables.get(var_name) {
+                Some(ConstValue::Boolean(b)) => *b,
+                Some(_) => return Err(CompileError::InvalidDirectiveCondition(directive.name.node.to_string())),
+                None => return Err(CompileError::UnknownVariable(var_name.to_string(), "if".to_owned())),
+            },
+            _ => return Err(CompileError::InvalidDirectiveCondition(directive.name.node.to_string())),
+        };
+        if condition == skip_when {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/// Builds the path segment of a field, with its arguments resolved against the given variables.
+///
+/// Arguments which are omitted get the default value declared by the schema, if any, so that the
+/// segment is the same whether a default argument is given explicitly or not. Arguments holding their
+/// default value are marked as such, since grants which do not mention them still match.
+fn field_segment(
+    field: &Field,
+    field_info: Option<&FieldInfo>,
+    variables: &Variables,
+) -> Result<Segment, CompileError> {
+    let mut arguments = field
+        .arguments
+        .iter()
+        .map(|(name, val)| {
+            let name = name.node.to_string();
+            let value = parse_argument_value(&name, &val.node, variables)?;
+
+            Ok(Argument {
+                name,
+                value,
+                is_default: false,
+            })
+        })
+        .collect::<Result<Vec<_>, CompileError>>()?;
+
+    let default_arguments = field_info.map_or(&[][..], |info| &info.default_arguments[..]);
+    for (name, default_value) in default_arguments {
+        let default_value = parse_const_value(name, default_value, variables)?;
+        match arguments.iter_mut().find(|arg| &arg.name == name) {
+            Some(argument) => argument.is_default = argument.value == default_value,
+            None => arguments.push(Argument {
+                name: name.clone(),
+                value: default_value,
+                is_default: true,
+            }),
+        }
     }
+
+    Ok(Segment::with_args(field.name.node.as_str(), arguments))
 }
 
 /// Parses argument values from GraphQL Value to ArgumentValue type.
@@ -114,37 +573,57 @@ fn append_path(
 /// Returns a parsed argument value, or a compilation error.
 ///
 /// # Limitations
-/// * This parser only supports `i64` for numeric literals. It does not support `u64` or `f64`.
-/// * Only string, number (with the above limitation) and boolean types are supported for arguments.
-/// * If the argument references a variable, the parser will try to replace that. If the variable is
-///   of an unsupported type, the resulting argument value will be a wildcard.
+/// * Numeric literals must fit either an `i64` or an `f64`. Integers beyond `i64` are not supported.
+/// * Binary values are not supported.
+/// * If the argument references a variable, the parser will try to replace that, also within lists
+///   and input objects. If the variable is of an unsupported type, the resulting argument value will
+///   be a wildcard.
 fn parse_argument_value(name: &str, value: &Value, variables: &Variables) -> Result<ArgumentValue, CompileError> {
     match value {
         Value::String(s) => Ok(ArgumentValue::StringLiteral(s.clone())),
-        Value::Number(n) => Ok(ArgumentValue::IntegerLiteral(
-            n.as_i64().ok_or(CompileError::UnsupportedNumericLiteral)?,
-        )),
+        Value::Number(n) => match (n.as_i64(), n.as_f64()) {
+            (Some(i), _) => Ok(ArgumentValue::IntegerLiteral(i)),
+            (None, Some(f)) if n.is_f64() => Ok(ArgumentValue::FloatLiteral(Float(f))),
+            _ => Err(CompileError::UnsupportedNumericLiteral),
+        },
         Value::Boolean(b) => Ok(ArgumentValue::BoolLiteral(*b)),
         Value::Enum(name) => Ok(ArgumentValue::Enum(name.to_string())),
+        Value::Null => Ok(ArgumentValue::Null),
+        Value::List(items) => Ok(ArgumentValue::List(
+            items
+                .iter()
+                .map(|item| parse_argument_value(name, item, variables))
+                .collect::<Result<_, _>>()?,
+        )),
+        Value::Object(fields) => Ok(ArgumentValue::Object(
+            fields
+                .iter()
+                .map(|(field_name, field)| Ok((field_name.to_string(), parse_argument_value(name, field, variables)?)))
+                .collect::<Result<_, CompileError>>()?,
+        )),
         Value::Variable(var_name) => {
             if let Some(var_value) = variables.get(var_name) {
-                match var_value {
-                    ConstValue::Number(_) | ConstValue::String(_) | ConstValue::Boolean(_) | ConstValue::Enum(_) => {
-                        parse_argument_value(name, &var_value.clone().into_value(), variables)
-                    }
-                    _ => {
-                        tracing::debug!(
-                            arg_name = name,
-                            "Converted argument value to a wildcard, since its type is not supported."
-                        );
-                        Ok(ArgumentValue::Wildcard)
-                    }
-                }
+                parse_const_value(name, var_value, variables)
             } else {
                 Err(CompileError::UnknownVariable(var_name.to_string(), name.to_string()))
             }
         }
-        _ => Err(CompileError::UnsupportedArgument(name.to_string(), value.to_string())),
+        Value::Binary(_) => Err(CompileError::UnsupportedArgument(name.to_string(), value.to_string())),
+    }
+}
+
+/// Parses a constant value, e.g. the value of a variable, to an ArgumentValue. If the value is of an
+/// unsupported type, the resulting argument value will be a wildcard.
+fn parse_const_value(name: &str, value: &ConstValue, variables: &Variables) -> Result<ArgumentValue, CompileError> {
+    match value {
+        ConstValue::Binary(_) => {
+            tracing::debug!(
+                arg_name = name,
+                "Converted argument value to a wildcard, since its type is not supported."
+            );
+            Ok(ArgumentValue::Wildcard)
+        }
+        _ => parse_argument_value(name, &value.clone().into_value(), variables),
     }
 }
 
@@ -156,18 +635,19 @@ impl CompileError {
 
 #[cfg(test)]
 mod tests {
-    use async_graphql_value::Name;
+    use rstest::rstest;
     use serde_json::json;
 
     use super::*;
+    use crate::resource_access::types::AccessKind;
 
     #[test]
     fn single_query() {
         use async_graphql_parser::parse_query;
 
         let document = parse_query("{ foo { bar baz } apiVersion }").expect("parse failed");
-        let access_requests =
-            from_document(&document, &Variables::default()).expect("failed compiling access requests");
+        let access_requests = from_document(&document, &SchemaInfo::default(), &Variables::default())
+            .expect("failed compiling access requests");
         assert_eq!(access_requests.len(), 1);
 
         let request = access_requests.first().unwrap();
@@ -189,7 +669,8 @@ mod tests {
             v.insert(Name::new("id"), ConstValue::String("foo".to_string()));
             v
         };
-        let access_requests = from_document(&document, &variables).expect("failed compiling access requests");
+        let access_requests =
+            from_document(&document, &SchemaInfo::default(), &variables).expect("failed compiling access requests");
         assert_eq!(access_requests.len(), 1);
 
         let request = access_requests.first().unwrap();
@@ -212,13 +693,14 @@ mod tests {
                 "foo": "bar",
             }
         }));
-        let access_requests = from_document(&document, &variables).expect("failed compiling access requests");
+        let access_requests =
+            from_document(&document, &SchemaInfo::default(), &variables).expect("failed compiling access requests");
         assert_eq!(access_requests.len(), 1);
 
         let request = access_requests.first().unwrap();
         assert_eq!(request.kind, AccessKind::Query);
 
-        let expected_paths = ["createAccount(params: *)::id"];
+        let expected_paths = ["createAccount(params: {foo: \"bar\"})::id"];
         for (path, expected_path) in std::iter::zip(&request.paths, expected_paths) {
             assert_eq!(path.to_string(), expected_path.to_string());
         }
@@ -229,8 +711,8 @@ mod tests {
         use async_graphql_parser::parse_query;
 
         let document = parse_query("{ foo(bar: BAZ) { doo } }").expect("parse failed");
-        let access_requests =
-            from_document(&document, &Variables::default()).expect("failed compiling access requests");
+        let access_requests = from_document(&document, &SchemaInfo::default(), &Variables::default())
+            .expect("failed compiling access requests");
         assert_eq!(access_requests.len(), 1);
 
         let request = access_requests.first().unwrap();
@@ -250,7 +732,8 @@ mod tests {
         let variables = Variables::from_json(json!({
             "b": "var"
         }));
-        let access_requests = from_document(&document, &variables).expect("failed compiling access requests");
+        let access_requests =
+            from_document(&document, &SchemaInfo::default(), &variables).expect("failed compiling access requests");
         assert_eq!(access_requests.len(), 1);
 
         let request = access_requests.first().unwrap();
@@ -261,4 +744,340 @@ mod tests {
             assert_eq!(path.to_string(), expected_path.to_string());
         }
     }
+
+    #[test]
+    fn subscription() {
+        use async_graphql_parser::parse_query;
+
+        let document = parse_query("subscription { accountStateChanged { id } }").expect("parse failed");
+        let access_requests = from_document(&document, &SchemaInfo::default(), &Variables::default())
+            .expect("failed compiling access requests");
+        assert_eq!(access_requests.len(), 1);
+
+        let request = access_requests.first().unwrap();
+        assert_eq!(request.kind, AccessKind::Subscription);
+        assert_eq!(request.paths[0].to_string(), "accountStateChanged::id");
+    }
+
+    #[test]
+    fn prune_query() {
+        use async_graphql_parser::parse_query;
+
+        use crate::resource_access::string_interop::compiler::from_string;
+
+        let mut document = parse_query("{ a { b c } d: e(id: 1) }").expect("parse failed");
+        let granted = from_string("a::b").unwrap();
+
+        let pruned = prune_document(
+            &mut document,
+            &SchemaInfo::default(),
+            &Variables::default(),
+            None,
+            &granted,
+        )
+        .expect("prune failed");
+        let pruned_paths: Vec<Vec<String>> = pruned.into_iter().map(|f| f.path).collect();
+        assert_eq!(
+            pruned_paths,
+            vec![vec!["a".to_owned(), "c".to_owned()], vec!["d".to_owned()]]
+        );
+
+        let access_requests =
+            from_document(&document, &SchemaInfo::default(), &Variables::default()).expect("compile failed");
+        let request = access_requests.first().unwrap();
+        let rendered: Vec<String> = request.paths.iter().map(|p| p.to_string()).collect();
+        assert_eq!(rendered, vec!["a::b"]);
+    }
+
+    #[test]
+    fn multiple_operations() {
+        use async_graphql_parser::parse_query;
+
+        let document = parse_query("query B { b } mutation A { a(id: 1) } query C { c { d } }").expect("parse failed");
+        let access_requests = from_document(&document, &SchemaInfo::default(), &Variables::default())
+            .expect("failed compiling access requests");
+
+        let rendered: Vec<(AccessKind, String)> = access_requests
+            .iter()
+            .map(|r| (r.kind, r.paths.iter().map(|p| p.to_string()).collect()))
+            .collect();
+        assert_eq!(
+            rendered,
+            vec![
+                (AccessKind::Mutation, "a(id: 1)".to_owned()),
+                (AccessKind::Query, "b".to_owned()),
+                (AccessKind::Query, "c::d".to_owned()),
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case("{ a }", None, Ok("a"))]
+    #[case("{ a }", Some("A"), Err("Unknown operation named A."))]
+    #[case("query A { a }", None, Ok("a"))]
+    #[case("query A { a } query B { b }", Some("B"), Ok("b"))]
+    #[case("query A { a } query B { b }", Some("C"), Err("Unknown operation named C."))]
+    #[case(
+        "query A { a } query B { b }",
+        None,
+        Err("Operation name required for a document with multiple operations.")
+    )]
+    fn operation_selection(
+        #[case] query: &str,
+        #[case] operation_name: Option<&str>,
+        #[case] expected: Result<&str, &str>,
+    ) {
+        let document = async_graphql_parser::parse_query(query).expect("parse failed");
+
+        let compiled =
+            from_document_operation(&document, &SchemaInfo::default(), &Variables::default(), operation_name)
+                .map(|r| r.paths.iter().map(|p| p.to_string()).collect::<String>())
+                .map_err(|e| e.to_string());
+        assert_eq!(compiled, expected.map(str::to_owned).map_err(str::to_owned));
+    }
+
+    #[test]
+    fn prune_named_operation() {
+        use async_graphql_parser::parse_query;
+
+        use crate::resource_access::string_interop::compiler::from_string;
+
+        let mut document = parse_query("query A { a b } query B { a b }").expect("parse failed");
+        let granted = from_string("a").unwrap();
+
+        let pruned = prune_document(
+            &mut document,
+            &SchemaInfo::default(),
+            &Variables::default(),
+            Some("B"),
+            &granted,
+        )
+        .expect("prune failed");
+        assert_eq!(pruned.len(), 1);
+
+        let compiled = |name| {
+            from_document_operation(&document, &SchemaInfo::default(), &Variables::default(), Some(name))
+                .unwrap()
+                .paths
+                .iter()
+                .map(|p| p.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(compiled("A"), vec!["a", "b"]);
+        assert_eq!(compiled("B"), vec!["a"]);
+    }
+
+    #[rstest]
+    #[case("{ a { ... on A { b } ... on B { c } } }", &["a::{b, c}"])]
+    #[case("{ a { ...F } } fragment F on A { b { ...G } } fragment G on B { c }", &["a::b::c"])]
+    #[case("{ ... { a } b }", &["a", "b"])]
+    #[case("{ a @skip(if: true) b @skip(if: false) }", &["b"])]
+    #[case("{ a @include(if: $on) b @include(if: $off) }", &["a"])]
+    #[case("{ a { ...F @skip(if: $on) c } } fragment F on A { b }", &["a::c"])]
+    #[case("{ a @skip(if: $off) @include(if: $off) b }", &["b"])]
+    fn query_with_fragments_and_directives(#[case] query: &str, #[case] expected_paths: &[&str]) {
+        let document = async_graphql_parser::parse_query(query).expect("parse failed");
+        let variables = Variables::from_json(json!({ "on": true, "off": false }));
+
+        let access_requests =
+            from_document(&document, &SchemaInfo::default(), &variables).expect("failed compiling access requests");
+        let rendered: Vec<String> = access_requests[0].paths.iter().map(|p| p.to_string()).collect();
+        assert_eq!(rendered, expected_paths);
+    }
+
+    #[rstest]
+    #[case("{ a { ...F } } fragment F on A { b { ...F } }", "Fragment F spreads itself.")]
+    #[case("{ a { ...F } }", "Fragment F is unknown.")]
+    #[case("{ a @skip(if: $x) }", "Variable x referenced by argument if is unknown.")]
+    #[case("{ a @skip(if: \"yes\") }", "Condition of directive @skip must be a boolean.")]
+    fn invalid_fragments_and_directives(#[case] query: &str, #[case] expected: &str) {
+        let document = async_graphql_parser::parse_query(query).expect("parse failed");
+
+        let error = from_document(&document, &SchemaInfo::default(), &Variables::default()).unwrap_err();
+        assert_eq!(error.to_string(), expected);
+    }
+
+    #[test]
+    fn prune_fragment_spread() {
+        use async_graphql_parser::parse_query;
+
+        use crate::resource_access::string_interop::compiler::from_string;
+
+        let query =
+            "query A { a { ...F } } query B { a { ...F } b { ...G } } fragment F on A { x y } fragment G on B { z }";
+        let mut document = parse_query(query).expect("parse failed");
+        let granted = from_string("a::x").unwrap();
+
+        let pruned = prune_document(
+            &mut document,
+            &SchemaInfo::default(),
+            &Variables::default(),
+            Some("B"),
+            &granted,
+        )
+        .expect("prune failed");
+        let pruned_paths: Vec<Vec<String>> = pruned.into_iter().map(|f| f.path).collect();
+        assert_eq!(
+            pruned_paths,
+            vec![vec!["a".to_owned(), "y".to_owned()], vec!["b".to_owned()]]
+        );
+
+        // F is still spread by A, while G is not spread anywhere anymore.
+        assert!(document.fragments.contains_key("F"));
+        assert!(!document.fragments.contains_key("G"));
+
+        let compiled = |name| {
+            from_document_operation(&document, &SchemaInfo::default(), &Variables::default(), Some(name))
+                .unwrap()
+                .paths
+                .iter()
+                .map(|p| p.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(compiled("A"), vec!["a::{x, y}"]);
+        assert_eq!(compiled("B"), vec!["a::x"]);
+    }
+
+    const SDL: &str = r#"
+        type Book {
+            title: String!
+            chapters(limit: Int! = 10): [Chapter!]!
+        }
+
+        interface Named {
+            books(limit: Int = 5): [Book!]!
+        }
+
+        type Chapter {
+            title: String!
+        }
+
+        type Query {
+            books(limit: Int = 20, filter: String): [Book!]!
+        }
+    "#;
+
+    #[rstest]
+    #[case("{ books { title } }", &["books(limit: 20)::title"])]
+    #[case("{ books(limit: 5, filter: \"x\") { title } }", &["books(filter: \"x\", limit: 5)::title"])]
+    #[case("{ first: books { t: title } }", &["books(limit: 20)::title"])]
+    #[case("{ books { chapters { title } } }", &["books(limit: 20)::chapters(limit: 10)::title"])]
+    #[case("{ books { ... on Book { chapters { title } } } }", &["books(limit: 20)::chapters(limit: 10)::title"])]
+    #[case("{ ... on Named { books { title } } }", &["books(limit: 5)::title"])]
+    #[case("{ unknown(limit: 1) { books } }", &["unknown(limit: 1)::books"])]
+    #[case("{ __typename books { __typename } }", &["books(limit: 20)"])]
+    #[case("{ __schema { types { name } } __type(name: \"Book\") { name } }", &["__introspection"])]
+    fn schema_aware_query(#[case] query: &str, #[case] expected_paths: &[&str]) {
+        let schema = SchemaInfo::from_sdl(SDL).unwrap();
+        let document = async_graphql_parser::parse_query(query).expect("parse failed");
+
+        let access_requests = from_document(&document, &schema, &Variables::default()).expect("compile failed");
+        let rendered: Vec<String> = access_requests[0].paths.iter().map(|p| p.to_string()).collect();
+        assert_eq!(rendered, expected_paths);
+    }
+
+    #[rstest]
+    #[case("books::title", "{ books { title } }", true)]
+    #[case("books::title", "{ books(limit: 20) { title } }", true)]
+    #[case("books::title", "{ books(limit: 5) { title } }", false)]
+    #[case("books::title", "{ books(filter: \"x\") { title } }", false)]
+    #[case("books(limit: 5)::title", "{ books { title } }", false)]
+    fn grants_without_default_arguments(#[case] granted: &str, #[case] query: &str, #[case] expected: bool) {
+        use crate::resource_access::string_interop::compiler::from_string;
+        use crate::resource_access::AccessPathSet;
+
+        let schema = SchemaInfo::from_sdl(SDL).unwrap();
+        let document = async_graphql_parser::parse_query(query).expect("parse failed");
+        let mut access_path_set = AccessPathSet::default();
+        from_string(granted)
+            .unwrap()
+            .into_paths()
+            .into_iter()
+            .for_each(|p| access_path_set.allow(p));
+
+        let access_requests = from_document(&document, &schema, &Variables::default()).expect("compile failed");
+        let mut desired = PathSet::default();
+        access_requests[0]
+            .paths
+            .iter()
+            .for_each(|p| desired.merge_path_node(p.clone()));
+        assert_eq!(access_path_set.permits(&desired), expected);
+    }
+
+    #[test]
+    fn prune_introspection() {
+        use async_graphql_parser::parse_query;
+
+        use crate::resource_access::string_interop::compiler::from_string;
+
+        let schema = SchemaInfo::from_sdl(SDL).unwrap();
+        let mut document =
+            parse_query("{ __typename __schema { types { name } } books { title } }").expect("parse failed");
+        let granted = from_string("books(limit: 20)::title").unwrap();
+
+        let pruned =
+            prune_document(&mut document, &schema, &Variables::default(), None, &granted).expect("prune failed");
+        let pruned_paths: Vec<Vec<String>> = pruned.into_iter().map(|f| f.path).collect();
+        assert_eq!(pruned_paths, vec![vec!["__schema".to_owned()]]);
+
+        let access_requests = from_document(&document, &schema, &Variables::default()).expect("compile failed");
+        let rendered: Vec<String> = access_requests[0].paths.iter().map(|p| p.to_string()).collect();
+        assert_eq!(rendered, vec!["books(limit: 20)::title"]);
+    }
+
+    #[test]
+    fn pruned_non_null_fields_null_their_nullable_parent() {
+        use async_graphql_parser::parse_query;
+
+        use crate::resource_access::string_interop::compiler::from_string;
+
+        let schema = SchemaInfo::from_sdl(
+            r#"
+            type Account {
+                id: ID!
+                email: String!
+                nickname: String
+            }
+
+            type Query {
+                account: Account
+                me: Account!
+            }
+            "#,
+        )
+        .unwrap();
+        let mut document = parse_query("{ account { id email nickname } me { id } }").expect("parse failed");
+        let granted = from_string("account::id").unwrap();
+
+        let pruned =
+            prune_document(&mut document, &schema, &Variables::default(), None, &granted).expect("prune failed");
+        let null_paths: Vec<Vec<String>> = pruned.into_iter().map(|f| f.null_path).collect();
+        assert_eq!(
+            null_paths,
+            vec![
+                vec!["account".to_owned()],
+                vec!["account".to_owned(), "nickname".to_owned()],
+                vec![],
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case("{ a(b: 1.5) }", "{}", "a(b: 1.5)")]
+    #[case("{ a(b: null) }", "{}", "a(b: null)")]
+    #[case("{ a(b: [1, $c]) }", r#"{"c": "x"}"#, r#"a(b: [1, "x"])"#)]
+    #[case("{ a(b: {c: $c, d: [E]}) }", r#"{"c": 2.5}"#, "a(b: {c: 2.5, d: [E]})")]
+    #[case(
+        "{ a(b: $b) }",
+        r#"{"b": {"c": [true, null], "d": {"e": 1}}}"#,
+        "a(b: {c: [true, null], d: {e: 1}})"
+    )]
+    fn query_with_input_values(#[case] query: &str, #[case] variables: &str, #[case] expected_path: &str) {
+        let document = async_graphql_parser::parse_query(query).expect("parse failed");
+        let variables = Variables::from_json(serde_json::from_str(variables).unwrap());
+
+        let access_requests =
+            from_document(&document, &SchemaInfo::default(), &variables).expect("failed compiling access requests");
+        assert_eq!(access_requests[0].paths[0].to_string(), expected_path);
+    }
 }
@@ -0,0 +1,224 @@
+use std::collections::{HashMap, HashSet};
+
+use async_graphql_parser::types::{
+    BaseType, FieldDefinition, InputValueDefinition, OperationType, Type, TypeKind, TypeSystemDefinition,
+};
+use async_graphql_parser::{parse_schema, Positioned};
+use async_graphql_value::ConstValue;
+
+/// The parts of a GraphQL schema which are relevant when compiling access requests and validating
+/// policy paths: the type returned by each field, its arguments, and the input types they take.
+///
+/// The default value, which knows no types at all, can be used when the schema is not available.
+#[derive(Debug, Clone, Default)]
+pub struct SchemaInfo {
+    query_type: Option<String>,
+    mutation_type: Option<String>,
+    subscription_type: Option<String>,
+    types: HashMap<String, HashMap<String, FieldInfo>>,
+    input_types: HashMap<String, InputTypeInfo>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct FieldInfo {
+    /// Name of the type returned by the field, without any list or non-null wrappers.
+    pub(crate) type_name: String,
+
+    /// Whether the field may resolve to `null`, i.e. its type is not wrapped in a non-null type.
+    pub(crate) nullable: bool,
+
+    /// Arguments of the field which have a default value.
+    pub(crate) default_arguments: Vec<(String, ConstValue)>,
+
+    /// Types of all the arguments of the field.
+    pub(crate) arguments: HashMap<String, Type>,
+}
+
+/// A type which can be used for arguments, other than the built-in scalars.
+#[derive(Debug, Clone)]
+pub(crate) enum InputTypeInfo {
+    Scalar,
+    Enum(HashSet<String>),
+    InputObject(HashMap<String, Type>),
+}
+
+
+impl SchemaInfo {
+    /// Builds the schema information from its SDL, e.g. as exported by `Schema::sdl`.
+    pub fn from_sdl(sdl: &str) -> Result<Self, async_graphql_parser::Error> {
+        let document = parse_schema(sdl)?;
+
+        let mut schema_info = SchemaInfo::default();
+        for definition in document.definitions {
+            match definition {
+                TypeSystemDefinition::Schema(schema) => {
+                    let schema = schema.node;
+                    if let Some(query) = schema.query {
+                        schema_info.query_type = Some(query.node.to_string());
+                    }
+                    if let Some(mutation) = schema.mutation {
+                        schema_info.mutation_type = Some(mutation.node.to_string());
+                    }
+                    if let Some(subscription) = schema.subscription {
+                        schema_info.subscription_type = Some(subscription.node.to_string());
+                    }
+                }
+                TypeSystemDefinition::Type(ty) => {
+                    let ty = ty.node;
+                    let fields = match ty.kind {
+                        TypeKind::Object(object) => object.fields,
+                        TypeKind::Interface(interface) => interface.fields,
+                        TypeKind::Scalar => {
+                            schema_info
+                                .input_types
+                                .insert(ty.name.node.to_string(), InputTypeInfo::Scalar);
+                            continue;
+                        }
+                        TypeKind::Enum(enum_type) => {
+                            let values = enum_type
+                                .values
+                                .into_iter()
+                                .map(|value| value.node.value.node.to_string())
+                                .collect();
+                            schema_info
+                                .input_types
+                                .insert(ty.name.node.to_string(), InputTypeInfo::Enum(values));
+                            continue;
+                        }
+                        TypeKind::InputObject(input_object) => {
+                            let fields = input_object.fields.into_iter().map(input_value_type).collect();
+                            schema_info
+                                .input_types
+                                .insert(ty.name.node.to_string(), InputTypeInfo::InputObject(fields));
+                            continue;
+                        }
+                        TypeKind::Union(_) => continue,
+                    };
+
+                    schema_info
+                        .types
+                        .entry(ty.name.node.to_string())
+                        .or_default()
+                        .extend(fields.into_iter().map(field_info));
+                }
+                TypeSystemDefinition::Directive(_) => {}
+            }
+        }
+
+        Ok(schema_info)
+    }
+
+    /// Name of the root type of the given operation type.
+    pub(crate) fn root_type(&self, operation_type: OperationType) -> &str {
+        match operation_type {
+            OperationType::Query => self.query_type.as_deref().unwrap_or("Query"),
+            OperationType::Mutation => self.mutation_type.as_deref().unwrap_or("Mutation"),
+            OperationType::Subscription => self.subscription_type.as_deref().unwrap_or("Subscription"),
+        }
+    }
+
+    /// Looks up a field of the given type, if both are known.
+    pub(crate) fn field(&self, type_name: Option<&str>, field_name: &str) -> Option<&FieldInfo> {
+        self.types.get(type_name?)?.get(field_name)
+    }
+
+    /// Looks up an input type which is not a built-in scalar.
+    pub(crate) fn input_type(&self, type_name: &str) -> Option<&InputTypeInfo> {
+        self.input_types.get(type_name)
+    }
+}
+
+fn field_info(field: Positioned<FieldDefinition>) -> (String, FieldInfo) {
+    let field = field.node;
+    let default_arguments = field
+        .arguments
+        .iter()
+        .filter_map(|arg| {
+            let arg = &arg.node;
+            arg.default_value
+                .as_ref()
+                .map(|value| (arg.name.node.to_string(), value.node.clone()))
+        })
+        .collect();
+    let arguments = field.arguments.into_iter().map(input_value_type).collect();
+
+    (
+        field.name.node.to_string(),
+        FieldInfo {
+            type_name: named_type(&field.ty.node).to_owned(),
+            nullable: field.ty.node.nullable,
+            default_arguments,
+            arguments,
+        },
+    )
+}
+
+fn input_value_type(input_value: Positioned<InputValueDefinition>) -> (String, Type) {
+    let input_value = input_value.node;
+    (input_value.name.node.to_string(), input_value.ty.node)
+}
+
+fn named_type(ty: &Type) -> &str {
+    match &ty.base {
+        BaseType::Named(name) => name.as_str(),
+        BaseType::List(ty) => named_type(ty),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SDL: &str = r#"
+        type Book {
+            title: String!
+            chapters(limit: Int! = 10, order: Order = ASC): [Chapter!]!
+        }
+
+        type Root {
+            books(filter: String): [Book!]!
+        }
+
+        type Events {
+            bookAdded: Book!
+        }
+
+        schema {
+            query: Root
+            subscription: Events
+        }
+    "#;
+
+    #[test]
+    fn from_sdl() {
+        let schema_info = SchemaInfo::from_sdl(SDL).unwrap();
+
+        assert_eq!(schema_info.root_type(OperationType::Query), "Root");
+        assert_eq!(schema_info.root_type(OperationType::Mutation), "Mutation");
+        assert_eq!(schema_info.root_type(OperationType::Subscription), "Events");
+
+        let books = schema_info.field(Some("Root"), "books").unwrap();
+        assert_eq!(books.type_name, "Book");
+        assert!(!books.nullable);
+        assert!(books.default_arguments.is_empty());
+
+        let chapters = schema_info.field(Some("Book"), "chapters").unwrap();
+        assert_eq!(chapters.type_name, "Chapter");
+        assert_eq!(
+            chapters.default_arguments,
+            vec![
+                ("limit".to_owned(), ConstValue::Number(10.into())),
+                (
+                    "order".to_owned(),
+                    ConstValue::Enum(async_graphql_value::Name::new("ASC"))
+                ),
+            ]
+        );
+        assert_eq!(chapters.arguments["limit"].to_string(), "Int!");
+        assert_eq!(chapters.arguments["order"].to_string(), "Order");
+
+        assert!(schema_info.field(Some("Book"), "author").is_none());
+        assert!(schema_info.field(None, "books").is_none());
+    }
+}
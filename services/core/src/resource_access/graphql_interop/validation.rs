@@ -0,0 +1,224 @@
+use async_graphql_parser::types::{BaseType, Type};
+
+use super::schema::{InputTypeInfo, SchemaInfo};
+use crate::resource_access::types::{ArgumentValue, PathNode, Segment};
+use crate::resource_access::AccessKind;
+
+/// Reasons for which a policy path does not fit a GraphQL schema.
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum SchemaMismatch {
+    #[error("Type {0} has no field {1}.")]
+    UnknownField(String, String),
+
+    #[error("Field {0} has no argument {1}.")]
+    UnknownArgument(String, String),
+
+    #[error("Argument {1} of field {0} does not accept the given value.")]
+    InvalidArgumentValue(String, String),
+
+    #[error("Field {0} is missing required argument {1}.")]
+    MissingArgument(String, String),
+}
+
+
+/// Checks that a path of a policy statement of the given kind only refers to fields and arguments
+/// which exist under the matching root type, and that its argument values fit the argument types.
+///
+/// # Notes
+///
+/// Wildcards and policy variables fit any argument type. Fields starting with `__` are not checked,
+/// since they are not part of the schema, e.g. the debug permission path.
+pub fn validate_path(schema: &SchemaInfo, kind: AccessKind, path: &PathNode) -> Result<(), SchemaMismatch> {
+    validate_node(schema, schema.root_type(kind.into()), path)
+}
+
+fn validate_node(schema: &SchemaInfo, type_name: &str, node: &PathNode) -> Result<(), SchemaMismatch> {
+    let Segment::Named(field_name, arguments) = &node.segment else { return Ok(()) };
+    if field_name.starts_with("__") {
+        return Ok(());
+    }
+
+    let field = schema
+        .field(Some(type_name), field_name)
+        .ok_or_else(|| SchemaMismatch::UnknownField(type_name.to_owned(), field_name.clone()))?;
+    for (argument_name, argument) in arguments {
+        let argument_type = field
+            .arguments
+            .get(argument_name)
+            .ok_or_else(|| SchemaMismatch::UnknownArgument(field_name.clone(), argument_name.clone()))?;
+        if !accepts(schema, argument_type, &argument.value) {
+            return Err(SchemaMismatch::InvalidArgumentValue(
+                field_name.clone(),
+                argument_name.clone(),
+            ));
+        }
+    }
+    // Requests always give required arguments, which a path without them would never match.
+    let missing_argument = field.arguments.iter().find(|(argument_name, argument_type)| {
+        !argument_type.nullable
+            && !arguments.contains_key(*argument_name)
+            && !field.default_arguments.iter().any(|(name, _)| name == *argument_name)
+    });
+    if let Some((argument_name, _)) = missing_argument {
+        return Err(SchemaMismatch::MissingArgument(field_name.clone(), argument_name.clone()));
+    }
+
+    node.fields
+        .values()
+        .try_for_each(|child| validate_node(schema, &field.type_name, child))
+}
+
+fn accepts(schema: &SchemaInfo, ty: &Type, value: &ArgumentValue) -> bool {
+    match (value, &ty.base) {
+        (ArgumentValue::Wildcard | ArgumentValue::Variable(_), _) => true,
+        (ArgumentValue::Null, _) => ty.nullable,
+        (ArgumentValue::OneOf(values), _) => values.iter().all(|value| accepts(schema, ty, value)),
+        (ArgumentValue::List(items), BaseType::List(item_type)) => {
+            items.iter().all(|item| accepts(schema, item_type, item))
+        }
+        (ArgumentValue::List(_), BaseType::Named(_)) => false,
+        // A single value gets coerced into a list of one.
+        (_, BaseType::List(item_type)) => accepts(schema, item_type, value),
+        (_, BaseType::Named(type_name)) => accepts_named(schema, type_name.as_str(), value),
+    }
+}
+
+fn accepts_named(schema: &SchemaInfo, type_name: &str, value: &ArgumentValue) -> bool {
+    use ArgumentValue::*;
+
+    match (type_name, value) {
+        ("Int", IntegerLiteral(_) | Range(_)) => true,
+        ("Float", IntegerLiteral(_) | FloatLiteral(_) | Range(_)) => true,
+        ("String", StringLiteral(_) | Glob(_)) => true,
+        ("ID", StringLiteral(_) | IntegerLiteral(_) | Glob(_)) => true,
+        ("Boolean", BoolLiteral(_)) => true,
+        ("Int" | "Float" | "String" | "ID" | "Boolean", _) => false,
+        _ => match (schema.input_type(type_name), value) {
+            (Some(InputTypeInfo::Scalar), _) => true,
+            (Some(InputTypeInfo::Enum(values)), Enum(value)) => values.contains(value),
+            (Some(InputTypeInfo::InputObject(fields)), Object(entries)) => entries.iter().all(|(name, value)| {
+                fields
+                    .get(name)
+                    .map_or(false, |field_type| accepts(schema, field_type, value))
+            }),
+            _ => false,
+        },
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+    use crate::resource_access::string_interop::compiler::from_string;
+
+    const SDL: &str = r#"
+        scalar DateTime
+
+        enum Order {
+            ASC
+            DESC
+        }
+
+        input AccountFilter {
+            email: String
+            createdAfter: DateTime
+        }
+
+        type Account {
+            id: ID!
+            email: String!
+            grades(limit: Int! = 10, order: Order): [Float!]!
+        }
+
+        type Query {
+            account(id: ID!): Account
+            accounts(filter: AccountFilter, ids: [ID!], pageSize: Int): [Account!]!
+        }
+
+        type Mutation {
+            updateAccount(id: ID!, email: String): Account!
+        }
+
+        type Subscription {
+            accountStateChanged(id: ID!): Account!
+        }
+    "#;
+
+    fn validate(kind: AccessKind, path: &str) -> Result<(), SchemaMismatch> {
+        let schema = SchemaInfo::from_sdl(SDL).unwrap();
+        from_string(path)
+            .unwrap()
+            .into_paths()
+            .iter()
+            .try_for_each(|path| validate_path(&schema, kind, path))
+    }
+
+    #[rstest]
+    #[case(AccessKind::Query, "*")]
+    #[case(
+        AccessKind::Query,
+        "account(id: ${self.accountId})::{email, grades(limit: <=50, order: DESC)}"
+    )]
+    #[case(
+        AccessKind::Query,
+        "accounts(filter: {email: ~\"*@uni.edu\", createdAfter: \"2022\"})::id"
+    )]
+    #[case(AccessKind::Query, "accounts(ids: [\"a\", 1], pageSize: in [10, 20])")]
+    #[case(AccessKind::Query, "accounts(ids: \"a\", filter: null)")]
+    #[case(AccessKind::Query, "account(id: *)::grades(order: ASC)")]
+    #[case(AccessKind::Query, "__debug::authorization")]
+    #[case(AccessKind::Mutation, "updateAccount(id: *, email: *)::email")]
+    #[case(AccessKind::Subscription, "accountStateChanged(id: ${self.accountId})::email")]
+    fn valid_paths(#[case] kind: AccessKind, #[case] path: &str) {
+        assert_eq!(validate(kind, path), Ok(()));
+    }
+
+    #[rstest]
+    #[case(AccessKind::Query, "acounts::*", "Type Query has no field acounts.")]
+    #[case(AccessKind::Mutation, "account", "Type Mutation has no field account.")]
+    #[case(AccessKind::Subscription, "account", "Type Subscription has no field account.")]
+    #[case(AccessKind::Query, "account(id: *)::email::length", "Type String has no field length.")]
+    #[case(AccessKind::Query, "account(email: *)", "Field account has no argument email.")]
+    #[case(AccessKind::Query, "account::*", "Field account is missing required argument id.")]
+    #[case(
+        AccessKind::Mutation,
+        "updateAccount(email: *)",
+        "Field updateAccount is missing required argument id."
+    )]
+    #[case(
+        AccessKind::Query,
+        "account(id: null)",
+        "Argument id of field account does not accept the given value."
+    )]
+    #[case(
+        AccessKind::Query,
+        "account(id: true)",
+        "Argument id of field account does not accept the given value."
+    )]
+    #[case(
+        AccessKind::Query,
+        "account(id: *)::grades(order: UP)",
+        "Argument order of field grades does not accept the given value."
+    )]
+    #[case(
+        AccessKind::Query,
+        "account(id: *)::grades(limit: \"10\")",
+        "Argument limit of field grades does not accept the given value."
+    )]
+    #[case(
+        AccessKind::Query,
+        "accounts(pageSize: [1])",
+        "Argument pageSize of field accounts does not accept the given value."
+    )]
+    #[case(
+        AccessKind::Query,
+        "accounts(filter: {name: *})",
+        "Argument filter of field accounts does not accept the given value."
+    )]
+    fn invalid_paths(#[case] kind: AccessKind, #[case] path: &str, #[case] expected: &str) {
+        assert_eq!(validate(kind, path).unwrap_err().to_string(), expected);
+    }
+}
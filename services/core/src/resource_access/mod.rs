@@ -1,9 +1,14 @@
+pub mod diff;
+pub mod explain;
 pub mod graphql_interop;
+pub mod normalize;
+pub mod predicate;
 pub mod serde;
 pub mod string_interop;
 pub mod types;
+pub mod variables;
 
-pub use types::{AccessKind, AccessRequest, PolicyStatement};
+pub use types::{AccessKind, AccessPathSet, AccessRequest, Effect, PolicyStatement};
 
 /// Parse resource path sets from strings.
 impl TryFrom<&str> for types::PathSet {
@@ -0,0 +1,105 @@
+use super::types::{Effect, PathSet, Superset};
+
+
+/// Merges the paths of the statements with the given effect the way they are merged when
+/// authorizing, which yields their minimal equivalent form.
+///
+/// Denied paths get their leaves turned into any-matches first, since a denied leaf revokes the
+/// whole subtree under it.
+pub fn merge_paths(effect: Effect, paths: impl IntoIterator<Item = PathSet>) -> PathSet {
+    let mut merged = PathSet::default();
+    for path_set in paths {
+        merged.merge_path_set(effective_paths(effect, path_set));
+    }
+
+    merged
+}
+
+/// Finds the paths, out of the paths of the statements with the given effect, which are subsumed
+/// by the union of the other ones and can be dropped without changing what the statements match.
+///
+/// # Notes
+///
+/// Paths are checked from last to first, each against the paths which are not redundant so far.
+/// Out of a set of equivalent paths, the first one is kept.
+///
+/// # Returns
+///
+/// Returns the indices of the redundant paths, in ascending order.
+pub fn redundant_paths(effect: Effect, paths: &[PathSet]) -> Vec<usize> {
+    let paths: Vec<PathSet> = paths
+        .iter()
+        .map(|path_set| effective_paths(effect, path_set.clone()))
+        .collect();
+    let mut redundant = vec![false; paths.len()];
+
+    for idx in (0..paths.len()).rev() {
+        let mut others = PathSet::default();
+        paths
+            .iter()
+            .enumerate()
+            .filter(|(other_idx, _)| *other_idx != idx && !redundant[*other_idx])
+            .for_each(|(_, other)| others.merge_path_set(other.clone()));
+
+        redundant[idx] = others.is_superset_of(&paths[idx]);
+    }
+
+    redundant
+        .into_iter()
+        .enumerate()
+        .filter(|(_, is_redundant)| *is_redundant)
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+fn effective_paths(effect: Effect, path_set: PathSet) -> PathSet {
+    match effect {
+        Effect::Allow => path_set,
+        Effect::Deny => {
+            let mut closed = PathSet::default();
+            for mut path in path_set.into_paths() {
+                path.close_leaves();
+                closed.merge_path_node(path);
+            }
+
+            closed
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+    use crate::resource_access::string_interop::compiler::from_string;
+
+    fn path_sets(paths: &[&str]) -> Vec<PathSet> {
+        paths.iter().map(|path| from_string(path).unwrap()).collect()
+    }
+
+    #[rstest]
+    #[case(Effect::Allow, &["accounts::id", "accounts::*", "accounts::{id, email}"], "accounts::*")]
+    #[case(Effect::Allow, &["accounts::id", "accounts::email"], "accounts::{email, id}")]
+    #[case(Effect::Allow, &["account(id: *)::id", "accounts"], "account(id: *)::id, accounts")]
+    #[case(Effect::Deny, &["accounts::password", "accounts"], "accounts::*")]
+    fn merge(#[case] effect: Effect, #[case] paths: &[&str], #[case] expected: &str) {
+        let merged = merge_paths(effect, path_sets(paths));
+        let rendered: Vec<String> = merged.paths().into_iter().map(ToString::to_string).collect();
+
+        assert_eq!(rendered.join(", "), expected);
+    }
+
+    #[rstest]
+    #[case(Effect::Allow, &["accounts::id", "accounts::*", "accounts::{id, email}"], vec![0, 2])]
+    #[case(Effect::Allow, &["accounts::{id, email}", "accounts::id", "accounts::email"], vec![1, 2])]
+    #[case(Effect::Allow, &["accounts::id", "accounts::id"], vec![1])]
+    #[case(Effect::Allow, &["accounts::id", "account::id"], vec![])]
+    #[case(Effect::Allow, &["account(id: *)::id", "account(id: \"a\")::id"], vec![1])]
+    #[case(Effect::Deny, &["accounts::password", "accounts"], vec![0])]
+    #[case(Effect::Deny, &["accounts", "accounts::password"], vec![1])]
+    fn redundant(#[case] effect: Effect, #[case] paths: &[&str], #[case] expected: Vec<usize>) {
+        assert_eq!(redundant_paths(effect, &path_sets(paths)), expected);
+    }
+}
@@ -0,0 +1,331 @@
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use super::types::{ArgumentValue, Float};
+
+/// A numeric bound of a predicate.
+#[derive(Debug, PartialOrd, Ord, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Number {
+    Integer(i64),
+    Float(Float),
+}
+
+/// A range of numbers, such as `0..=50` or `<=100`.
+///
+/// Only ranges which can be written in the string syntax can be constructed.
+#[derive(Debug, PartialOrd, Ord, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct NumberRange {
+    lower: RangeBound,
+    upper: RangeBound,
+}
+
+#[derive(Debug, PartialOrd, Ord, PartialEq, Eq, Clone, Copy, Hash)]
+enum RangeBound {
+    Unbounded,
+    Included(Number),
+    Excluded(Number),
+}
+
+
+impl Number {
+    /// Reads a numeric literal, if the value is one.
+    pub fn from_value(value: &ArgumentValue) -> Option<Self> {
+        match value {
+            ArgumentValue::IntegerLiteral(i) => Some(Number::Integer(*i)),
+            ArgumentValue::FloatLiteral(f) => Some(Number::Float(*f)),
+            _ => None,
+        }
+    }
+
+    /// Compares numbers by value, regardless of whether they are integers or floats.
+    fn cmp_value(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Number::Integer(l), Number::Integer(r)) => l.cmp(r),
+            _ => self.as_f64().total_cmp(&other.as_f64()),
+        }
+    }
+
+    fn as_f64(&self) -> f64 {
+        match self {
+            Number::Integer(i) => *i as f64,
+            Number::Float(f) => f.0,
+        }
+    }
+}
+
+impl NumberRange {
+    /// `start..end`, or `start..=end` if inclusive.
+    pub fn between(start: Number, end: Number, inclusive: bool) -> Self {
+        NumberRange {
+            lower: RangeBound::Included(start),
+            upper: if inclusive {
+                RangeBound::Included(end)
+            } else {
+                RangeBound::Excluded(end)
+            },
+        }
+    }
+
+    /// `<=end`, or `<end` if not inclusive.
+    pub fn below(end: Number, inclusive: bool) -> Self {
+        NumberRange {
+            lower: RangeBound::Unbounded,
+            upper: if inclusive {
+                RangeBound::Included(end)
+            } else {
+                RangeBound::Excluded(end)
+            },
+        }
+    }
+
+    /// `>=start`, or `>start` if not inclusive.
+    pub fn above(start: Number, inclusive: bool) -> Self {
+        NumberRange {
+            lower: if inclusive {
+                RangeBound::Included(start)
+            } else {
+                RangeBound::Excluded(start)
+            },
+            upper: RangeBound::Unbounded,
+        }
+    }
+
+    pub fn contains(&self, number: &Number) -> bool {
+        let above_lower = match &self.lower {
+            RangeBound::Unbounded => true,
+            RangeBound::Included(lower) => number.cmp_value(lower).is_ge(),
+            RangeBound::Excluded(lower) => number.cmp_value(lower).is_gt(),
+        };
+        let below_upper = match &self.upper {
+            RangeBound::Unbounded => true,
+            RangeBound::Included(upper) => number.cmp_value(upper).is_le(),
+            RangeBound::Excluded(upper) => number.cmp_value(upper).is_lt(),
+        };
+
+        above_lower && below_upper
+    }
+
+    /// Whether every number of the other range is part of this range.
+    pub fn covers(&self, other: &NumberRange) -> bool {
+        use RangeBound::*;
+
+        let lower_covered = match (&self.lower, &other.lower) {
+            (Unbounded, _) => true,
+            (_, Unbounded) => false,
+            (Excluded(l), Included(r)) => l.cmp_value(r).is_lt(),
+            (Included(l) | Excluded(l), Included(r) | Excluded(r)) => l.cmp_value(r).is_le(),
+        };
+        let upper_covered = match (&self.upper, &other.upper) {
+            (Unbounded, _) => true,
+            (_, Unbounded) => false,
+            (Excluded(l), Included(r)) => l.cmp_value(r).is_gt(),
+            (Included(l) | Excluded(l), Included(r) | Excluded(r)) => l.cmp_value(r).is_ge(),
+        };
+
+        lower_covered && upper_covered
+    }
+
+    /// Whether both ranges have at least one number in common.
+    ///
+    /// Integer ranges are considered continuous, e.g. `<1` and `>0` do overlap.
+    pub fn overlaps(&self, other: &NumberRange) -> bool {
+        fn lower_below_upper(lower: &RangeBound, upper: &RangeBound) -> bool {
+            match (lower, upper) {
+                (RangeBound::Unbounded, _) | (_, RangeBound::Unbounded) => true,
+                (RangeBound::Included(l), RangeBound::Included(u)) => l.cmp_value(u).is_le(),
+                (
+                    RangeBound::Included(l) | RangeBound::Excluded(l),
+                    RangeBound::Included(u) | RangeBound::Excluded(u),
+                ) => l.cmp_value(u).is_lt(),
+            }
+        }
+
+        lower_below_upper(&self.lower, &other.upper) && lower_below_upper(&other.lower, &self.upper)
+    }
+}
+
+/// Matches a string against a glob pattern, where `*` matches any sequence of characters and `?`
+/// matches a single character.
+pub fn glob_matches(pattern: &str, text: &str) -> bool {
+    matches(
+        &pattern.chars().collect::<Vec<_>>(),
+        &text.chars().collect::<Vec<_>>(),
+        false,
+    )
+}
+
+/// Whether every string matched by the other pattern is also matched by the pattern.
+///
+/// The check is sound but not complete: it may fail to see that a pattern covers another one, e.g.
+/// `*?` covers `?*`, which is not detected.
+pub fn glob_covers(pattern: &str, other: &str) -> bool {
+    matches(
+        &pattern.chars().collect::<Vec<_>>(),
+        &other.chars().collect::<Vec<_>>(),
+        true,
+    )
+}
+
+/// Matches the text against the pattern. If the text is itself a pattern, its wildcards can only be
+/// matched by wildcards which are at least as broad.
+///
+/// Runs in O(n·m): when a character does not match, only the most recent `*` is retried, consuming
+/// one more character, as any earlier `*` could only cover what the latest one already does.
+fn matches(pattern: &[char], text: &[char], text_is_pattern: bool) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` in the pattern, and of the text it was last tried against.
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(c) if char_matches(*c, text[t], text_is_pattern) => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, star_t)) => {
+                    backtrack = Some((star, star_t + 1));
+                    p = star + 1;
+                    t = star_t + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Whether a pattern character other than `*` matches a single character of the text.
+fn char_matches(pattern: char, text: char, text_is_pattern: bool) -> bool {
+    match text {
+        '*' if text_is_pattern => false,
+        '?' if text_is_pattern => pattern == '?',
+        _ => pattern == '?' || pattern == text,
+    }
+}
+
+impl Display for Number {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Number::Integer(i) => write!(f, "{}", i),
+            Number::Float(float) => write!(f, "{}", float),
+        }
+    }
+}
+
+impl Display for NumberRange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        use RangeBound::*;
+
+        match (&self.lower, &self.upper) {
+            (Included(lower), Included(upper)) => write!(f, "{}..={}", lower, upper),
+            (Included(lower), Excluded(upper)) => write!(f, "{}..{}", lower, upper),
+            (Unbounded, Included(upper)) => write!(f, "<={}", upper),
+            (Unbounded, Excluded(upper)) => write!(f, "<{}", upper),
+            (Included(lower), Unbounded) => write!(f, ">={}", lower),
+            (Excluded(lower), Unbounded) => write!(f, ">{}", lower),
+            _ => unreachable!("range cannot be constructed"),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    fn int(i: i64) -> Number {
+        Number::Integer(i)
+    }
+
+    #[rstest]
+    #[case(NumberRange::between(int(0), int(50), true), int(50), true)]
+    #[case(NumberRange::between(int(0), int(50), false), int(50), false)]
+    #[case(NumberRange::between(int(0), int(50), false), Number::Float(Float(49.5)), true)]
+    #[case(NumberRange::below(int(100), true), int(-3), true)]
+    #[case(NumberRange::below(int(100), false), int(100), false)]
+    #[case(NumberRange::above(int(0), false), int(0), false)]
+    #[case(NumberRange::above(int(0), true), int(0), true)]
+    fn contains(#[case] range: NumberRange, #[case] number: Number, #[case] expected: bool) {
+        assert_eq!(range.contains(&number), expected);
+    }
+
+    #[rstest]
+    #[case(
+        NumberRange::between(int(0), int(50), true),
+        NumberRange::between(int(10), int(20), true),
+        true
+    )]
+    #[case(
+        NumberRange::between(int(0), int(50), true),
+        NumberRange::between(int(0), int(50), false),
+        true
+    )]
+    #[case(
+        NumberRange::between(int(0), int(50), false),
+        NumberRange::between(int(0), int(50), true),
+        false
+    )]
+    #[case(NumberRange::below(int(100), true), NumberRange::between(int(-5), int(100), true), true)]
+    #[case(NumberRange::below(int(100), true), NumberRange::above(int(5), true), false)]
+    #[case(NumberRange::above(int(0), true), NumberRange::above(int(0), false), true)]
+    #[case(NumberRange::above(int(0), false), NumberRange::above(int(0), true), false)]
+    #[case(NumberRange::above(int(0), false), NumberRange::between(int(1), int(2), true), true)]
+    fn covers(#[case] range: NumberRange, #[case] other: NumberRange, #[case] expected: bool) {
+        assert_eq!(range.covers(&other), expected);
+    }
+
+    #[rstest]
+    #[case(
+        NumberRange::between(int(0), int(50), true),
+        NumberRange::between(int(50), int(60), true),
+        true
+    )]
+    #[case(
+        NumberRange::between(int(0), int(50), false),
+        NumberRange::between(int(50), int(60), true),
+        false
+    )]
+    #[case(NumberRange::below(int(0), true), NumberRange::above(int(0), true), true)]
+    #[case(NumberRange::below(int(0), true), NumberRange::above(int(0), false), false)]
+    fn overlaps(#[case] range: NumberRange, #[case] other: NumberRange, #[case] expected: bool) {
+        assert_eq!(range.overlaps(&other), expected);
+        assert_eq!(other.overlaps(&range), expected);
+    }
+
+    #[rstest]
+    #[case("*@uni.edu", "jane@uni.edu", true)]
+    #[case("*@uni.edu", "jane@uni.edu.evil", false)]
+    #[case("admin-*", "admin-", true)]
+    #[case("a?c", "abc", true)]
+    #[case("a?c", "ac", false)]
+    #[case("a?c", "a*c", true)]
+    #[case("*", "", true)]
+    #[case("a*b*c", "aXbYbZc", true)]
+    #[case("*a*b", "aab", true)]
+    #[case("a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*b", &"a".repeat(1000), false)]
+    fn glob_matching(#[case] pattern: &str, #[case] text: &str, #[case] expected: bool) {
+        assert_eq!(glob_matches(pattern, text), expected);
+    }
+
+    #[rstest]
+    #[case("*uni.edu", "*@cs.uni.edu", true)]
+    #[case("*@uni.edu", "*@cs.uni.edu", false)]
+    #[case("*@cs.uni.edu", "*uni.edu", false)]
+    #[case("*", "a?*", true)]
+    #[case("a?c", "a*c", false)]
+    #[case("a*c", "a?c", true)]
+    #[case("a?c", "a?c", true)]
+    #[case("*a*b", "*a?*b", true)]
+    #[case("a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*b", &"a*".repeat(500), false)]
+    fn glob_covering(#[case] pattern: &str, #[case] other: &str, #[case] expected: bool) {
+        assert_eq!(glob_covers(pattern, other), expected);
+    }
+}
@@ -2,7 +2,8 @@ use async_graphql_value::value;
 use thiserror::Error;
 
 use super::{parse, types as parser_types, ParseError};
-use crate::resource_access::types::{AppendNodeError, Argument, ArgumentValue, PathSet, Segment};
+use crate::resource_access::predicate::{Number, NumberRange};
+use crate::resource_access::types::{AppendNodeError, Argument, ArgumentValue, Float, PathSet, Segment};
 
 #[derive(Error, Debug)]
 pub enum CompileError {
@@ -67,7 +68,7 @@ fn tr_singular_selection_set(
     mut path: Vec<Segment>,
     selection: parser_types::SingularSelectionSet<'_>,
 ) -> Result<(), CompileError> {
-    use parser_types::{Field, FieldArgValue, SingularSelectionSet};
+    use parser_types::{Field, SingularSelectionSet};
 
     match selection {
         SingularSelectionSet::Wildcard => {
@@ -85,13 +86,8 @@ fn tr_singular_selection_set(
                                 arg.name.to_owned(),
                                 Argument {
                                     name: arg.name.to_owned(),
-                                    value: match arg.value {
-                                        FieldArgValue::BoolLiteral(value) => ArgumentValue::BoolLiteral(value),
-                                        FieldArgValue::IntegerLiteral(value) => ArgumentValue::IntegerLiteral(value),
-                                        FieldArgValue::StringLiteral(value) => ArgumentValue::StringLiteral(value),
-                                        FieldArgValue::Enum(name) => ArgumentValue::Enum(name),
-                                        FieldArgValue::Wildcard => ArgumentValue::Wildcard,
-                                    },
+                                    value: tr_field_arg_value(arg.value),
+                                    is_default: false,
                                 },
                             )
                         })
@@ -110,8 +106,48 @@ fn tr_singular_selection_set(
     Ok(())
 }
 
+fn tr_field_arg_value(value: parser_types::FieldArgValue) -> ArgumentValue {
+    use parser_types::{FieldArgRange, FieldArgValue};
+
+    match value {
+        FieldArgValue::BoolLiteral(value) => ArgumentValue::BoolLiteral(value),
+        FieldArgValue::IntegerLiteral(value) => ArgumentValue::IntegerLiteral(value),
+        FieldArgValue::FloatLiteral(value) => ArgumentValue::FloatLiteral(Float(value)),
+        FieldArgValue::StringLiteral(value) => ArgumentValue::StringLiteral(value),
+        FieldArgValue::Enum(name) => ArgumentValue::Enum(name),
+        FieldArgValue::Null => ArgumentValue::Null,
+        FieldArgValue::List(items) => ArgumentValue::List(items.into_iter().map(tr_field_arg_value).collect()),
+        FieldArgValue::Object(fields) => ArgumentValue::Object(
+            fields
+                .into_iter()
+                .map(|(name, value)| (name, tr_field_arg_value(value)))
+                .collect(),
+        ),
+        FieldArgValue::Wildcard => ArgumentValue::Wildcard,
+        FieldArgValue::OneOf(items) => ArgumentValue::OneOf(items.into_iter().map(tr_field_arg_value).collect()),
+        FieldArgValue::Range(range) => ArgumentValue::Range(match range {
+            FieldArgRange::Between(start, end, inclusive) => {
+                NumberRange::between(tr_number(start), tr_number(end), inclusive)
+            }
+            FieldArgRange::Below(end, inclusive) => NumberRange::below(tr_number(end), inclusive),
+            FieldArgRange::Above(start, inclusive) => NumberRange::above(tr_number(start), inclusive),
+        }),
+        FieldArgValue::Glob(pattern) => ArgumentValue::Glob(pattern),
+        FieldArgValue::Variable(name) => ArgumentValue::Variable(name),
+    }
+}
+
+fn tr_number(number: parser_types::FieldArgNumber) -> Number {
+    match number {
+        parser_types::FieldArgNumber::Integer(value) => Number::Integer(value),
+        parser_types::FieldArgNumber::Float(value) => Number::Float(Float(value)),
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use rstest::rstest;
+
     use super::*;
 
     #[test]
@@ -163,4 +199,22 @@ mod tests {
         assert_eq!(second_arg.name.as_str(), "state");
         assert_eq!(second_arg.value, ArgumentValue::Enum("ACTIVE".to_owned()));
     }
+
+    #[rstest]
+    #[case("createAccount(params: {email: *, password: *, name: null})")]
+    #[case("updatePermissions(statements: [{kind: QUERY, paths: [*]}], ratio: 0.5)")]
+    #[case("a(b: [], c: {}, d: -1.5e-7)")]
+    #[case("course(id: in [\"a\", \"b\"])::grades(value: 0..=50, weight: 0.5..1)")]
+    #[case("accounts(pageSize: <=100, offset: >=0, min: >-1.5, max: <10)")]
+    #[case("account(email: ~\"*@uni.edu\")")]
+    #[case("account(id: ${self.accountId}, filter: {email: ${jwt.email}})")]
+    #[case(r#"course(name: "say \"hi\"", path: "C:\\tmp", note: "a\nb\tc\r\b\f")"#)]
+    #[case(r#"account(email: ~"*\"@uni.edu\\")"#)]
+    fn input_values_roundtrip(#[case] raw: &str) {
+        let path_set = from_string(raw).expect("parse failed");
+        let rendered = path_set.paths().first().expect("path_set empty").to_string();
+
+        let reparsed = from_string(&rendered).expect("reparse failed");
+        assert_eq!(reparsed.into_paths(), path_set.into_paths());
+    }
 }
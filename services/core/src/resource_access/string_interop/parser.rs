@@ -2,22 +2,42 @@ use nom::branch::alt;
 use nom::bytes::complete::{tag, take_while, take_while1};
 use nom::character::complete::{char, digit1, multispace0, one_of};
 use nom::character::{is_alphabetic, is_alphanumeric};
-use nom::combinator::{cut, eof, map, map_res, opt, recognize, value};
-use nom::multi::separated_list0;
-use nom::sequence::{pair, preceded, separated_pair, terminated, tuple};
+use nom::combinator::{cut, eof, map, map_res, not, opt, recognize, value};
+use nom::multi::{many0, separated_list0};
+use nom::sequence::{delimited, pair, preceded, separated_pair, terminated, tuple};
 use nom::IResult;
 
 use super::string_literal;
-use super::types::{Expression, Field, FieldArg, FieldArgValue, SelectionSet, SingularSelectionSet};
+use super::types::{
+    Expression, Field, FieldArg, FieldArgNumber, FieldArgRange, FieldArgValue, SelectionSet, SingularSelectionSet,
+};
+
+/// A keyword which is not the prefix of a longer identifier, e.g. `null` but not `nullable`.
+fn keyword<'a>(word: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, &'a str> {
+    terminated(tag(word), not(take_while1(|c| is_alphanumeric(c as u8) || c == '_')))
+}
 
 pub fn bool(input: &str) -> IResult<&str, bool> {
-    alt((value(true, tag("true")), value(false, tag("false"))))(input)
+    alt((value(true, keyword("true")), value(false, keyword("false"))))(input)
 }
 
 pub fn i64(input: &str) -> IResult<&str, i64> {
     map_res(recognize(pair(opt(one_of("+-")), digit1)), str::parse)(input)
 }
 
+pub fn f64(input: &str) -> IResult<&str, f64> {
+    let exponent = || recognize(tuple((one_of("eE"), opt(one_of("+-")), digit1)));
+
+    map_res(
+        recognize(tuple((
+            opt(one_of("+-")),
+            digit1,
+            alt((recognize(pair(pair(char('.'), digit1), opt(exponent()))), exponent())),
+        ))),
+        str::parse,
+    )(input)
+}
+
 pub fn identifier(input: &str) -> IResult<&str, &str> {
     recognize(pair(
         take_while1(|c| is_alphabetic(c as u8) || c == '_'),
@@ -29,14 +49,81 @@ pub fn field_arg_value(input: &str) -> IResult<&str, FieldArgValue> {
     use string_literal::string_literal;
 
     alt((
+        map(range, FieldArgValue::Range),
+        map(one_of_value, FieldArgValue::OneOf),
+        map(preceded(char('~'), string_literal), FieldArgValue::Glob),
+        map(variable, |s| FieldArgValue::Variable(s.to_owned())),
         map(bool, FieldArgValue::BoolLiteral),
+        map(keyword("null"), |_| FieldArgValue::Null),
+        map(f64, FieldArgValue::FloatLiteral),
         map(i64, FieldArgValue::IntegerLiteral),
         map(string_literal, FieldArgValue::StringLiteral),
         map(identifier, |s| FieldArgValue::Enum(s.to_owned())),
         map(tag("*"), |_| FieldArgValue::Wildcard),
+        map(list_value, FieldArgValue::List),
+        map(object_value, FieldArgValue::Object),
     ))(input)
 }
 
+/// A policy variable, either `$self.accountId` or `${self.accountId}`.
+pub fn variable(input: &str) -> IResult<&str, &str> {
+    let name = || recognize(pair(identifier, many0(pair(char('.'), identifier))));
+
+    preceded(char('$'), cut(alt((delimited(char('{'), name(), char('}')), name()))))(input)
+}
+
+pub fn number(input: &str) -> IResult<&str, FieldArgNumber> {
+    alt((map(f64, FieldArgNumber::Float), map(i64, FieldArgNumber::Integer)))(input)
+}
+
+pub fn range(input: &str) -> IResult<&str, FieldArgRange> {
+    alt((
+        map(
+            tuple((number, alt((tag("..="), tag(".."))), number)),
+            |(start, op, end)| FieldArgRange::Between(start, end, op == "..="),
+        ),
+        map(
+            pair(
+                alt((tag("<="), tag("<"), tag(">="), tag(">"))),
+                preceded(multispace0, number),
+            ),
+            |(op, bound)| match op {
+                "<=" => FieldArgRange::Below(bound, true),
+                "<" => FieldArgRange::Below(bound, false),
+                ">=" => FieldArgRange::Above(bound, true),
+                _ => FieldArgRange::Above(bound, false),
+            },
+        ),
+    ))(input)
+}
+
+pub fn one_of_value(input: &str) -> IResult<&str, Vec<FieldArgValue>> {
+    preceded(pair(tag("in"), multispace0), list_value)(input)
+}
+
+pub fn list_value(input: &str) -> IResult<&str, Vec<FieldArgValue>> {
+    preceded(
+        char('['),
+        cut(terminated(
+            separated_list0(preceded(multispace0, char(',')), preceded(multispace0, field_arg_value)),
+            preceded(multispace0, char(']')),
+        )),
+    )(input)
+}
+
+pub fn object_value(input: &str) -> IResult<&str, Vec<(String, FieldArgValue)>> {
+    preceded(
+        char('{'),
+        cut(terminated(
+            separated_list0(
+                preceded(multispace0, char(',')),
+                map(field_arg, |arg| (arg.name.to_owned(), arg.value)),
+            ),
+            preceded(multispace0, char('}')),
+        )),
+    )(input)
+}
+
 pub fn field_arg(input: &str) -> IResult<&str, FieldArg> {
     preceded(
         multispace0,
@@ -117,6 +204,65 @@ mod test {
     #[case("a(b: C)", FieldArgValue::Enum("C".to_string()))]
     #[case("a(b: FOO_BAR)", FieldArgValue::Enum("FOO_BAR".to_string()))]
     #[case("a(b: *)", FieldArgValue::Wildcard)]
+    #[case("a(b: 1.5)", FieldArgValue::FloatLiteral(1.5))]
+    #[case("a(b: -2e3)", FieldArgValue::FloatLiteral(-2000.0))]
+    #[case("a(b: 1.0E-1)", FieldArgValue::FloatLiteral(0.1))]
+    #[case("a(b: null)", FieldArgValue::Null)]
+    #[case("a(b: [])", FieldArgValue::List(vec![]))]
+    #[case(
+        "a(b: [1, *,\"c\" ])",
+        FieldArgValue::List(vec![
+            FieldArgValue::IntegerLiteral(1),
+            FieldArgValue::Wildcard,
+            FieldArgValue::StringLiteral("c".to_string()),
+        ])
+    )]
+    #[case("a(b: {})", FieldArgValue::Object(vec![]))]
+    #[case(
+        "a(b: {c: [D], e: {f: *} })",
+        FieldArgValue::Object(vec![
+            ("c".to_string(), FieldArgValue::List(vec![FieldArgValue::Enum("D".to_string())])),
+            (
+                "e".to_string(),
+                FieldArgValue::Object(vec![("f".to_string(), FieldArgValue::Wildcard)])
+            ),
+        ])
+    )]
+    #[case(
+        "a(b: in [\"x\", 1])",
+        FieldArgValue::OneOf(vec![FieldArgValue::StringLiteral("x".to_string()), FieldArgValue::IntegerLiteral(1)])
+    )]
+    #[case("a(b: inactive)", FieldArgValue::Enum("inactive".to_string()))]
+    #[case("a(b: nullable)", FieldArgValue::Enum("nullable".to_string()))]
+    #[case("a(b: null_value)", FieldArgValue::Enum("null_value".to_string()))]
+    #[case("a(b: trueish)", FieldArgValue::Enum("trueish".to_string()))]
+    #[case(
+        "a(b: 0..=50)",
+        FieldArgValue::Range(FieldArgRange::Between(FieldArgNumber::Integer(0), FieldArgNumber::Integer(50), true))
+    )]
+    #[case(
+        "a(b: -1.5..2)",
+        FieldArgValue::Range(FieldArgRange::Between(FieldArgNumber::Float(-1.5), FieldArgNumber::Integer(2), false))
+    )]
+    #[case(
+        "a(b: <=100)",
+        FieldArgValue::Range(FieldArgRange::Below(FieldArgNumber::Integer(100), true))
+    )]
+    #[case(
+        "a(b: < 100)",
+        FieldArgValue::Range(FieldArgRange::Below(FieldArgNumber::Integer(100), false))
+    )]
+    #[case(
+        "a(b: >=0.5)",
+        FieldArgValue::Range(FieldArgRange::Above(FieldArgNumber::Float(0.5), true))
+    )]
+    #[case(
+        "a(b: >0)",
+        FieldArgValue::Range(FieldArgRange::Above(FieldArgNumber::Integer(0), false))
+    )]
+    #[case("a(b: ~\"*@uni.edu\")", FieldArgValue::Glob("*@uni.edu".to_string()))]
+    #[case("a(b: $self.accountId)", FieldArgValue::Variable("self.accountId".to_string()))]
+    #[case("a(b: ${jwt.sub})", FieldArgValue::Variable("jwt.sub".to_string()))]
     fn single_argument(#[case] raw: &str, #[case] expected_field_arg_value: FieldArgValue) {
         let (_, expr) = path_set(raw).expect("parse should not fail");
         let Expression::SelectionSet(selection) = expr;
@@ -131,6 +277,22 @@ mod test {
         assert_eq!(first_arg.value, expected_field_arg_value);
     }
 
+    #[rstest]
+    #[case("a(b: [1, 2)")]
+    #[case("a(b: {c})")]
+    #[case("a(b: {c: 1,})")]
+    #[case("a(b: 1.)")]
+    #[case("a(b: in 1)")]
+    #[case("a(b: 1..)")]
+    #[case("a(b: <=x)")]
+    #[case("a(b: ~x)")]
+    #[case("a(b: $)")]
+    #[case("a(b: ${jwt.sub)")]
+    #[case("a(b: $jwt.)")]
+    fn invalid_argument(#[case] raw: &str) {
+        assert!(path_set(raw).is_err());
+    }
+
     #[test]
     fn multiple_arguments() {
         let raw = "a(b: 10, c: true)";
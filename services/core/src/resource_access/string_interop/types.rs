@@ -29,11 +29,35 @@ pub struct FieldArg<'a> {
     pub value: FieldArgValue,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq)]
 pub enum FieldArgValue {
     StringLiteral(String),
     IntegerLiteral(i64),
+    FloatLiteral(f64),
     BoolLiteral(bool),
     Enum(String),
+    Null,
+    List(Vec<FieldArgValue>),
+    Object(Vec<(String, FieldArgValue)>),
     Wildcard,
+    OneOf(Vec<FieldArgValue>),
+    Range(FieldArgRange),
+    Glob(String),
+    Variable(String),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum FieldArgRange {
+    /// `start..end`, or `start..=end` if inclusive.
+    Between(FieldArgNumber, FieldArgNumber, bool),
+    /// `<end`, or `<=end` if inclusive.
+    Below(FieldArgNumber, bool),
+    /// `>start`, or `>=start` if inclusive.
+    Above(FieldArgNumber, bool),
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum FieldArgNumber {
+    Integer(i64),
+    Float(f64),
 }
@@ -1,12 +1,14 @@
-use std::cmp::PartialEq;
+use std::cmp::{Ordering, PartialEq};
 use std::collections::btree_map::OccupiedError;
-use std::collections::BTreeMap;
-use std::convert::TryFrom;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::hash::{Hash, Hasher};
 
 use async_graphql_parser::types::OperationType;
 use serde::{Deserialize, Serialize};
 
+use super::predicate::{glob_covers, glob_matches, Number, NumberRange};
+
 #[derive(Debug, Clone)]
 pub struct AccessRequest {
     pub kind: AccessKind,
@@ -15,14 +17,26 @@ pub struct AccessRequest {
 
 #[derive(Debug, Serialize, Clone, PartialEq, Deserialize)]
 pub struct PolicyStatement {
+    #[serde(default)]
+    pub effect: Effect,
     pub kind: AccessKind,
     pub paths: Vec<PathNode>,
 }
 
+/// Whether a policy statement grants or revokes access to its paths.
+///
+/// Deny statements always take precedence over allow statements, regardless of their order.
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Copy)]
+pub enum Effect {
+    Allow,
+    Deny,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Serialize, Deserialize, Clone, Copy)]
 pub enum AccessKind {
     Query,
     Mutation,
+    Subscription,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -30,6 +44,13 @@ pub struct PathSet {
     pub(crate) paths: BTreeMap<Segment, PathNode>,
 }
 
+/// The paths an entity is allowed to access, together with the paths explicitly denied to it.
+#[derive(Debug, Clone, Default)]
+pub struct AccessPathSet {
+    pub allowed: PathSet,
+    pub denied: PathSet,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct PathNode {
     pub segment: Segment,
@@ -42,21 +63,48 @@ pub enum Segment {
     Any,
 }
 
-#[derive(Debug, PartialOrd, Ord, PartialEq, Eq, Hash, Clone)]
+/// Arguments compare by name and value only: whether the value is the default does not change what
+/// the argument selects.
+#[derive(Debug, Clone)]
 pub struct Argument {
     pub name: String,
     pub value: ArgumentValue,
+    /// Whether the value is the default declared by the schema, whether given explicitly or not. Only
+    /// set for requests, so that grants written before a field gained an argument with a default value
+    /// keep matching the field.
+    pub is_default: bool,
 }
 
 #[derive(Debug, PartialOrd, Ord, PartialEq, Eq, Clone, Hash)]
 pub enum ArgumentValue {
     StringLiteral(String),
     IntegerLiteral(i64),
+    FloatLiteral(Float),
     BoolLiteral(bool),
     Enum(String),
+    Null,
+    List(Vec<ArgumentValue>),
+    Object(BTreeMap<String, ArgumentValue>),
     Wildcard,
+
+    /// Matches any of the given values, e.g. `in ["a", "b"]`.
+    OneOf(BTreeSet<ArgumentValue>),
+
+    /// Matches the numbers within a range, e.g. `0..=50` or `<=100`.
+    Range(NumberRange),
+
+    /// Matches the strings which match a glob pattern, e.g. `~"*@uni.edu"`.
+    Glob(String),
+
+    /// A policy variable, e.g. `${self.accountId}`, which gets replaced by its value at
+    /// authorization time. Unbound, it only matches itself.
+    Variable(String),
 }
 
+/// A float argument value, totally ordered so that it can be part of a segment.
+#[derive(Debug, Clone, Copy)]
+pub struct Float(pub f64);
+
 #[derive(thiserror::Error, Debug)]
 pub enum AppendNodeError {
     #[error("Node already has a wildcard match on subfields. Cannot append to that.")]
@@ -105,6 +153,61 @@ impl PathSet {
     pub fn into_paths(self) -> Vec<PathNode> {
         self.paths.into_values().collect()
     }
+
+    /// Computes the part of `other` which is covered by this path set.
+    ///
+    /// Each branch of `other` (i.e. each path from a root to a leaf) is kept only if this path set
+    /// is a superset of it.
+    pub fn intersection(&self, other: &PathSet) -> PathSet {
+        other.filter_branches(|branch| self.is_superset_of(branch))
+    }
+
+    pub(crate) fn filter_branches(&self, mut predicate: impl FnMut(&PathSet) -> bool) -> PathSet {
+        let mut filtered = PathSet::default();
+        self.paths
+            .values()
+            .flat_map(PathNode::branches)
+            .map(PathSet::from)
+            .filter(|branch| predicate(branch))
+            .for_each(|branch| filtered.merge_path_set(branch));
+
+        filtered
+    }
+}
+
+impl From<PathNode> for PathSet {
+    fn from(node: PathNode) -> Self {
+        let mut path_set = PathSet::default();
+        path_set.merge_path_node(node);
+        path_set
+    }
+}
+
+impl AccessPathSet {
+    /// Grants access to the given path.
+    pub fn allow(&mut self, path: PathNode) {
+        self.allowed.merge_path_node(path);
+    }
+
+    /// Denies access to the given path and everything under it.
+    ///
+    /// A leaf in a denied path revokes the whole subtree, so leaves are turned into any-matches
+    /// before merging. Otherwise, merging `a` with `a::b` would narrow the denial to `a::b`.
+    pub fn deny(&mut self, mut path: PathNode) {
+        path.close_leaves();
+        self.denied.merge_path_node(path);
+    }
+
+    /// Checks whether the desired paths are all allowed and none of them is denied.
+    pub fn permits(&self, desired: &PathSet) -> bool {
+        self.allowed.is_superset_of(desired) && !self.denied.intersects(desired)
+    }
+
+    /// Computes the part of the desired paths which is permitted, leaving out every branch that is
+    /// either not allowed or denied.
+    pub fn prune(&self, desired: &PathSet) -> PathSet {
+        desired.filter_branches(|branch| self.permits(branch))
+    }
 }
 
 impl PathNode {
@@ -161,6 +264,37 @@ impl PathNode {
         self.fields.values().collect()
     }
 
+    /// Splits this node into single-chain paths, one for each of its leaves.
+    pub fn branches(&self) -> Vec<PathNode> {
+        if self.fields.is_empty() {
+            return vec![PathNode::new(self.segment.clone())];
+        }
+
+        self.fields
+            .values()
+            .flat_map(PathNode::branches)
+            .map(|sub_branch| {
+                let mut branch = PathNode::new(self.segment.clone());
+                branch.fields.insert(sub_branch.segment.clone(), sub_branch);
+                branch
+            })
+            .collect()
+    }
+
+    /// Appends an any-match to every leaf of this node, so that it matches whole subtrees.
+    pub(crate) fn close_leaves(&mut self) {
+        if matches!(self.segment, Segment::Any) {
+            return;
+        }
+
+        if self.fields.is_empty() {
+            self.fields.insert(Segment::Any, PathNode::new(Segment::Any));
+            return;
+        }
+
+        self.fields.values_mut().for_each(PathNode::close_leaves);
+    }
+
     pub fn into_fields(self) -> Vec<PathNode> {
         self.fields.into_values().collect()
     }
@@ -184,6 +318,7 @@ impl Argument {
         Argument {
             name: name.into(),
             value,
+            is_default: false,
         }
     }
 }
@@ -194,6 +329,15 @@ pub trait Superset {
 }
 
 
+/// Checks whether `self`, read as a restriction, applies to any part of `other`.
+///
+/// Unlike `Superset`, a restricting segment does not need to list all the arguments of the other
+/// segment: `accounts::password` applies to `accounts(includeNonDiscoverable: true)::password`.
+pub trait Intersects {
+    fn intersects(&self, other: &Self) -> bool;
+}
+
+
 impl Superset for PathSet {
     fn is_superset_of(&self, other: &Self) -> bool {
         if other.paths.len() > self.paths.len() {
@@ -268,7 +412,11 @@ impl Superset for Segment {
                 if lname != rname {
                     false
                 } else {
-                    if largs.keys().collect::<Vec<_>>() != rargs.keys().collect::<Vec<_>>() {
+                    // Arguments the grant does not mention only match while they hold their default value.
+                    if rargs
+                        .values()
+                        .any(|rarg| !rarg.is_default && !largs.contains_key(&rarg.name))
+                    {
                         return false;
                     }
 
@@ -291,6 +439,33 @@ impl Superset for Segment {
     }
 }
 
+impl PartialEq for Argument {
+    fn eq(&self, other: &Self) -> bool {
+        (&self.name, &self.value) == (&other.name, &other.value)
+    }
+}
+
+impl Eq for Argument {}
+
+impl PartialOrd for Argument {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Argument {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (&self.name, &self.value).cmp(&(&other.name, &other.value))
+    }
+}
+
+impl Hash for Argument {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+        self.value.hash(state);
+    }
+}
+
 impl Superset for Argument {
     fn is_superset_of(&self, other: &Self) -> bool {
         if self.name != other.name {
@@ -305,20 +480,165 @@ impl Superset for ArgumentValue {
     fn is_superset_of(&self, other: &Self) -> bool {
         match (self, other) {
             (ArgumentValue::Wildcard, _) => true,
+            (_, ArgumentValue::OneOf(ritems)) => ritems.iter().all(|r| self.is_superset_of(r)),
+            (ArgumentValue::OneOf(litems), _) => litems.iter().any(|l| l.is_superset_of(other)),
+            (ArgumentValue::Range(lrange), ArgumentValue::Range(rrange)) => lrange.covers(rrange),
+            (ArgumentValue::Range(lrange), _) => Number::from_value(other).map_or(false, |r| lrange.contains(&r)),
+            (ArgumentValue::Glob(lpattern), ArgumentValue::Glob(rpattern)) => glob_covers(lpattern, rpattern),
+            (ArgumentValue::Glob(lpattern), ArgumentValue::StringLiteral(r)) => glob_matches(lpattern, r),
+            (ArgumentValue::List(litems), ArgumentValue::List(ritems)) => {
+                litems.len() == ritems.len() && std::iter::zip(litems, ritems).all(|(l, r)| l.is_superset_of(r))
+            }
+            (ArgumentValue::Object(lfields), ArgumentValue::Object(rfields)) => {
+                // Like the arguments of a segment, objects with different fields are not comparable. This
+                // is deliberately strict for allows: granting `{x: *}` does not grant objects with fields
+                // it does not mention. Denies go through `Intersects`, which ignores such fields.
+                lfields.len() == rfields.len()
+                    && lfields
+                        .iter()
+                        .all(|(name, l)| rfields.get(name).map_or(false, |r| l.is_superset_of(r)))
+            }
             _ => self == other,
         }
     }
 }
 
 
-impl TryFrom<OperationType> for AccessKind {
-    type Error = ();
+impl Intersects for PathSet {
+    fn intersects(&self, other: &Self) -> bool {
+        other
+            .paths
+            .values()
+            .any(|r| self.paths.values().any(|l| l.intersects(r)))
+    }
+}
+
+impl Intersects for PathNode {
+    fn intersects(&self, other: &Self) -> bool {
+        if !self.segment.intersects(&other.segment) {
+            return false;
+        }
+
+        if self.fields.is_empty() || self.fields.contains_key(&Segment::Any) {
+            // The whole subtree is matched.
+            return true;
+        }
 
-    fn try_from(operation_type: OperationType) -> Result<Self, Self::Error> {
+        if other.fields.contains_key(&Segment::Any) {
+            // Other node selects everything, including the restricted fields.
+            return true;
+        }
+
+        other
+            .fields
+            .values()
+            .any(|r| self.fields.values().any(|l| l.intersects(r)))
+    }
+}
+
+impl Intersects for Segment {
+    fn intersects(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Segment::Any, _) | (_, Segment::Any) => true,
+            (Segment::Named(lname, largs), Segment::Named(rname, rargs)) => {
+                lname == rname
+                    && largs.values().all(|larg| match rargs.get(&larg.name) {
+                        None => false,
+                        Some(rarg) => larg.intersects(rarg),
+                    })
+            }
+        }
+    }
+}
+
+impl Intersects for Argument {
+    fn intersects(&self, other: &Self) -> bool {
+        if self.name != other.name {
+            panic!("called intersects for arguments with different names");
+        } else {
+            self.value.intersects(&other.value)
+        }
+    }
+}
+
+impl Intersects for ArgumentValue {
+    fn intersects(&self, other: &Self) -> bool {
+        match (self, other) {
+            (ArgumentValue::Wildcard, _) | (_, ArgumentValue::Wildcard) => true,
+            (ArgumentValue::OneOf(litems), _) => litems.iter().any(|l| l.intersects(other)),
+            (_, ArgumentValue::OneOf(ritems)) => ritems.iter().any(|r| self.intersects(r)),
+            (ArgumentValue::Range(lrange), ArgumentValue::Range(rrange)) => lrange.overlaps(rrange),
+            (ArgumentValue::Range(range), value) | (value, ArgumentValue::Range(range)) => {
+                Number::from_value(value).map_or(false, |n| range.contains(&n))
+            }
+            // Telling whether two patterns have a match in common is not worth it: assume they do.
+            (ArgumentValue::Glob(_), ArgumentValue::Glob(_)) => true,
+            (ArgumentValue::Glob(pattern), ArgumentValue::StringLiteral(s))
+            | (ArgumentValue::StringLiteral(s), ArgumentValue::Glob(pattern)) => glob_matches(pattern, s),
+            (ArgumentValue::List(litems), ArgumentValue::List(ritems)) => {
+                litems.len() == ritems.len() && std::iter::zip(litems, ritems).all(|(l, r)| l.intersects(r))
+            }
+            (ArgumentValue::Object(lfields), ArgumentValue::Object(rfields)) => {
+                // Fields given on one side only do not make objects disjoint, otherwise adding any field
+                // to a request would slip it past a deny.
+                lfields
+                    .iter()
+                    .all(|(name, l)| rfields.get(name).map_or(true, |r| l.intersects(r)))
+            }
+            _ => self == other,
+        }
+    }
+}
+
+impl PartialEq for Float {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Float {}
+
+impl PartialOrd for Float {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Float {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl Hash for Float {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.to_bits().hash(state);
+    }
+}
+
+
+impl Default for Effect {
+    fn default() -> Self {
+        Effect::Allow
+    }
+}
+
+impl From<OperationType> for AccessKind {
+    fn from(operation_type: OperationType) -> Self {
         match operation_type {
-            OperationType::Query => Ok(AccessKind::Query),
-            OperationType::Mutation => Ok(AccessKind::Mutation),
-            _ => Err(()),
+            OperationType::Query => AccessKind::Query,
+            OperationType::Mutation => AccessKind::Mutation,
+            OperationType::Subscription => AccessKind::Subscription,
+        }
+    }
+}
+
+impl From<AccessKind> for OperationType {
+    fn from(access_kind: AccessKind) -> Self {
+        match access_kind {
+            AccessKind::Query => OperationType::Query,
+            AccessKind::Mutation => OperationType::Mutation,
+            AccessKind::Subscription => OperationType::Subscription,
         }
     }
 }
@@ -373,6 +693,14 @@ impl Display for Segment {
     }
 }
 
+impl Display for Float {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        // The debug representation always has a decimal point or an exponent, which tells floats
+        // apart from integers.
+        write!(f, "{:?}", self.0)
+    }
+}
+
 impl Display for Argument {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         write!(f, "{}: {}", self.name, self.value)?;
@@ -386,16 +714,54 @@ impl Display for ArgumentValue {
         match self {
             ArgumentValue::BoolLiteral(val) => write!(f, "{}", val)?,
             ArgumentValue::IntegerLiteral(val) => write!(f, "{}", val)?,
-            // FIXME Properly escape characters.
-            ArgumentValue::StringLiteral(val) => write!(f, "\"{}\"", val)?,
+            ArgumentValue::StringLiteral(val) => write_string_literal(f, val)?,
+            ArgumentValue::FloatLiteral(val) => write!(f, "{}", val)?,
             ArgumentValue::Enum(name) => write!(f, "{}", name)?,
+            ArgumentValue::Null => write!(f, "null")?,
+            ArgumentValue::List(items) => {
+                let rendered: Vec<String> = items.iter().map(ToString::to_string).collect();
+                write!(f, "[{}]", rendered.join(", "))?
+            }
+            ArgumentValue::Object(fields) => {
+                let rendered: Vec<String> = fields.iter().map(|(name, value)| format!("{name}: {value}")).collect();
+                write!(f, "{{{}}}", rendered.join(", "))?
+            }
             ArgumentValue::Wildcard => write!(f, "*")?,
+            ArgumentValue::OneOf(items) => {
+                let rendered: Vec<String> = items.iter().map(ToString::to_string).collect();
+                write!(f, "in [{}]", rendered.join(", "))?
+            }
+            ArgumentValue::Range(range) => write!(f, "{}", range)?,
+            ArgumentValue::Glob(pattern) => {
+                write!(f, "~")?;
+                write_string_literal(f, pattern)?
+            }
+            ArgumentValue::Variable(name) => write!(f, "${{{}}}", name)?,
         };
 
         Ok(())
     }
 }
 
+/// Writes a quoted string, escaping the characters which the string literals of resource paths
+/// escape, so that it parses back to the same string.
+fn write_string_literal(f: &mut Formatter<'_>, s: &str) -> FmtResult {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            '\u{08}' => write!(f, "\\b")?,
+            '\u{0C}' => write!(f, "\\f")?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
 
 #[cfg(test)]
 mod path_tests {
@@ -536,6 +902,7 @@ mod string_tests {
             vec![Argument {
                 name: "id".to_owned(),
                 value: ArgumentValue::StringLiteral("foo".to_owned()),
+                is_default: false,
             }],
         );
         path_set.extend([account_seg.clone(), Segment::no_args("id")]);
@@ -698,20 +1065,102 @@ mod superset_tests {
     #[case(ArgumentValue::BoolLiteral(false), ArgumentValue::BoolLiteral(false), true)]
     #[case(ArgumentValue::Enum("A".to_string()), ArgumentValue::Enum("B".to_string()), false)]
     #[case(ArgumentValue::Enum("A".to_string()), ArgumentValue::Enum("A".to_string()), true)]
+    #[case(
+        ArgumentValue::FloatLiteral(Float(1.5)),
+        ArgumentValue::FloatLiteral(Float(1.5)),
+        true
+    )]
+    #[case(ArgumentValue::FloatLiteral(Float(1.0)), ArgumentValue::IntegerLiteral(1), false)]
+    #[case(ArgumentValue::Null, ArgumentValue::Null, true)]
+    #[case(ArgumentValue::Null, ArgumentValue::Wildcard, false)]
+    #[case(
+        list(&[ArgumentValue::Wildcard, ArgumentValue::IntegerLiteral(1)]),
+        list(&[ArgumentValue::Null, ArgumentValue::IntegerLiteral(1)]),
+        true
+    )]
+    #[case(list(&[ArgumentValue::Wildcard]), list(&[ArgumentValue::Null, ArgumentValue::Null]), false)]
+    #[case(
+        object(&[("a", ArgumentValue::Wildcard), ("b", ArgumentValue::BoolLiteral(true))]),
+        object(&[("a", ArgumentValue::IntegerLiteral(2)), ("b", ArgumentValue::BoolLiteral(true))]),
+        true
+    )]
+    #[case(
+        object(&[("a", ArgumentValue::Wildcard)]),
+        object(&[("a", ArgumentValue::Null), ("b", ArgumentValue::Null)]),
+        false
+    )]
+    #[case(
+        object(&[("a", object(&[("b", ArgumentValue::Wildcard)]))]),
+        object(&[("a", object(&[("b", ArgumentValue::Null)]))]),
+        true
+    )]
+    #[case(
+        object(&[("a", object(&[("b", ArgumentValue::Null)]))]),
+        object(&[("a", object(&[("b", ArgumentValue::Wildcard)]))]),
+        false
+    )]
     fn argument_value(#[case] a: ArgumentValue, #[case] b: ArgumentValue, #[case] expected: bool) {
         assert_eq!(a.is_superset_of(&b), expected);
     }
 
+    #[rstest]
+    #[case("a(b: in [1, 2, 3])", "a(b: 2)", true)]
+    #[case("a(b: in [1, 2, 3])", "a(b: 4)", false)]
+    #[case("a(b: in [1, 2, 3])", "a(b: in [3, 1])", true)]
+    #[case("a(b: in [1, 2])", "a(b: in [1, 4])", false)]
+    #[case("a(b: in [1, 2])", "a(b: *)", false)]
+    #[case("a(b: 1)", "a(b: in [1])", true)]
+    #[case("a(b: 0..=50)", "a(b: 50)", true)]
+    #[case("a(b: 0..=50)", "a(b: 25.5)", true)]
+    #[case("a(b: 0..50)", "a(b: 50)", false)]
+    #[case("a(b: 0..=50)", "a(b: \"10\")", false)]
+    #[case("a(b: 0..=50)", "a(b: 10..20)", true)]
+    #[case("a(b: 0..=50)", "a(b: >=10)", false)]
+    #[case("a(b: 0..=50)", "a(b: in [0, 50])", true)]
+    #[case("a(b: <=100)", "a(b: -1000)", true)]
+    #[case("a(b: <=100)", "a(b: <100)", true)]
+    #[case("a(b: <100)", "a(b: <=100)", false)]
+    #[case("a(b: >0)", "a(b: 0)", false)]
+    #[case("a(b: ~\"*@uni.edu\")", "a(b: \"jane@uni.edu\")", true)]
+    #[case("a(b: ~\"*@uni.edu\")", "a(b: \"jane@gmail.com\")", false)]
+    #[case("a(b: ~\"*@uni.edu\")", "a(b: in [\"a@uni.edu\", \"b@uni.edu\"])", true)]
+    #[case("a(b: ~\"*.uni.edu\")", "a(b: ~\"*@cs.uni.edu\")", true)]
+    #[case("a(b: ~\"*@cs.uni.edu\")", "a(b: ~\"*.uni.edu\")", false)]
+    #[case("a(b: \"*@uni.edu\")", "a(b: ~\"*@uni.edu\")", false)]
+    fn predicates(#[case] a: &str, #[case] b: &str, #[case] expected: bool) {
+        use crate::resource_access::string_interop::compiler::from_string;
+
+        let a = from_string(a).expect("failed parsing a");
+        let b = from_string(b).expect("failed parsing b");
+
+        assert_eq!(a.is_superset_of(&b), expected);
+    }
+
+    fn list(items: &[ArgumentValue]) -> ArgumentValue {
+        ArgumentValue::List(items.to_vec())
+    }
+
+    fn object(fields: &[(&str, ArgumentValue)]) -> ArgumentValue {
+        ArgumentValue::Object(
+            fields
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.clone()))
+                .collect(),
+        )
+    }
+
     #[test]
     #[should_panic]
     fn arguments_with_different_names() {
         let a = Argument {
             name: "a".to_string(),
             value: ArgumentValue::Wildcard,
+            is_default: false,
         };
         let b = Argument {
             name: "b".to_string(),
             value: ArgumentValue::Wildcard,
+            is_default: false,
         };
 
         a.is_superset_of(&b);
@@ -722,10 +1171,12 @@ mod superset_tests {
         let a = Argument {
             name: "a".to_string(),
             value: ArgumentValue::Wildcard,
+            is_default: false,
         };
         let b = Argument {
             name: "a".to_string(),
             value: ArgumentValue::Wildcard,
+            is_default: false,
         };
 
         assert!(a.is_superset_of(&b));
@@ -874,3 +1325,137 @@ mod superset_tests {
         assert!(allowed_path_set.is_superset_of(&desired_path_set));
     }
 }
+
+#[cfg(test)]
+mod pruning_tests {
+    use rstest::rstest;
+
+    use super::*;
+    use crate::resource_access::string_interop::compiler::from_string;
+
+    #[rstest]
+    #[case("a::{b, c}", "a::{b, d}", &["a::b"])]
+    #[case("a::*", "a::{b, c::d}", &["a::{b, c::d}"])]
+    #[case("a::b", "x::y", &[])]
+    #[case("a::b::c", "a::{b::{c, d}, e}", &["a::b::c"])]
+    #[case("a(id: *)::x", "a(id: 1)::{x, y}", &["a(id: 1)::x"])]
+    fn intersection(#[case] allowed: &str, #[case] desired: &str, #[case] expected: &[&str]) {
+        let allowed = from_string(allowed).unwrap();
+        let desired = from_string(desired).unwrap();
+
+        let rendered: Vec<String> = allowed
+            .intersection(&desired)
+            .paths()
+            .into_iter()
+            .map(|p| p.to_string())
+            .collect();
+        assert_eq!(rendered, expected.to_vec());
+    }
+
+    #[test]
+    fn branches() {
+        let path = from_string("a::{b::{c, d}, e}").unwrap().into_paths().pop().unwrap();
+        let rendered: Vec<String> = path.branches().iter().map(|b| b.to_string()).collect();
+
+        assert_eq!(rendered, vec!["a::b::c", "a::b::d", "a::e"]);
+    }
+
+    #[test]
+    fn prune_leaves_out_denied() {
+        let mut access_path_set = AccessPathSet::default();
+        access_path_set.allow(from_string("a::*").unwrap().into_paths().pop().unwrap());
+        access_path_set.deny(from_string("a::password").unwrap().into_paths().pop().unwrap());
+
+        let desired = from_string("a::{id, password}").unwrap();
+        let pruned = access_path_set.prune(&desired);
+
+        assert_eq!(pruned.paths()[0].to_string(), "a::id");
+    }
+}
+
+#[cfg(test)]
+mod intersects_tests {
+    use rstest::rstest;
+
+    use super::*;
+    use crate::resource_access::string_interop::compiler::from_string;
+
+    #[rstest]
+    #[case("a::b", "a::b", true)]
+    #[case("a::b", "a::c", false)]
+    #[case("a::b", "a::{b, c}", true)]
+    #[case("a", "a::{b, c}", true)]
+    #[case("a::*", "a::c", true)]
+    #[case("a::b::c", "a::b::d", false)]
+    #[case("a::b", "a::*", true)]
+    #[case("a::b", "a(foo: 10)::b", true)]
+    #[case("a(foo: 10)::b", "a::b", false)]
+    #[case("a(foo: 10)::b", "a(foo: 10)::b", true)]
+    #[case("a(foo: 10)::b", "a(foo: 11)::b", false)]
+    #[case("a(foo: 10)::b", "a(foo: *)::b", true)]
+    #[case("a(foo: *)::b", "a(foo: \"x\", bar: true)::b", true)]
+    #[case("a(foo: {x: *, y: [1]})", "a(foo: {x: 2, y: [*]})", true)]
+    #[case("a(foo: {x: *, y: [1]})", "a(foo: {x: 2, y: [2]})", false)]
+    #[case("a(foo: {x: *})", "a(foo: {x: 2, y: 3})", true)]
+    #[case("a(foo: {x: 1})", "a(foo: {x: 2, y: 3})", false)]
+    #[case("a(foo: in [1, 2])", "a(foo: 2)", true)]
+    #[case("a(foo: in [1, 2])", "a(foo: in [2, 3])", true)]
+    #[case("a(foo: in [1, 2])", "a(foo: 3..4)", false)]
+    #[case("a(foo: 0..=50)", "a(foo: 50..60)", true)]
+    #[case("a(foo: 0..50)", "a(foo: >=50)", false)]
+    #[case("a(foo: ~\"*@uni.edu\")", "a(foo: \"x@uni.edu\")", true)]
+    #[case("a(foo: ~\"*@uni.edu\")", "a(foo: \"x@gmail.com\")", false)]
+    fn path_set(#[case] a: &str, #[case] b: &str, #[case] expected: bool) {
+        let a = from_string(a).expect("failed parsing a");
+        let b = from_string(b).expect("failed parsing b");
+
+        assert_eq!(a.intersects(&b), expected);
+    }
+
+    #[rstest]
+    #[case(&["a::*"], &[], "a::b", true)]
+    #[case(&["a::*"], &["a::password"], "a::b", true)]
+    #[case(&["a::*"], &["a::password"], "a::{b, password}", false)]
+    #[case(&["a::*"], &["a"], "a::b", false)]
+    #[case(&["a(id: *)::*"], &["a(id: \"root\")::*"], "a(id: \"me\")::b", true)]
+    #[case(&["a(id: *)::*"], &["a(id: \"root\")::*"], "a(id: \"root\")::b", false)]
+    #[case(&[], &["a::b"], "a::b", false)]
+    #[case(&["a(foo: *)"], &["a(foo: {x: *})"], "a(foo: {x: 2})", false)]
+    #[case(&["a(foo: *)"], &["a(foo: {x: *})"], "a(foo: {x: 2, y: 3})", false)]
+    #[case(&["a(foo: *)"], &["a(foo: {x: 1})"], "a(foo: {x: 2, y: 3})", true)]
+    #[case(&["a(foo: *)"], &["a(foo: {x: {y: *}})"], "a(foo: {x: {y: 1, z: 2}, w: 3})", false)]
+    fn access_path_set(
+        #[case] allowed: &[&str],
+        #[case] denied: &[&str],
+        #[case] desired: &str,
+        #[case] expected: bool,
+    ) {
+        let mut access_path_set = AccessPathSet::default();
+        for path in allowed {
+            from_string(path)
+                .expect("failed parsing allowed path")
+                .into_paths()
+                .into_iter()
+                .for_each(|p| access_path_set.allow(p));
+        }
+        for path in denied {
+            from_string(path)
+                .expect("failed parsing denied path")
+                .into_paths()
+                .into_iter()
+                .for_each(|p| access_path_set.deny(p));
+        }
+
+        let desired = from_string(desired).expect("failed parsing desired path");
+        assert_eq!(access_path_set.permits(&desired), expected);
+    }
+
+    #[test]
+    fn deny_leaf_is_not_narrowed_by_merge() {
+        let mut access_path_set = AccessPathSet::default();
+        access_path_set.deny(from_string("a").unwrap().into_paths().pop().unwrap());
+        access_path_set.deny(from_string("a::b").unwrap().into_paths().pop().unwrap());
+
+        assert_eq!(access_path_set.denied.paths()[0].to_string(), "a::*");
+    }
+}
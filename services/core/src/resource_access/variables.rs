@@ -0,0 +1,221 @@
+use std::collections::btree_map::OccupiedError;
+use std::collections::HashMap;
+
+use super::types::{AccessPathSet, Argument, ArgumentValue, PathNode, PathSet, Segment};
+
+/// Values of the policy variables (e.g. `${self.accountId}`) for the subject of an authorization.
+#[derive(Debug, Clone, Default)]
+pub struct PolicyVariables {
+    values: HashMap<String, ArgumentValue>,
+}
+
+
+impl PolicyVariables {
+    pub fn insert(&mut self, name: impl Into<String>, value: ArgumentValue) {
+        self.values.insert(name.into(), value);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ArgumentValue> {
+        self.values.get(name)
+    }
+}
+
+impl AccessPathSet {
+    /// Replaces the policy variables found in the allowed and denied paths with their values.
+    ///
+    /// Unbound variables never widen access: allowed fields using one are dropped, together with their
+    /// parents left without fields, while denied paths using one match any value in its place.
+    pub fn bind_variables(self, variables: &PolicyVariables) -> AccessPathSet {
+        let mut bound = AccessPathSet::default();
+        for path in self.allowed.into_paths() {
+            if let Some(path) = path.bind_variables(variables, None) {
+                bound.allowed.merge_path_node(path);
+            }
+        }
+        for path in self.denied.into_paths() {
+            if let Some(path) = path.bind_variables(variables, Some(&ArgumentValue::Wildcard)) {
+                bound.denied.merge_path_node(path);
+            }
+        }
+
+        bound
+    }
+}
+
+impl PathSet {
+    /// Names of the policy variables used anywhere in this path set.
+    pub fn variable_names(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        self.paths
+            .values()
+            .for_each(|path| path.collect_variable_names(&mut names));
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
+}
+
+impl PathNode {
+    /// Binds the variables of this path, or returns `None` if its segment uses an unbound variable
+    /// and there is no fallback value. Fields using one are dropped, and so is the node if none of
+    /// its fields are left, since a leaf would grant all of them.
+    fn bind_variables(self, variables: &PolicyVariables, unbound: Option<&ArgumentValue>) -> Option<PathNode> {
+        let mut node = PathNode::new(self.segment.bind_variables(variables, unbound)?);
+        let has_fields = !self.fields.is_empty();
+        for field in self.fields.into_values() {
+            let Some(field) = field.bind_variables(variables, unbound) else { continue };
+            // Distinct fields may become the same once bound.
+            if let Err(OccupiedError { mut entry, value }) = node.fields.try_insert(field.segment.clone(), field) {
+                entry.get_mut().merge(value);
+            }
+        }
+
+        if has_fields && node.fields.is_empty() {
+            return None;
+        }
+
+        Some(node)
+    }
+
+    fn collect_variable_names<'a>(&'a self, names: &mut Vec<&'a str>) {
+        if let Segment::Named(_, args) = &self.segment {
+            args.values().for_each(|arg| arg.value.collect_variable_names(names));
+        }
+        self.fields
+            .values()
+            .for_each(|field| field.collect_variable_names(names));
+    }
+}
+
+impl Segment {
+    fn bind_variables(self, variables: &PolicyVariables, unbound: Option<&ArgumentValue>) -> Option<Segment> {
+        match self {
+            Segment::Any => Some(Segment::Any),
+            Segment::Named(name, args) => {
+                let args = args
+                    .into_values()
+                    .map(|arg| {
+                        Some(Argument {
+                            value: arg.value.bind_variables(variables, unbound)?,
+                            name: arg.name,
+                            is_default: arg.is_default,
+                        })
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+
+                Some(Segment::with_args(name, args))
+            }
+        }
+    }
+}
+
+impl ArgumentValue {
+    fn bind_variables(self, variables: &PolicyVariables, unbound: Option<&ArgumentValue>) -> Option<ArgumentValue> {
+        let bound = match self {
+            ArgumentValue::Variable(name) => variables.get(&name).or(unbound)?.clone(),
+            ArgumentValue::List(items) => ArgumentValue::List(
+                items
+                    .into_iter()
+                    .map(|item| item.bind_variables(variables, unbound))
+                    .collect::<Option<_>>()?,
+            ),
+            ArgumentValue::Object(fields) => ArgumentValue::Object(
+                fields
+                    .into_iter()
+                    .map(|(name, value)| Some((name, value.bind_variables(variables, unbound)?)))
+                    .collect::<Option<_>>()?,
+            ),
+            ArgumentValue::OneOf(items) => ArgumentValue::OneOf(
+                items
+                    .into_iter()
+                    .map(|item| item.bind_variables(variables, unbound))
+                    .collect::<Option<_>>()?,
+            ),
+            value => value,
+        };
+
+        Some(bound)
+    }
+
+    fn collect_variable_names<'a>(&'a self, names: &mut Vec<&'a str>) {
+        match self {
+            ArgumentValue::Variable(name) => names.push(name),
+            ArgumentValue::List(items) => items.iter().for_each(|item| item.collect_variable_names(names)),
+            ArgumentValue::Object(fields) => fields.values().for_each(|value| value.collect_variable_names(names)),
+            ArgumentValue::OneOf(items) => items.iter().for_each(|item| item.collect_variable_names(names)),
+            _ => {}
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+    use crate::resource_access::string_interop::compiler::from_string;
+
+    fn variables() -> PolicyVariables {
+        let mut variables = PolicyVariables::default();
+        variables.insert("self.accountId", ArgumentValue::StringLiteral("abc".to_owned()));
+        variables.insert("jwt.email", ArgumentValue::StringLiteral("jane@uni.edu".to_owned()));
+        variables
+    }
+
+    fn render(path_set: &PathSet) -> String {
+        let rendered: Vec<String> = path_set.paths().into_iter().map(ToString::to_string).collect();
+        rendered.join(", ")
+    }
+
+    fn access_path_set(allowed: &str, denied: &str) -> AccessPathSet {
+        let mut access_path_set = AccessPathSet::default();
+        from_string(allowed)
+            .unwrap()
+            .into_paths()
+            .into_iter()
+            .for_each(|p| access_path_set.allow(p));
+        from_string(denied)
+            .unwrap()
+            .into_paths()
+            .into_iter()
+            .for_each(|p| access_path_set.deny(p));
+        access_path_set
+    }
+
+    #[rstest]
+    #[case("account(id: $self.accountId)::*", "account(id: \"abc\")::*")]
+    #[case("account(id: ${self.accountId})::email", "account(id: \"abc\")::email")]
+    #[case(
+        "accounts(filter: {email: ${jwt.email}})",
+        "accounts(filter: {email: \"jane@uni.edu\"})"
+    )]
+    #[case("account(id: in [$self.accountId, \"def\"])", "account(id: in [\"abc\", \"def\"])")]
+    #[case("a::{b(id: $self.accountId), b(id: \"abc\")}", "a::b(id: \"abc\")")]
+    #[case("account(id: $jwt.sub)::*", "")]
+    #[case("a::{b, c(id: $jwt.sub)}", "a::b")]
+    #[case("a::{b::c(id: $jwt.sub), d}", "a::d")]
+    #[case("a::c(id: $jwt.sub)", "")]
+    #[case("a::b::c(id: $jwt.sub)", "")]
+    fn bind_allowed(#[case] allowed: &str, #[case] expected: &str) {
+        let bound = access_path_set(allowed, "x").bind_variables(&variables());
+
+        assert_eq!(render(&bound.allowed), expected);
+    }
+
+    #[rstest]
+    #[case("account(id: $self.accountId)::password", "account(id: \"abc\")::password::*")]
+    #[case("account(id: $jwt.sub)::password", "account(id: *)::password::*")]
+    fn bind_denied(#[case] denied: &str, #[case] expected: &str) {
+        let bound = access_path_set("x", denied).bind_variables(&variables());
+
+        assert_eq!(render(&bound.denied), expected);
+    }
+
+    #[test]
+    fn variable_names() {
+        let path_set = from_string("a(x: $jwt.sub, y: [${self.accountId}])::b(z: in [$jwt.sub, 1])").unwrap();
+
+        assert_eq!(path_set.variable_names(), vec!["jwt.sub", "self.accountId"]);
+    }
+}
@@ -1,13 +1,33 @@
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use async_graphql::{extensions, ServerError};
-use identity_service::pb::AuthorizeInput;
-use service_core::resource_access::graphql_interop::parser::from_document;
+use async_graphql::{
+    extensions, value, ErrorExtensionValues, Name, PathSegment, Request, Response, ServerError, Value,
+};
+use identity_service::pb::access_request::AccessKind as AccessKindPb;
+use identity_service::pb::path_denial::Reason as ReasonPb;
+use identity_service::pb::{AccessRequest, AuthorizeInput, PathDenial};
+use service_core::auth::jwt::Claims;
+use service_core::resource_access::graphql_interop::parser::{from_document_operation, prune_document, PrunedField};
+use service_core::resource_access::graphql_interop::schema::SchemaInfo;
+use service_core::resource_access::string_interop::compiler::from_string;
+use service_core::resource_access::types::PathSet;
+use service_core::resource_access::{AccessKind, AccessRequest as AccessRequestModel};
 use tracing_futures::Instrument;
 
 use crate::integration::identity_service::schema::GraphQLError;
 use crate::integration::identity_service::IdentityServiceRef;
 use crate::schema::authorization::Authorization;
+
+/// Query path that, when granted, allows a caller to see why its requests were denied.
+pub const DEBUG_PERMISSION_PATH: &str = "__debug::authorization";
+
+/// Access scopes embedded into access tokens are considered stale after this long, in which case
+/// requests get authorized by the identity service. This bounds how long a permission which was
+/// revoked keeps being granted by the scopes of tokens issued before.
+const ACCESS_SCOPES_MAX_AGE: Duration = Duration::from_secs(30);
+
 pub struct Authorizer;
 
 impl extensions::ExtensionFactory for Authorizer {
@@ -16,11 +36,39 @@ impl extensions::ExtensionFactory for Authorizer {
     }
 }
 
+/// Authorizes each request before it gets executed, including subscriptions when they start. Only
+/// the operation selected by the `operationName` of the request is authorized, since it is the only
+/// one being executed.
+///
+/// Requests permitted by the access scopes embedded into the access token are authorized locally.
+/// Any other request is authorized by the identity service.
+///
+/// Queries which are only partially allowed get pruned: the fields which are not granted are left
+/// out of execution, then reported as `null` with a per-field error. Mutations and subscriptions
+/// are either allowed as a whole or rejected.
+///
+/// Subscriptions are not authorized again while they run, so revoking the session or deactivating
+/// the account does not end them. They end when the access token they were authorized with expires
+/// instead, including the ones started on a websocket connection after its token expired.
 #[derive(Default)]
-pub struct AuthorizerExtension;
+pub struct AuthorizerExtension {
+    operation_name: Mutex<Option<String>>,
+    pruned: Mutex<Vec<PrunedField>>,
+}
 
 #[async_trait::async_trait]
 impl extensions::Extension for AuthorizerExtension {
+    async fn prepare_request(
+        &self,
+        ctx: &extensions::ExtensionContext<'_>,
+        request: Request,
+        next: extensions::NextPrepareRequest<'_>,
+    ) -> async_graphql::ServerResult<Request> {
+        let request = next.run(ctx, request).await?;
+        *self.operation_name.lock().unwrap() = request.operation_name.clone();
+        Ok(request)
+    }
+
     #[tracing::instrument(skip_all)]
     async fn parse_query(
         &self,
@@ -29,24 +77,29 @@ impl extensions::Extension for AuthorizerExtension {
         variables: &async_graphql::Variables,
         next: extensions::NextParseQuery<'_>,
     ) -> async_graphql::ServerResult<async_graphql_parser::types::ExecutableDocument> {
-        let document = next.run(ctx, query, variables).await?;
+        let mut document = next.run(ctx, query, variables).await?;
 
-        let mut access_requests =
-            from_document(&document, &variables).map_err(|e| ServerError::new(e.to_string(), None))?;
-        // FIXME Add support for multi-operation documents.
-        let access_request = access_requests
-            .pop()
-            .ok_or_else(|| ServerError::new("No access request was compiled.", None))?;
+        let schema_info = ctx.data_unchecked::<SchemaInfo>();
+        let operation_name = self.operation_name.lock().unwrap().clone();
+        let access_request = from_document_operation(&document, schema_info, &variables, operation_name.as_deref())
+            .map_err(|e| ServerError::new(e.to_string(), None))?;
 
-        let account_id = ctx
-            .data_unchecked::<Option<Authorization>>()
-            .as_ref()
-            .map(|v| v.claims.sub.clone());
+        let authorization = ctx.data_unchecked::<Option<Authorization>>().as_ref();
+        let account_id = authorization.map(|v| v.claims.sub.clone());
+        let claims = authorization.map(|v| v.claims.string_claims()).unwrap_or_default();
+        if authorization.map_or(false, |v| permitted_by_scopes(&v.claims, &access_request)) {
+            tracing::debug!("Request authorized by access scopes.");
+            return Ok(document);
+        }
 
+        let can_prune = access_request.kind == AccessKind::Query;
         let mut identity_service_client = ctx.data_unchecked::<IdentityServiceRef>().clone();
         let request = tonic::Request::new(AuthorizeInput {
-            account_id,
+            account_id: account_id.clone(),
             access_request: Some(access_request.into()),
+            explain: true,
+            prune: can_prune,
+            claims: claims.clone(),
         });
         let output = identity_service_client
             .authorize(request)
@@ -57,10 +110,155 @@ impl extensions::Extension for AuthorizerExtension {
                 ServerError::from(GraphQLError::Internal)
             })?
             .into_inner();
-        if !output.permission_granted {
-            return Err(GraphQLError::PermissionDenied.into());
+        if !output.permission_granted && can_prune && !output.granted_paths.is_empty() {
+            let mut granted = PathSet::default();
+            for path in &output.granted_paths {
+                let path_set = from_string(path).map_err(|e| {
+                    tracing::error!(error = ?&e, path = path.as_str(), "Invalid granted path.");
+                    ServerError::from(GraphQLError::Internal)
+                })?;
+                granted.merge_path_set(path_set);
+            }
+
+            let pruned = prune_document(
+                &mut document,
+                schema_info,
+                variables,
+                operation_name.as_deref(),
+                &granted,
+            )
+            .map_err(|e| ServerError::new(e.to_string(), None))?;
+            *self.pruned.lock().unwrap() = pruned;
+        } else if !output.permission_granted {
+            let mut error = ServerError::from(GraphQLError::PermissionDenied);
+            if !output.denials.is_empty() && can_debug(&mut identity_service_client, account_id, claims).await {
+                let mut extensions = ErrorExtensionValues::default();
+                extensions.set(
+                    "denials",
+                    Value::List(output.denials.into_iter().map(denial_value).collect()),
+                );
+                error.extensions = Some(extensions);
+            }
+
+            return Err(error);
         }
 
         Ok(document)
     }
+
+    async fn execute(
+        &self,
+        ctx: &extensions::ExtensionContext<'_>,
+        operation_name: Option<&str>,
+        next: extensions::NextExecute<'_>,
+    ) -> Response {
+        let mut response = next.run(ctx, operation_name).await;
+
+        let pruned = std::mem::take(&mut *self.pruned.lock().unwrap());
+        for field in pruned {
+            // Non-null fields can't be nulled themselves, so the null propagates to their closest
+            // nullable ancestor, or to the whole response.
+            set_null(&mut response.data, &field.null_path);
+
+            let mut error = ServerError::new(GraphQLError::PermissionDenied.to_string(), Some(field.pos));
+            error.path = field.path.into_iter().map(PathSegment::Field).collect();
+            response.errors.push(error);
+        }
+
+        response
+    }
+}
+
+/// Checks whether the access scopes embedded into the access token permit the whole access request.
+/// Missing, stale or invalid scopes never do, so that the identity service gets asked instead.
+fn permitted_by_scopes(claims: &Claims, access_request: &AccessRequestModel) -> bool {
+    let Some(scopes) = &claims.scopes else { return false };
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs())
+        .unwrap_or_default();
+    if scopes.issued_at as u64 + ACCESS_SCOPES_MAX_AGE.as_secs() < now {
+        return false;
+    }
+
+    let access_path_set = match scopes.paths(access_request.kind).access_path_set() {
+        Ok(access_path_set) => access_path_set,
+        Err(e) => {
+            tracing::warn!(error = ?&e, "Invalid access scopes.");
+            return false;
+        }
+    };
+    let mut desired_paths = PathSet::default();
+    for path in &access_request.paths {
+        desired_paths.merge_path_node(path.clone());
+    }
+
+    access_path_set.permits(&desired_paths)
+}
+
+/// Sets the value found under the given response keys to `null`, for every element of the lists
+/// found along the way. An empty path sets the value itself to `null`.
+fn set_null(value: &mut Value, path: &[String]) {
+    let Some((key, rest)) = path.split_first() else {
+        *value = Value::Null;
+        return;
+    };
+
+    match value {
+        Value::Object(fields) if rest.is_empty() => {
+            fields.insert(Name::new(key), Value::Null);
+        }
+        Value::Object(fields) => {
+            if let Some(field) = fields.get_mut(key.as_str()) {
+                set_null(field, rest);
+            }
+        }
+        Value::List(items) => items.iter_mut().for_each(|item| set_null(item, path)),
+        _ => {}
+    }
+}
+
+/// Checks whether the given account is allowed to see authorization explanations.
+async fn can_debug(
+    identity_service_client: &mut IdentityServiceRef,
+    account_id: Option<String>,
+    claims: HashMap<String, String>,
+) -> bool {
+    let request = tonic::Request::new(AuthorizeInput {
+        account_id,
+        access_request: Some(AccessRequest {
+            access_kind: AccessKindPb::Query as i32,
+            paths: vec![DEBUG_PERMISSION_PATH.to_owned()],
+        }),
+        explain: false,
+        prune: false,
+        claims,
+    });
+
+    match identity_service_client
+        .authorize(request)
+        .instrument(tracing::info_span!("identity_service::authorize"))
+        .await
+    {
+        Ok(output) => output.into_inner().permission_granted,
+        Err(e) => {
+            tracing::error!(error = ?&e, "Authorize failed for debug permission.");
+            false
+        }
+    }
+}
+
+fn denial_value(denial: PathDenial) -> Value {
+    let reason = if denial.reason == ReasonPb::Denied as i32 {
+        "DENIED"
+    } else {
+        "NOT_ALLOWED"
+    };
+
+    value!({
+        "path": denial.path,
+        "reason": reason,
+        "closestAllowedPath": denial.closest_allowed_path,
+        "statementIndex": denial.statement_index,
+    })
 }
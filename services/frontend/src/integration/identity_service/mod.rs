@@ -1,4 +1,5 @@
 pub mod schema;
+pub mod signing_keys;
 
 use identity_service::pb::identity_service_client::IdentityServiceClient;
 use tonic::transport::Channel;
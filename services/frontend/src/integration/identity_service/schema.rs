@@ -1,5 +1,12 @@
-use async_graphql::{Context, Enum, InputObject, Object, ServerError, SimpleObject, ID};
-use identity_service::pb::GetPermissionsInput;
+use async_graphql::{Context, Enum, InputObject, Json, Object, ServerError, SimpleObject, Variables, ID};
+use async_graphql_parser::parse_query;
+use identity_service::pb::path_denial::Reason as ReasonPb;
+use identity_service::pb::{
+    AccessRequest, GetPermissionsInput, PathDenial, PermissionsChange as PermissionsChangeModel, PolicyStatement,
+    Session as SessionModel, SimulatedAuthorization,
+};
+use service_core::resource_access::graphql_interop::parser::from_document_operation;
+use service_core::resource_access::graphql_interop::schema::SchemaInfo;
 use thiserror::Error;
 use tracing_futures::Instrument;
 
@@ -15,20 +22,40 @@ pub struct UserAccount {
 
 #[derive(Clone, SimpleObject)]
 pub struct RenderedPolicyStatement {
+    pub effect: Effect,
     pub access_kind: AccessKind,
     pub paths: Vec<String>,
+    /// Unix timestamp, in seconds, before which the statement does not apply.
+    pub not_before: Option<i64>,
+    /// Unix timestamp, in seconds, after which the statement expires.
+    pub not_after: Option<i64>,
+    /// Whether the statement applies to active accounts only.
+    pub active_accounts_only: bool,
 }
 
 #[derive(Clone, InputObject)]
 pub struct InputPolicyStatement {
+    #[graphql(default_with = "Effect::Allow")]
+    pub effect: Effect,
     pub access_kind: AccessKind,
     pub paths: Vec<String>,
+    pub not_before: Option<i64>,
+    pub not_after: Option<i64>,
+    #[graphql(default)]
+    pub active_accounts_only: bool,
+}
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+pub enum Effect {
+    Allow,
+    Deny,
 }
 
 #[derive(Enum, Copy, Clone, Eq, PartialEq)]
 pub enum AccessKind {
     Query,
     Mutation,
+    Subscription,
 }
 
 #[derive(Clone, SimpleObject)]
@@ -64,6 +91,80 @@ pub enum AccountState {
     Deactivated,
 }
 
+/// An access request to authorize, given either as a GraphQL document or as raw resource paths.
+#[derive(InputObject)]
+pub struct SimulationRequest {
+    pub document: Option<String>,
+    pub operation_name: Option<String>,
+    pub variables: Option<Json<Variables>>,
+    #[graphql(default_with = "AccessKind::Query")]
+    pub access_kind: AccessKind,
+    #[graphql(default)]
+    pub paths: Vec<String>,
+}
+
+#[derive(Clone, SimpleObject)]
+pub struct SimulationResult {
+    pub permission_granted: bool,
+    pub denials: Vec<AuthorizationDenial>,
+    pub granted_paths: Vec<String>,
+}
+
+#[derive(Clone, SimpleObject)]
+pub struct AuthorizationDenial {
+    pub path: String,
+    pub reason: DenialReason,
+    pub closest_allowed_path: Option<String>,
+    pub statement_index: Option<u32>,
+}
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+pub enum DenialReason {
+    NotAllowed,
+    Denied,
+}
+
+#[derive(Clone, SimpleObject)]
+pub struct AccountStateChangedEvent {
+    pub account_id: ID,
+    pub account_state: AccountState,
+}
+
+#[derive(Clone, SimpleObject)]
+pub struct PermissionsChangedEvent {
+    pub account_id: ID,
+    pub changes: Vec<PermissionsChange>,
+}
+
+/// A session of an account, started by authenticating and kept alive by refreshing its tokens.
+#[derive(Clone, SimpleObject)]
+pub struct Session {
+    pub session_id: ID,
+    /// Unix timestamp, in seconds, at which the account authenticated.
+    pub created_at: i64,
+    /// Unix timestamp, in seconds, at which the session last refreshed its tokens.
+    pub last_refreshed_at: i64,
+    /// Unix timestamp, in seconds, after which the session ends unless refreshed.
+    pub expires_at: i64,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    /// Whether the session is the one the request was made in.
+    pub current: bool,
+}
+
+/// The change in the paths matched by the statements with the same effect, access kind and
+/// conditions.
+#[derive(Clone, SimpleObject)]
+pub struct PermissionsChange {
+    pub effect: Effect,
+    pub access_kind: AccessKind,
+    pub not_before: Option<i64>,
+    pub not_after: Option<i64>,
+    pub active_accounts_only: bool,
+    pub added_paths: Vec<String>,
+    pub removed_paths: Vec<String>,
+}
+
 #[derive(Debug, Error)]
 pub enum GraphQLError {
     #[error("Permission denied.")]
@@ -118,22 +219,151 @@ impl UserAccount {
 
         Ok(statements
             .map(|stmt| {
-                use identity_service::pb::policy_statement::AccessKind as ProtobufAccessKind;
+                use identity_service::pb::policy_statement::{
+                    AccessKind as ProtobufAccessKind, Effect as ProtobufEffect,
+                };
                 let access_kind = if stmt.access_kind == ProtobufAccessKind::Mutation as i32 {
                     AccessKind::Mutation
+                } else if stmt.access_kind == ProtobufAccessKind::Subscription as i32 {
+                    AccessKind::Subscription
                 } else {
                     AccessKind::Query
                 };
+                let effect = if stmt.effect == ProtobufEffect::Deny as i32 {
+                    Effect::Deny
+                } else {
+                    Effect::Allow
+                };
 
                 RenderedPolicyStatement {
                     paths: stmt.paths,
                     access_kind,
+                    effect,
+                    not_before: stmt.not_before,
+                    not_after: stmt.not_after,
+                    active_accounts_only: stmt.active_accounts_only,
                 }
             })
             .collect())
     }
 }
 
+impl SimulationRequest {
+    /// Compiles the request into an access request, against the types of the given schema.
+    pub fn into_access_request(self, schema_info: &SchemaInfo) -> Result<AccessRequest, GraphQLError> {
+        let Some(document) = self.document else {
+            return Ok(AccessRequest {
+                access_kind: match self.access_kind {
+                    AccessKind::Query => identity_service::pb::access_request::AccessKind::Query as i32,
+                    AccessKind::Mutation => identity_service::pb::access_request::AccessKind::Mutation as i32,
+                    AccessKind::Subscription => identity_service::pb::access_request::AccessKind::Subscription as i32,
+                },
+                paths: self.paths,
+            });
+        };
+        if !self.paths.is_empty() {
+            return Err(GraphQLError::Operation(
+                "Either a document or paths must be provided, not both.".into(),
+            ));
+        }
+
+        let document = parse_query(document).map_err(|e| GraphQLError::Operation(e.to_string().into()))?;
+        let variables = self.variables.map(|variables| variables.0).unwrap_or_default();
+        from_document_operation(&document, schema_info, &variables, self.operation_name.as_deref())
+            .map(Into::into)
+            .map_err(|e| GraphQLError::Operation(e.to_string().into()))
+    }
+}
+
+impl From<InputPolicyStatement> for PolicyStatement {
+    fn from(statement: InputPolicyStatement) -> Self {
+        use identity_service::pb::policy_statement::{AccessKind as ProtobufAccessKind, Effect as ProtobufEffect};
+
+        PolicyStatement {
+            access_kind: match statement.access_kind {
+                AccessKind::Query => ProtobufAccessKind::Query as i32,
+                AccessKind::Mutation => ProtobufAccessKind::Mutation as i32,
+                AccessKind::Subscription => ProtobufAccessKind::Subscription as i32,
+            },
+            paths: statement.paths,
+            effect: match statement.effect {
+                Effect::Allow => ProtobufEffect::Allow as i32,
+                Effect::Deny => ProtobufEffect::Deny as i32,
+            },
+            not_before: statement.not_before,
+            not_after: statement.not_after,
+            active_accounts_only: statement.active_accounts_only,
+        }
+    }
+}
+
+impl From<SimulatedAuthorization> for SimulationResult {
+    fn from(result: SimulatedAuthorization) -> Self {
+        SimulationResult {
+            permission_granted: result.permission_granted,
+            denials: result.denials.into_iter().map(Into::into).collect(),
+            granted_paths: result.granted_paths,
+        }
+    }
+}
+
+impl From<PathDenial> for AuthorizationDenial {
+    fn from(denial: PathDenial) -> Self {
+        AuthorizationDenial {
+            reason: if denial.reason == ReasonPb::Denied as i32 {
+                DenialReason::Denied
+            } else {
+                DenialReason::NotAllowed
+            },
+            path: denial.path,
+            closest_allowed_path: denial.closest_allowed_path,
+            statement_index: denial.statement_index,
+        }
+    }
+}
+
+impl From<PermissionsChangeModel> for PermissionsChange {
+    fn from(change: PermissionsChangeModel) -> Self {
+        use identity_service::pb::policy_statement::{AccessKind as ProtobufAccessKind, Effect as ProtobufEffect};
+
+        PermissionsChange {
+            effect: if change.effect == ProtobufEffect::Deny as i32 {
+                Effect::Deny
+            } else {
+                Effect::Allow
+            },
+            access_kind: if change.access_kind == ProtobufAccessKind::Mutation as i32 {
+                AccessKind::Mutation
+            } else if change.access_kind == ProtobufAccessKind::Subscription as i32 {
+                AccessKind::Subscription
+            } else {
+                AccessKind::Query
+            },
+            not_before: change.not_before,
+            not_after: change.not_after,
+            active_accounts_only: change.active_accounts_only,
+            added_paths: change.added_paths,
+            removed_paths: change.removed_paths,
+        }
+    }
+}
+
+impl Session {
+    pub fn from_model(session: SessionModel, current_session_id: Option<&str>) -> Self {
+        let non_empty = |value: String| (!value.is_empty()).then_some(value);
+
+        Session {
+            current: current_session_id == Some(session.session_id.as_str()),
+            session_id: session.session_id.into(),
+            created_at: session.created_at,
+            last_refreshed_at: session.last_refreshed_at,
+            expires_at: session.expires_at,
+            user_agent: non_empty(session.user_agent),
+            ip_address: non_empty(session.ip_address),
+        }
+    }
+}
+
 impl From<GraphQLError> for ServerError {
     fn from(e: GraphQLError) -> Self {
         ServerError::new(e.to_string(), None)
@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use identity_service::pb::{GetSigningKeysInput, SigningKey};
+use jsonwebtoken::{Algorithm, DecodingKey};
+use serde::Serialize;
+use tokio::sync::RwLock;
+use tracing_futures::Instrument;
+
+use super::IdentityServiceRef;
+
+/// Minimum time between two refreshes of the key set, so that tokens with made up key IDs cannot
+/// flood the identity service.
+const MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Time the identity service has to return the key set, which is refreshed while holding the lock of
+/// the key set, so that a stuck refresh does not block the requests waiting to verify their tokens.
+const REFRESH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Time between two scheduled refreshes of the key set. New keys have to be published for longer
+/// than this before the identity service starts signing with them.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// The public keys which access tokens are verified with, fetched from the identity service.
+///
+/// # Notes
+///
+/// The key set is refreshed every `REFRESH_INTERVAL` by [`SigningKeySet::refresh_periodically`].
+/// The identity service publishes every key in its directory, but only signs with the one set by
+/// `SIGNING_KEY_ID`, so a new key is rolled out by adding it while `SIGNING_KEY_ID` still names the
+/// current key, and only activated once every frontend has refreshed.
+///
+/// Tokens signed with a key the set does not know also trigger a refresh, at most once every
+/// `MIN_REFRESH_INTERVAL`. Since tokens with made up key IDs share that limit, this is only a
+/// fallback for keys activated too early, and the scheduled refresh is what valid tokens rely on.
+pub struct SigningKeySet {
+    identity_service_client: IdentityServiceRef,
+    state: RwLock<KeySetState>,
+}
+
+#[derive(Default)]
+struct KeySetState {
+    decoding_keys: HashMap<String, (Algorithm, DecodingKey)>,
+    public_keys: Vec<SigningKey>,
+    refreshed_at: Option<Instant>,
+}
+
+/// A JSON Web Key Set (RFC 7517), as served at `/.well-known/jwks.json`.
+#[derive(Debug, Serialize)]
+pub struct Jwks {
+    pub keys: Vec<Jwk>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Jwk {
+    pub kid: String,
+    pub kty: String,
+    pub alg: String,
+    #[serde(rename = "use")]
+    pub key_use: &'static str,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub n: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub e: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub crv: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub x: String,
+}
+
+impl SigningKeySet {
+    pub fn new(identity_service_client: IdentityServiceRef) -> Self {
+        Self {
+            identity_service_client,
+            state: RwLock::new(KeySetState::default()),
+        }
+    }
+
+    /// The algorithm and key which tokens signed with the given key are verified with, if any.
+    pub async fn decoding_key(&self, kid: &str) -> Option<(Algorithm, DecodingKey)> {
+        if let Some(key) = self.state.read().await.decoding_keys.get(kid) {
+            return Some(key.clone());
+        }
+
+        let mut state = self.state.write().await;
+        // Another request may have refreshed the key set while waiting for the lock.
+        if !state.decoding_keys.contains_key(kid) && state.is_stale() {
+            if let Err(e) = self.refresh(&mut state).await {
+                tracing::error!(error = ?&e, "GetSigningKeys failed.");
+            }
+        }
+
+        state.decoding_keys.get(kid).cloned()
+    }
+
+    /// Refreshes the key set every `REFRESH_INTERVAL`, starting right away.
+    pub async fn refresh_periodically(&self) {
+        let mut interval = tokio::time::interval(REFRESH_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.refresh(&mut *self.state.write().await).await {
+                tracing::error!(error = ?&e, "GetSigningKeys failed.");
+            }
+        }
+    }
+
+    pub async fn jwks(&self) -> Result<Jwks, tonic::Status> {
+        {
+            let state = self.state.read().await;
+            if !state.is_stale() {
+                return Ok(state.jwks());
+            }
+        }
+
+        let mut state = self.state.write().await;
+        // Another request may have refreshed the key set while waiting for the lock.
+        if state.is_stale() {
+            let result = self.refresh(&mut state).await;
+            if state.public_keys.is_empty() {
+                result?;
+            }
+        }
+
+        Ok(state.jwks())
+    }
+
+    async fn refresh(&self, state: &mut KeySetState) -> Result<(), tonic::Status> {
+        state.refreshed_at = Some(Instant::now());
+
+        let mut identity_service_client = self.identity_service_client.clone();
+        let get_signing_keys = identity_service_client
+            .get_signing_keys(tonic::Request::new(GetSigningKeysInput {}))
+            .instrument(tracing::info_span!("identity_service::get_signing_keys"));
+        let output = tokio::time::timeout(REFRESH_TIMEOUT, get_signing_keys)
+            .await
+            .map_err(|_| tonic::Status::deadline_exceeded("GetSigningKeys timed out."))??
+            .into_inner();
+
+        state.decoding_keys = output
+            .keys
+            .iter()
+            .filter_map(|key| Some((key.kid.clone(), decoding_key(key)?)))
+            .collect();
+        state.public_keys = output.keys;
+        tracing::info!(kids = ?state.decoding_keys.keys().collect::<Vec<_>>(), "Refreshed signing keys.");
+
+        Ok(())
+    }
+}
+
+impl KeySetState {
+    fn is_stale(&self) -> bool {
+        self.refreshed_at
+            .map_or(true, |refreshed_at| refreshed_at.elapsed() >= MIN_REFRESH_INTERVAL)
+    }
+
+    fn jwks(&self) -> Jwks {
+        Jwks {
+            keys: self.public_keys.iter().cloned().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<SigningKey> for Jwk {
+    fn from(key: SigningKey) -> Self {
+        Jwk {
+            kid: key.kid,
+            kty: key.kty,
+            alg: key.alg,
+            key_use: "sig",
+            n: key.n,
+            e: key.e,
+            crv: key.crv,
+            x: key.x,
+        }
+    }
+}
+
+fn decoding_key(key: &SigningKey) -> Option<(Algorithm, DecodingKey)> {
+    match key.alg.as_str() {
+        "RS256" => Some((Algorithm::RS256, DecodingKey::from_rsa_components(&key.n, &key.e).ok()?)),
+        "EdDSA" => Some((Algorithm::EdDSA, DecodingKey::from_ed_components(&key.x).ok()?)),
+        _ => None,
+    }
+}
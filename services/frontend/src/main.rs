@@ -4,22 +4,30 @@ use std::{env, io};
 use actix_web::{guard, web, App, HttpRequest, HttpResponse, HttpServer, Result};
 use async_graphql::extensions::Tracing;
 use async_graphql::http::{playground_source, GraphQLPlaygroundConfig};
-use async_graphql::{Context, EmptySubscription, Object, Response, Schema, ServerError, ID};
+use async_graphql::{Context, Object, Response, Schema, ServerError, Subscription, ID};
 use async_graphql_actix_web::{GraphQLRequest, GraphQLResponse};
 use frontend::actix_middleware::request_id::RequestIdHeader;
 use frontend::graphql::extension::Authorizer;
 use frontend::integration::identity_service::schema::{
-    AccessKind, AccountState, AuthenticationOutput, CreateAccountOutput, CreateAccountParams,
-    GenerateAccessTokenOutput, GraphQLError, InputPolicyStatement, RenderedPolicyStatement, UserAccount,
+    AccountState, AccountStateChangedEvent, AuthenticationOutput, CreateAccountOutput, CreateAccountParams,
+    GenerateAccessTokenOutput, GraphQLError, InputPolicyStatement, PermissionsChangedEvent, Session, SimulationRequest,
+    SimulationResult, UserAccount,
 };
+use frontend::integration::identity_service::signing_keys::SigningKeySet;
 use frontend::integration::identity_service::IdentityServiceRef;
-use frontend::schema::authorization::Authorization;
-use futures_util::SinkExt;
+use frontend::schema::authorization::{Authorization, TokenValidation};
+use frontend::schema::client::{ClientInfo, ProxyConfig};
+use futures_util::future::{pending, ready};
+use futures_util::{SinkExt, Stream, StreamExt};
+use identity_service::pb::account_event::Event;
 use identity_service::pb::identity_service_client::IdentityServiceClient;
 use identity_service::pb::{
-    AccountAttributes, AuthenticateInput, CreateAccountInput, DescribeAccountInput, GenerateAccessTokenInput,
-    ListAccountsInput, PermissionsDocument, PolicyStatement, UpdateAccountStateInput, UpdatePermissionsInput,
+    AccountAttributes, AccountEvent, AuthenticateInput, CreateAccountInput, DescribeAccountInput,
+    GenerateAccessTokenInput, ListAccountsInput, ListSessionsInput, PermissionsDocument, RevokeRefreshTokensInput,
+    RevokeSessionInput, SimulateAuthorizationInput, UpdateAccountStateInput, UpdatePermissionsInput,
+    WatchAccountEventsInput,
 };
+use service_core::resource_access::graphql_interop::schema::SchemaInfo;
 use service_core::simple_err_map;
 use service_core::telemetry::logging::{init_subscriber, make_subscriber};
 use thiserror::Error;
@@ -31,9 +39,15 @@ pub enum InitServiceError {
     #[error("Environment variable {0} is missing.")]
     MissingEnv(&'static str),
 
+    #[error("Environment variable {0} is invalid.")]
+    InvalidEnv(&'static str),
+
     #[error("Cannot acquire client.")]
     CannotAcquireClient,
 
+    #[error("Cannot read the GraphQL schema: {0}")]
+    InvalidSchema(String),
+
     #[error(transparent)]
     IO(#[from] io::Error),
 }
@@ -45,7 +59,15 @@ async fn main() -> std::result::Result<(), InitServiceError> {
     let subscriber = make_subscriber("frontend", "info");
     init_subscriber(subscriber);
 
-    let schema = create_schema_with_context().await?;
+    let identity_service_client = identity_service_client().await?;
+    let signing_keys = web::Data::new(SigningKeySet::new(identity_service_client.clone()));
+    tokio::spawn({
+        let signing_keys = signing_keys.clone();
+        async move { signing_keys.refresh_periodically().await }
+    });
+    let token_validation = web::Data::new(token_validation()?);
+    let proxy_config = web::Data::new(proxy_config()?);
+    let schema = create_schema_with_context(identity_service_client).await?;
 
     HttpServer::new(move || {
         App::new()
@@ -53,6 +75,9 @@ async fn main() -> std::result::Result<(), InitServiceError> {
             .wrap(tracing_actix_web::TracingLogger::default())
             .configure(configure_service)
             .data(schema.clone())
+            .app_data(signing_keys.clone())
+            .app_data(token_validation.clone())
+            .app_data(proxy_config.clone())
     })
     .bind("0.0.0.0:8080")?
     .run()
@@ -62,6 +87,7 @@ async fn main() -> std::result::Result<(), InitServiceError> {
 
 
 pub fn configure_service(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("/.well-known/jwks.json").route(web::get().to(jwks)));
     cfg.service(
         web::resource("/")
             .route(web::post().to(index))
@@ -71,8 +97,15 @@ pub fn configure_service(cfg: &mut web::ServiceConfig) {
 }
 
 #[tracing::instrument(skip_all)]
-async fn index(schema: web::Data<AppSchema>, http_req: HttpRequest, req: GraphQLRequest) -> GraphQLResponse {
-    let authorization = match Authorization::try_from_req(&http_req) {
+async fn index(
+    schema: web::Data<AppSchema>,
+    signing_keys: web::Data<SigningKeySet>,
+    token_validation: web::Data<TokenValidation>,
+    proxy_config: web::Data<ProxyConfig>,
+    http_req: HttpRequest,
+    req: GraphQLRequest,
+) -> GraphQLResponse {
+    let authorization = match Authorization::try_from_req(&http_req, &signing_keys, &token_validation).await {
         Err(e) => {
             tracing::debug!("Cannot extract authorization data: {}", e);
 
@@ -82,17 +115,66 @@ async fn index(schema: web::Data<AppSchema>, http_req: HttpRequest, req: GraphQL
         }
         Ok(v) => v,
     };
-    let query = req.into_inner().data(authorization);
+    let query = req
+        .into_inner()
+        .data(authorization)
+        .data(ClientInfo::from_req(&http_req, &proxy_config));
     schema.execute(query).await.into()
 }
 
-async fn index_ws(schema: web::Data<AppSchema>, req: HttpRequest, payload: web::Payload) -> Result<HttpResponse> {
+/// Serves subscriptions over websockets. Since browsers cannot set headers on websocket requests, the
+/// access token may also be given as `Authorization` in the payload of the `connection_init` message.
+async fn index_ws(
+    schema: web::Data<AppSchema>,
+    signing_keys: web::Data<SigningKeySet>,
+    token_validation: web::Data<TokenValidation>,
+    proxy_config: web::Data<ProxyConfig>,
+    req: HttpRequest,
+    payload: web::Payload,
+) -> Result<HttpResponse> {
+    use async_graphql::Data;
     use async_graphql_actix_web::GraphQLSubscription;
 
-    let ws_subscription = GraphQLSubscription::new(Schema::clone(&*schema));
+    let header_authorization = Authorization::try_from_req(&req, &signing_keys, &token_validation)
+        .await
+        .map_err(|e| {
+            tracing::debug!("Cannot extract authorization data: {}", e);
+            actix_web::error::ErrorUnauthorized("Permission denied.")
+        })?;
+    let client_info = ClientInfo::from_req(&req, &proxy_config);
+    let ws_subscription = GraphQLSubscription::new(Schema::clone(&*schema)).on_connection_init(|payload| async move {
+        let authorization = match payload.get("Authorization").and_then(|token| token.as_str()) {
+            Some(token) => Some(
+                Authorization::try_from_header(token, &signing_keys, &token_validation)
+                    .await
+                    .map_err(|e| {
+                        tracing::debug!("Cannot extract authorization data: {}", e);
+                        async_graphql::Error::new("Permission denied.")
+                    })?,
+            ),
+            None => header_authorization,
+        };
+
+        let mut data = Data::default();
+        data.insert(authorization);
+        data.insert(client_info);
+        Ok(data)
+    });
     ws_subscription.start(&req, payload)
 }
 
+/// Serves the public keys which access tokens are verified with, for other services to verify them
+/// without asking the identity service.
+async fn jwks(signing_keys: web::Data<SigningKeySet>) -> HttpResponse {
+    match signing_keys.jwks().await {
+        Ok(jwks) => HttpResponse::Ok().json(jwks),
+        Err(e) => {
+            tracing::error!(error = ?&e, "GetSigningKeys failed.");
+            HttpResponse::ServiceUnavailable().finish()
+        }
+    }
+}
+
 async fn index_playground() -> HttpResponse {
     HttpResponse::Ok()
         .content_type("text/html; charset=utf-8")
@@ -102,7 +184,7 @@ async fn index_playground() -> HttpResponse {
 }
 
 #[tracing::instrument]
-pub async fn create_schema_with_context() -> std::result::Result<AppSchema, InitServiceError> {
+async fn identity_service_client() -> std::result::Result<IdentityServiceRef, InitServiceError> {
     const IDENTITY_SERVICE_ENDPOINT_VAR: &str = "IDENTITY_SERVICE_ENDPOINT";
     let identity_service_endpoint = env::var(IDENTITY_SERVICE_ENDPOINT_VAR)
         .map_err(|_| InitServiceError::MissingEnv(IDENTITY_SERVICE_ENDPOINT_VAR))?;
@@ -112,10 +194,63 @@ pub async fn create_schema_with_context() -> std::result::Result<AppSchema, Init
 
     tracing::info!("Created IdentityService client.");
 
-    let schema = Schema::build(Query, Mutation, EmptySubscription)
+    Ok(identity_service_client)
+}
+
+/// The issuer and audience access tokens must be minted by and for, which differ between
+/// environments.
+fn token_validation() -> std::result::Result<TokenValidation, InitServiceError> {
+    const TOKEN_ISSUER_VAR: &str = "TOKEN_ISSUER";
+    const TOKEN_AUDIENCE_VAR: &str = "TOKEN_AUDIENCE";
+    const TOKEN_LEEWAY_VAR: &str = "TOKEN_LEEWAY_SECONDS";
+    const DEFAULT_TOKEN_LEEWAY_SECONDS: u64 = 10;
+
+    let issuer = env::var(TOKEN_ISSUER_VAR).map_err(|_| InitServiceError::MissingEnv(TOKEN_ISSUER_VAR))?;
+    let audience = env::var(TOKEN_AUDIENCE_VAR).map_err(|_| InitServiceError::MissingEnv(TOKEN_AUDIENCE_VAR))?;
+    let leeway = match env::var(TOKEN_LEEWAY_VAR) {
+        Ok(leeway) => leeway
+            .parse()
+            .map_err(|_| InitServiceError::InvalidEnv(TOKEN_LEEWAY_VAR))?,
+        Err(_) => DEFAULT_TOKEN_LEEWAY_SECONDS,
+    };
+
+    Ok(TokenValidation {
+        issuer,
+        audience,
+        leeway,
+    })
+}
+
+/// Whether the client addresses named by forwarded headers are trusted, which is only the case
+/// behind a proxy setting those headers.
+fn proxy_config() -> std::result::Result<ProxyConfig, InitServiceError> {
+    const TRUST_FORWARDED_HEADERS_VAR: &str = "TRUST_FORWARDED_HEADERS";
+
+    let trust_forwarded_headers = match env::var(TRUST_FORWARDED_HEADERS_VAR) {
+        Ok(trust_forwarded_headers) => trust_forwarded_headers
+            .parse()
+            .map_err(|_| InitServiceError::InvalidEnv(TRUST_FORWARDED_HEADERS_VAR))?,
+        Err(_) => false,
+    };
+
+    Ok(ProxyConfig {
+        trust_forwarded_headers,
+    })
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn create_schema_with_context(
+    identity_service_client: IdentityServiceRef,
+) -> std::result::Result<AppSchema, InitServiceError> {
+    // Access requests are compiled against the types of the schema, e.g. for default arguments.
+    let schema_info = SchemaInfo::from_sdl(&Schema::new(Query, Mutation, Subscription).sdl())
+        .map_err(|e| InitServiceError::InvalidSchema(e.to_string()))?;
+
+    let schema = Schema::build(Query, Mutation, Subscription)
         .extension(Authorizer)
         .extension(Tracing)
         .data(identity_service_client)
+        .data(schema_info)
         .finish();
 
     use std::io::Write;
@@ -126,9 +261,10 @@ pub async fn create_schema_with_context() -> std::result::Result<AppSchema, Init
     Ok(schema)
 }
 
-pub type AppSchema = Schema<Query, Mutation, EmptySubscription>;
+pub type AppSchema = Schema<Query, Mutation, Subscription>;
 pub struct Query;
 pub struct Mutation;
+pub struct Subscription;
 
 #[Object]
 impl Query {
@@ -183,6 +319,77 @@ impl Query {
             })
             .expect("malformed response"))
     }
+
+    /// Lists the sessions of an account which neither expired nor were revoked, most recently refreshed
+    /// first.
+    #[tracing::instrument(skip_all)]
+    async fn sessions(&self, ctx: &Context<'_>, account_id: ID) -> std::result::Result<Vec<Session>, GraphQLError> {
+        let mut identity_service_client = ctx.data_unchecked::<IdentityServiceRef>().clone();
+        let current_session_id = ctx
+            .data_unchecked::<Option<Authorization>>()
+            .as_ref()
+            .and_then(|authorization| authorization.claims.sid.clone());
+        let request = tonic::Request::new(ListSessionsInput {
+            account_id: account_id.into(),
+        });
+        let output = identity_service_client
+            .list_sessions(request)
+            .instrument(tracing::info_span!("identity_service::list_sessions"))
+            .await
+            .map_err(|e| match e.code() {
+                Code::InvalidArgument => GraphQLError::Operation("Invalid argument.".into()),
+                Code::NotFound => GraphQLError::Operation("Account not found.".into()),
+                _ => GraphQLError::Internal,
+            })?
+            .into_inner();
+
+        Ok(output
+            .sessions
+            .into_iter()
+            .map(|session| Session::from_model(session, current_session_id.as_deref()))
+            .collect())
+    }
+
+    /// Authorizes the given requests against the permissions of an account, the given policy
+    /// statements, or both, without changing anything. The policy statements take the place of the
+    /// account's own permissions document.
+    #[tracing::instrument(skip_all)]
+    async fn simulate_authorization(
+        &self,
+        ctx: &Context<'_>,
+        account_id: Option<ID>,
+        policy_statements: Option<Vec<InputPolicyStatement>>,
+        requests: Vec<SimulationRequest>,
+    ) -> std::result::Result<Vec<SimulationResult>, GraphQLError> {
+        let schema_info = ctx.data_unchecked::<SchemaInfo>();
+        let access_requests = requests
+            .into_iter()
+            .map(|request| request.into_access_request(schema_info))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let mut identity_service_client = ctx.data_unchecked::<IdentityServiceRef>().clone();
+        let request = tonic::Request::new(SimulateAuthorizationInput {
+            account_id: account_id.map(Into::into),
+            permissions_document: policy_statements.map(|statements| PermissionsDocument {
+                statements: statements.into_iter().map(Into::into).collect(),
+            }),
+            access_requests,
+            // The identity service binds the claims of the simulated account itself.
+            claims: Default::default(),
+        });
+        let output = identity_service_client
+            .simulate_authorization(request)
+            .instrument(tracing::info_span!("identity_service::simulate_authorization"))
+            .await
+            .map_err(|e| match e.code() {
+                Code::InvalidArgument => GraphQLError::Operation(e.message().to_owned().into()),
+                Code::NotFound => GraphQLError::Operation("Account not found.".into()),
+                _ => GraphQLError::Internal,
+            })?
+            .into_inner();
+
+        Ok(output.results.into_iter().map(Into::into).collect())
+    }
 }
 
 #[Object]
@@ -195,7 +402,13 @@ impl Mutation {
         password: String,
     ) -> std::result::Result<AuthenticationOutput, AuthenticateError> {
         let mut identity_service_client = ctx.data_unchecked::<IdentityServiceRef>().clone();
-        let request = tonic::Request::new(AuthenticateInput { email, password });
+        let client_info = ctx.data_unchecked::<ClientInfo>();
+        let request = tonic::Request::new(AuthenticateInput {
+            email,
+            password,
+            user_agent: client_info.user_agent.clone(),
+            ip_address: client_info.ip_address.clone(),
+        });
         let output = identity_service_client
             .authenticate(request)
             .instrument(tracing::info_span!("identity_service::authenticate"))
@@ -294,29 +507,66 @@ impl Mutation {
         Ok(true)
     }
 
+    #[tracing::instrument(skip_all)]
+    async fn revoke_refresh_tokens(
+        &self,
+        ctx: &Context<'_>,
+        account_id: String,
+    ) -> std::result::Result<bool, GraphQLError> {
+        let mut identity_service_client = ctx.data_unchecked::<IdentityServiceRef>().clone();
+        let request = tonic::Request::new(RevokeRefreshTokensInput { account_id });
+        identity_service_client
+            .revoke_refresh_tokens(request)
+            .await
+            .map_err(|e| match e.code() {
+                Code::InvalidArgument => GraphQLError::Operation("Invalid argument.".into()),
+                Code::NotFound => GraphQLError::Operation("Account not found.".into()),
+                _ => GraphQLError::Internal,
+            })?
+            .into_inner();
+
+        Ok(true)
+    }
+
+    /// Revokes a session of an account. Its refresh token stops working right away, while the access
+    /// tokens already issued in it stay valid until they expire.
+    #[tracing::instrument(skip_all)]
+    async fn revoke_session(
+        &self,
+        ctx: &Context<'_>,
+        account_id: String,
+        session_id: String,
+    ) -> std::result::Result<bool, GraphQLError> {
+        let mut identity_service_client = ctx.data_unchecked::<IdentityServiceRef>().clone();
+        let request = tonic::Request::new(RevokeSessionInput { account_id, session_id });
+        identity_service_client
+            .revoke_session(request)
+            .await
+            .map_err(|e| match e.code() {
+                Code::InvalidArgument => GraphQLError::Operation("Invalid argument.".into()),
+                Code::NotFound => GraphQLError::Operation("Session not found.".into()),
+                _ => GraphQLError::Internal,
+            })?
+            .into_inner();
+
+        Ok(true)
+    }
+
     #[tracing::instrument(skip_all)]
     async fn update_permissions(
         &self,
         ctx: &Context<'_>,
         account_id: String,
         policy_statements: Vec<InputPolicyStatement>,
+        #[graphql(default)] normalize: bool,
     ) -> std::result::Result<bool, GraphQLError> {
         let mut identity_service_client = ctx.data_unchecked::<IdentityServiceRef>().clone();
-        // FIXME: Implement proper conversion functions.
         let request = tonic::Request::new(UpdatePermissionsInput {
             account_id,
             permissions_document: Some(PermissionsDocument {
-                statements: policy_statements
-                    .into_iter()
-                    .map(|statement| PolicyStatement {
-                        access_kind: match statement.access_kind {
-                            AccessKind::Query => identity_service::pb::policy_statement::AccessKind::Query as i32,
-                            AccessKind::Mutation => identity_service::pb::policy_statement::AccessKind::Mutation as i32,
-                        },
-                        paths: statement.paths,
-                    })
-                    .collect(),
+                statements: policy_statements.into_iter().map(Into::into).collect(),
             }),
+            normalize,
         });
         identity_service_client
             .update_permissions(request)
@@ -332,6 +582,93 @@ impl Mutation {
     }
 }
 
+#[Subscription]
+impl Subscription {
+    async fn account_state_changed(
+        &self,
+        ctx: &Context<'_>,
+        account_id: ID,
+    ) -> std::result::Result<impl Stream<Item = AccountStateChangedEvent>, GraphQLError> {
+        let account_events = watch_account_events(ctx, account_id).await?;
+
+        Ok(account_events.filter_map(|account_event| {
+            ready(match account_event.event {
+                Some(Event::AccountStateChanged(account_state)) => {
+                    identity_service::pb::AccountState::from_i32(account_state).map(|account_state| {
+                        AccountStateChangedEvent {
+                            account_id: account_event.account_id.into(),
+                            account_state: account_state.into(),
+                        }
+                    })
+                }
+                _ => None,
+            })
+        }))
+    }
+
+    async fn permissions_changed(
+        &self,
+        ctx: &Context<'_>,
+        account_id: ID,
+    ) -> std::result::Result<impl Stream<Item = PermissionsChangedEvent>, GraphQLError> {
+        let account_events = watch_account_events(ctx, account_id).await?;
+
+        Ok(account_events.filter_map(|account_event| {
+            ready(match account_event.event {
+                Some(Event::PermissionsChanged(permissions_changed)) => Some(PermissionsChangedEvent {
+                    account_id: account_event.account_id.into(),
+                    changes: permissions_changed.changes.into_iter().map(Into::into).collect(),
+                }),
+                _ => None,
+            })
+        }))
+    }
+}
+
+/// Opens a stream of the events of an account. The stream ends on the first error from the identity
+/// service, or when the access token of the caller expires, either of which ends the subscription.
+async fn watch_account_events(
+    ctx: &Context<'_>,
+    account_id: ID,
+) -> std::result::Result<impl Stream<Item = AccountEvent>, GraphQLError> {
+    let mut identity_service_client = ctx.data_unchecked::<IdentityServiceRef>().clone();
+    let request = tonic::Request::new(WatchAccountEventsInput {
+        account_id: account_id.0,
+    });
+    let account_events = identity_service_client
+        .watch_account_events(request)
+        .instrument(tracing::info_span!("identity_service::watch_account_events"))
+        .await
+        .map_err(|e| match e.code() {
+            Code::InvalidArgument => GraphQLError::Operation("Invalid argument.".into()),
+            _ => GraphQLError::Internal,
+        })?
+        .into_inner();
+
+    // Subscriptions are only authorized when they start, so they must not outlive the token they were
+    // authorized with, which would otherwise keep them going after it was revoked.
+    let expires_in = ctx
+        .data_unchecked::<Option<Authorization>>()
+        .as_ref()
+        .map(Authorization::expires_in);
+    let token_expired = async move {
+        match expires_in {
+            Some(expires_in) => tokio::time::sleep(expires_in).await,
+            None => pending().await,
+        }
+    };
+
+    Ok(account_events
+        .take_while(|account_event| {
+            if let Err(e) = account_event {
+                tracing::error!("Account events stream failed: {}", e);
+            }
+            ready(account_event.is_ok())
+        })
+        .filter_map(|account_event| ready(account_event.ok()))
+        .take_until(token_expired))
+}
+
 #[derive(Error, Debug)]
 enum ListAccountsError {
     #[error("Operation error.")]
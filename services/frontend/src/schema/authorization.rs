@@ -1,50 +1,97 @@
-use std::env;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use actix_web::HttpRequest;
-use jsonwebtoken::{Algorithm, DecodingKey, TokenData, Validation};
+use jsonwebtoken::{Algorithm, TokenData, Validation};
 use service_core::auth::jwt::Claims;
 use thiserror::Error;
 
+use crate::integration::identity_service::signing_keys::SigningKeySet;
+
 pub struct Authorization {
     pub claims: Claims,
 }
 
+/// What access tokens are checked against besides their signature, so that tokens minted by other
+/// environments, or for other audiences, are rejected.
+#[derive(Debug, Clone)]
+pub struct TokenValidation {
+    /// The `iss` claim, i.e. the identity service environment minting the tokens.
+    pub issuer: String,
+    /// The `aud` claim, i.e. this frontend.
+    pub audience: String,
+    /// Clock skew tolerated when checking `exp` and `nbf`, in seconds.
+    pub leeway: u64,
+}
+
 #[derive(Debug, Error)]
 pub enum ExtractAuthorizationError {
-    #[error("Service has invalid secret.")]
-    InvalidSecret,
+    #[error("Token is signed with unknown key {0}.")]
+    UnknownKey(String),
 
     #[error("Invalid token.")]
     InvalidToken,
 }
 
 impl Authorization {
-    pub fn try_from_req(req: &HttpRequest) -> Result<Option<Self>, ExtractAuthorizationError> {
+    pub async fn try_from_req(
+        req: &HttpRequest,
+        signing_keys: &SigningKeySet,
+        token_validation: &TokenValidation,
+    ) -> Result<Option<Self>, ExtractAuthorizationError> {
         if let Some(token) = req.headers().get("Authorization") {
             let token = token.to_str().unwrap_or_default();
-            if !token.starts_with("Bearer ") {
-                return Err(ExtractAuthorizationError::InvalidToken);
-            }
-
-            let secret = jwt_secret().ok_or(ExtractAuthorizationError::InvalidSecret)?;
-            let secret = DecodingKey::from_base64_secret(secret.as_str())
-                .map_err(|_| ExtractAuthorizationError::InvalidSecret)?;
-            let token = token.trim_start_matches("Bearer ");
-            println!("token = {token}");
-            let token_data: TokenData<Claims> =
-                jsonwebtoken::decode(token, &secret, &Validation::new(Algorithm::HS512)).map_err(|e| {
-                    log::error!("Failed decoding token: {:?}", e);
-                    ExtractAuthorizationError::InvalidToken
-                })?;
-            return Ok(Some(Self {
-                claims: token_data.claims,
-            }));
+            return Self::try_from_header(token, signing_keys, token_validation)
+                .await
+                .map(Some);
         }
 
         Ok(None)
     }
+
+    /// Extracts the authorization data from the value of an `Authorization` header, i.e. a bearer
+    /// token, verified with the key named by its `kid`.
+    pub async fn try_from_header(
+        token: &str,
+        signing_keys: &SigningKeySet,
+        token_validation: &TokenValidation,
+    ) -> Result<Self, ExtractAuthorizationError> {
+        let token = token
+            .strip_prefix("Bearer ")
+            .ok_or(ExtractAuthorizationError::InvalidToken)?;
+        let kid = jsonwebtoken::decode_header(token)
+            .ok()
+            .and_then(|header| header.kid)
+            .ok_or(ExtractAuthorizationError::InvalidToken)?;
+        let (algorithm, key) = signing_keys
+            .decoding_key(&kid)
+            .await
+            .ok_or(ExtractAuthorizationError::UnknownKey(kid))?;
+        let token_data: TokenData<Claims> = jsonwebtoken::decode(token, &key, &token_validation.validation(algorithm))
+            .map_err(|e| {
+                log::error!("Failed decoding token: {:?}", e);
+                ExtractAuthorizationError::InvalidToken
+            })?;
+
+        Ok(Self {
+            claims: token_data.claims,
+        })
+    }
+
+    /// Time left until the access token expires.
+    pub fn expires_in(&self) -> Duration {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        Duration::from_secs(self.claims.exp as u64).saturating_sub(now)
+    }
 }
 
-fn jwt_secret() -> Option<String> {
-    env::var("ACCESS_TOKEN_SECRET").ok()
+impl TokenValidation {
+    fn validation(&self, algorithm: Algorithm) -> Validation {
+        let mut validation = Validation::new(algorithm);
+        validation.set_issuer(&[&self.issuer]);
+        validation.set_audience(&[&self.audience]);
+        validation.set_required_spec_claims(&["exp", "nbf", "iss", "aud"]);
+        validation.validate_nbf = true;
+        validation.leeway = self.leeway;
+        validation
+    }
 }
@@ -0,0 +1,37 @@
+use actix_web::HttpRequest;
+
+/// Details about the client which sent a request, recorded with the sessions it starts.
+#[derive(Clone, Debug, Default)]
+pub struct ClientInfo {
+    pub user_agent: String,
+    pub ip_address: String,
+}
+
+/// Whether requests reach the frontend through a trusted proxy, e.g. a load balancer, which names the
+/// client in the `Forwarded` or `X-Forwarded-For` header of every request.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ProxyConfig {
+    pub trust_forwarded_headers: bool,
+}
+
+impl ClientInfo {
+    pub fn from_req(req: &HttpRequest, proxy_config: &ProxyConfig) -> Self {
+        let user_agent = req
+            .headers()
+            .get("User-Agent")
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+        // Forwarded headers are set by the client unless a trusted proxy overwrites them, so without
+        // one only the address of the connection itself is recorded.
+        let ip_address = if proxy_config.trust_forwarded_headers {
+            req.connection_info().realip_remote_addr().map(str::to_owned)
+        } else {
+            req.peer_addr().map(|addr| addr.ip().to_string())
+        };
+
+        Self {
+            user_agent: user_agent.to_owned(),
+            ip_address: ip_address.unwrap_or_default(),
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::account_group::{AccountGroup, GroupStoreError, GroupsRepository, ListGroupsPage};
+use crate::utils::ddb_table::{DdbTable, DdbTableError, ThreadSafeDdbClient};
+
+
+pub struct DdbGroupsRepository<T: ThreadSafeDdbClient> {
+    groups_table: DdbTable<T>,
+}
+
+impl<T: ThreadSafeDdbClient> DdbGroupsRepository<T> {
+    pub fn new(ddb: T, groups_table_name: impl Into<String>) -> Self {
+        Self {
+            groups_table: DdbTable::new(ddb, groups_table_name, "GroupId"),
+        }
+    }
+}
+
+#[async_trait]
+impl<T: ThreadSafeDdbClient> GroupsRepository for DdbGroupsRepository<T> {
+    async fn create_group(&self, group: &AccountGroup) -> Result<(), GroupStoreError> {
+        Ok(self.groups_table.create(group).await?)
+    }
+
+    async fn get_group(&self, group_id: &Uuid) -> Result<AccountGroup, GroupStoreError> {
+        Ok(self.groups_table.get(group_id).await?)
+    }
+
+    async fn list_groups(
+        &self,
+        starting_token: Option<Uuid>,
+        page_size: u32,
+    ) -> Result<ListGroupsPage, GroupStoreError> {
+        let (groups, next_token) = self.groups_table.list(starting_token, page_size).await?;
+        Ok(ListGroupsPage { groups, next_token })
+    }
+
+    async fn update_group(&self, group: &AccountGroup) -> Result<(), GroupStoreError> {
+        Ok(self.groups_table.update(group).await?)
+    }
+
+    async fn delete_group(&self, group_id: &Uuid) -> Result<(), GroupStoreError> {
+        Ok(self.groups_table.delete(group_id).await?)
+    }
+}
+
+impl From<DdbTableError> for GroupStoreError {
+    fn from(err: DdbTableError) -> Self {
+        match err {
+            DdbTableError::NotFound => GroupStoreError::NotFound,
+            DdbTableError::Serde(e) => GroupStoreError::Serde(e),
+            DdbTableError::Other(e) => GroupStoreError::Other(e),
+        }
+    }
+}
@@ -0,0 +1,6 @@
+pub mod ddb_repository;
+pub mod repository;
+pub mod types;
+
+pub use repository::{GroupStoreError, GroupsRepository, ListGroupsPage};
+pub use types::AccountGroup;
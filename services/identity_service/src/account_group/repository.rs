@@ -0,0 +1,47 @@
+use std::error::Error;
+
+use async_trait::async_trait;
+use thiserror::Error;
+use uuid::Uuid;
+
+use super::AccountGroup;
+
+
+#[derive(Debug, Error)]
+pub enum GroupStoreError {
+    #[error("Group not found.")]
+    NotFound,
+
+    #[error(transparent)]
+    Serde(serde_ddb::Error),
+
+    #[error(transparent)]
+    Other(#[from] Box<dyn Error>),
+}
+
+
+/// A page of account groups, together with the token of the next page, if any.
+#[derive(Clone, Debug, Default)]
+pub struct ListGroupsPage {
+    pub groups: Vec<AccountGroup>,
+    pub next_token: Option<Uuid>,
+}
+
+
+#[async_trait]
+pub trait GroupsRepository {
+    async fn create_group(&self, group: &AccountGroup) -> Result<(), GroupStoreError>;
+
+    async fn get_group(&self, group_id: &Uuid) -> Result<AccountGroup, GroupStoreError>;
+
+    async fn list_groups(
+        &self,
+        starting_token: Option<Uuid>,
+        page_size: u32,
+    ) -> Result<ListGroupsPage, GroupStoreError>;
+
+    /// Replaces an existing group. Fails with `NotFound` if there is no group with the same ID.
+    async fn update_group(&self, group: &AccountGroup) -> Result<(), GroupStoreError>;
+
+    async fn delete_group(&self, group_id: &Uuid) -> Result<(), GroupStoreError>;
+}
@@ -0,0 +1,65 @@
+use identity_service::pb::AccountGroup as AccountGroupModel;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::user_account::PermissionsDocument;
+
+/// A group of accounts (e.g. the students of a class), whose permissions document is inherited by
+/// all of its members.
+///
+/// Accounts hold the IDs of the groups they belong to, so changes to the permissions of a group
+/// apply to all of its members at their next authorization.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct AccountGroup {
+    pub group_id: Uuid,
+
+    pub name: String,
+
+    #[serde(default)]
+    pub description: String,
+
+    #[serde(default)]
+    pub permissions_document: PermissionsDocument,
+}
+
+
+impl From<AccountGroup> for AccountGroupModel {
+    fn from(val: AccountGroup) -> AccountGroupModel {
+        AccountGroupModel {
+            group_id: val.group_id.to_hyphenated().to_string(),
+            name: val.name,
+            description: val.description,
+            permissions_document: Some(val.permissions_document.into()),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use aws_sdk_dynamodb::model::AttributeValue;
+
+    use super::*;
+
+    #[test]
+    fn deserializes_without_optional_fields() {
+        let mut doc = HashMap::new();
+        doc.insert(
+            "GroupId".to_string(),
+            AttributeValue::S(Uuid::nil().to_hyphenated().to_string()),
+        );
+        doc.insert("Name".to_string(), AttributeValue::S("CS-2026 students".to_string()));
+
+        let expected = AccountGroup {
+            group_id: Uuid::nil(),
+            name: "CS-2026 students".to_string(),
+            ..Default::default()
+        };
+        let actual = serde_ddb::from_hashmap::<AccountGroup, _>(doc).unwrap();
+
+        assert_eq!(expected, actual);
+    }
+}
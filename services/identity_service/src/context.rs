@@ -1,25 +1,73 @@
 use core::fmt;
 use std::env;
 use std::str::FromStr;
+use std::time::Duration;
 
 use service_core::ddb::Adapter;
+use service_core::resource_access::graphql_interop::schema::SchemaInfo;
+
+use crate::utils::signing_keys::SigningKeys;
+
+/// How long compiled permissions are cached, unless `PERMISSIONS_CACHE_TTL_SECONDS` is set.
+const DEFAULT_PERMISSIONS_CACHE_TTL_SECONDS: u64 = 60;
+
+/// How long access tokens are valid, unless `ACCESS_TOKEN_TTL_SECONDS` is set.
+const DEFAULT_ACCESS_TOKEN_TTL_SECONDS: u64 = 60;
+
+/// How long refresh tokens stay valid without being used, unless `REFRESH_TOKEN_TTL_SECONDS` is set.
+const DEFAULT_REFRESH_TOKEN_TTL_SECONDS: u64 = 10 * 60 * 60;
 
 #[derive(Debug, Clone, Copy)]
 pub(crate) enum ContextKey {
     DynamoDbEndpoint,
     AccountsTableName,
-    AccessTokenSecret,
+    PoliciesTableName,
+    GroupsTableName,
+    SigningKeysDir,
+    SigningKeyId,
+    TokenIssuer,
+    TokenAudience,
+    AccessTokenTtl,
+    RefreshTokenTtl,
     RefreshTokenSecret,
+    RefreshTokenStore,
     RefreshTokenCache,
+    RefreshTokensTableName,
+    PermissionsCacheTtl,
+    EmbedAccessScopes,
+    GraphQLSchemaPath,
 }
 
 #[derive(Debug)]
 pub(crate) struct Context {
     pub dynamodb_adapter: Adapter,
     pub accounts_table_name: String,
-    pub access_token_secret: String,
+    pub policies_table_name: String,
+    pub groups_table_name: String,
+    pub signing_keys: SigningKeys,
+    /// The `iss` claim of access tokens, which should differ between environments.
+    pub token_issuer: String,
+    /// The `aud` claim of access tokens, i.e. the frontend they are meant for.
+    pub token_audience: String,
+    pub access_token_ttl: Duration,
+    pub refresh_token_ttl: Duration,
     pub refresh_token_secret: String,
-    pub refresh_token_cache: String,
+    pub refresh_token_store: RefreshTokenStoreKind,
+    pub permissions_cache_ttl: Duration,
+    pub embed_access_scopes: bool,
+    /// The GraphQL schema of the frontend, which permission paths are validated against when set.
+    pub schema_info: Option<SchemaInfo>,
+}
+
+/// Backend which refresh tokens are kept in, chosen by `REFRESH_TOKEN_STORE`.
+#[derive(Debug, Clone)]
+pub(crate) enum RefreshTokenStoreKind {
+    /// Only meant for tests and local development, since tokens are not shared between replicas.
+    InMemory,
+    /// The URL of the memcache server.
+    Memcache(String),
+    /// The name of the DynamoDB table.
+    DynamoDb(String),
 }
 
 impl fmt::Display for ContextKey {
@@ -27,9 +75,21 @@ impl fmt::Display for ContextKey {
         match *self {
             Self::DynamoDbEndpoint => write!(f, "DYNAMODB_ENDPOINT"),
             Self::AccountsTableName => write!(f, "ACCOUNTS_TABLE_NAME"),
-            Self::AccessTokenSecret => write!(f, "ACCESS_TOKEN_SECRET"),
+            Self::PoliciesTableName => write!(f, "POLICIES_TABLE_NAME"),
+            Self::GroupsTableName => write!(f, "GROUPS_TABLE_NAME"),
+            Self::SigningKeysDir => write!(f, "SIGNING_KEYS_DIR"),
+            Self::SigningKeyId => write!(f, "SIGNING_KEY_ID"),
+            Self::TokenIssuer => write!(f, "TOKEN_ISSUER"),
+            Self::TokenAudience => write!(f, "TOKEN_AUDIENCE"),
+            Self::AccessTokenTtl => write!(f, "ACCESS_TOKEN_TTL_SECONDS"),
+            Self::RefreshTokenTtl => write!(f, "REFRESH_TOKEN_TTL_SECONDS"),
             Self::RefreshTokenSecret => write!(f, "REFRESH_TOKEN_SECRET"),
+            Self::RefreshTokenStore => write!(f, "REFRESH_TOKEN_STORE"),
             Self::RefreshTokenCache => write!(f, "REFRESH_TOKEN_CACHE"),
+            Self::RefreshTokensTableName => write!(f, "REFRESH_TOKENS_TABLE_NAME"),
+            Self::PermissionsCacheTtl => write!(f, "PERMISSIONS_CACHE_TTL_SECONDS"),
+            Self::EmbedAccessScopes => write!(f, "EMBED_ACCESS_SCOPES"),
+            Self::GraphQLSchemaPath => write!(f, "GRAPHQL_SCHEMA_PATH"),
         }
     }
 }
@@ -50,12 +110,51 @@ impl Context {
         };
 
         let client = aws_sdk_dynamodb::Client::from_conf(dynamodb_config);
+        let permissions_cache_ttl = Context::key(&ContextKey::PermissionsCacheTtl)
+            .map(|ttl| ttl.parse().expect("invalid permissions cache TTL"))
+            .unwrap_or(DEFAULT_PERMISSIONS_CACHE_TTL_SECONDS);
+        let access_token_ttl = Context::key(&ContextKey::AccessTokenTtl)
+            .map(|ttl| ttl.parse().expect("invalid access token TTL"))
+            .unwrap_or(DEFAULT_ACCESS_TOKEN_TTL_SECONDS);
+        let refresh_token_ttl = Context::key(&ContextKey::RefreshTokenTtl)
+            .map(|ttl| ttl.parse().expect("invalid refresh token TTL"))
+            .unwrap_or(DEFAULT_REFRESH_TOKEN_TTL_SECONDS);
+        let schema_info = Context::key(&ContextKey::GraphQLSchemaPath).map(|path| {
+            log::info!("Validating permission paths against the GraphQL schema at {}.", &path);
+            let sdl = std::fs::read_to_string(&path).expect("failed reading the GraphQL schema");
+            SchemaInfo::from_sdl(&sdl).expect("invalid GraphQL schema")
+        });
+        let signing_keys = SigningKeys::from_dir(
+            Context::key(&ContextKey::SigningKeysDir).unwrap(),
+            Context::key(&ContextKey::SigningKeyId).as_deref(),
+        )
+        .expect("failed loading the signing keys");
+        log::info!("Loaded signing keys {:?}.", &signing_keys);
+        let refresh_token_store = match Context::key(&ContextKey::RefreshTokenStore).as_deref() {
+            None | Some("memcache") => {
+                RefreshTokenStoreKind::Memcache(Context::key(&ContextKey::RefreshTokenCache).unwrap())
+            }
+            Some("dynamodb") => {
+                RefreshTokenStoreKind::DynamoDb(Context::key(&ContextKey::RefreshTokensTableName).unwrap())
+            }
+            Some("memory") => RefreshTokenStoreKind::InMemory,
+            Some(store) => panic!("unknown refresh token store {}", store),
+        };
         Context {
             dynamodb_adapter: client.into(),
             accounts_table_name: Context::key(&ContextKey::AccountsTableName).unwrap(),
-            access_token_secret: Context::key(&ContextKey::AccessTokenSecret).unwrap(),
+            policies_table_name: Context::key(&ContextKey::PoliciesTableName).unwrap(),
+            groups_table_name: Context::key(&ContextKey::GroupsTableName).unwrap(),
+            signing_keys,
+            token_issuer: Context::key(&ContextKey::TokenIssuer).unwrap(),
+            token_audience: Context::key(&ContextKey::TokenAudience).unwrap(),
+            access_token_ttl: Duration::from_secs(access_token_ttl),
+            refresh_token_ttl: Duration::from_secs(refresh_token_ttl),
             refresh_token_secret: Context::key(&ContextKey::RefreshTokenSecret).unwrap(),
-            refresh_token_cache: Context::key(&ContextKey::RefreshTokenCache).unwrap(),
+            refresh_token_store,
+            permissions_cache_ttl: Duration::from_secs(permissions_cache_ttl),
+            embed_access_scopes: Context::key(&ContextKey::EmbedAccessScopes).map_or(false, |embed| embed == "true"),
+            schema_info,
         }
     }
 
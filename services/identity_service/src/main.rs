@@ -3,19 +3,33 @@
 
 extern crate core;
 
+mod account_group;
 mod context;
+mod managed_policy;
 mod operations;
 mod permissions;
+mod refresh_token;
 mod user_account;
 mod utils;
 
+use std::sync::Arc;
+use std::time::Duration;
+
 use context::Context;
 use identity_service::pb::identity_service_server::{IdentityService, IdentityServiceServer};
 use identity_service::pb::{
-    AuthenticateInput, AuthenticateOutput, AuthorizeInput, AuthorizeOutput, CreateAccountInput, CreateAccountOutput,
-    DescribeAccountInput, DescribeAccountOutput, GenerateAccessTokenInput, GenerateAccessTokenOutput,
-    GetPermissionsInput, GetPermissionsOutput, ListAccountsInput, ListAccountsOutput, UpdateAccountStateInput,
-    UpdateAccountStateOutput, UpdatePermissionsInput, UpdatePermissionsOutput,
+    AddGroupMemberInput, AddGroupMemberOutput, AttachPolicyInput, AttachPolicyOutput, AuthenticateInput,
+    AuthenticateOutput, AuthorizeInput, AuthorizeOutput, CreateAccountInput, CreateAccountOutput, CreateGroupInput,
+    CreateGroupOutput, CreatePolicyInput, CreatePolicyOutput, DeleteGroupInput, DeleteGroupOutput, DeletePolicyInput,
+    DeletePolicyOutput, DescribeAccountInput, DescribeAccountOutput, DescribeGroupInput, DescribeGroupOutput,
+    DescribePolicyInput, DescribePolicyOutput, DetachPolicyInput, DetachPolicyOutput, GenerateAccessTokenInput,
+    GenerateAccessTokenOutput, GetPermissionsInput, GetPermissionsOutput, GetSigningKeysInput, GetSigningKeysOutput,
+    ListAccountsInput, ListAccountsOutput, ListGroupsInput, ListGroupsOutput, ListPoliciesInput, ListPoliciesOutput,
+    ListSessionsInput, ListSessionsOutput, NormalizePermissionsInput, NormalizePermissionsOutput,
+    RemoveGroupMemberInput, RemoveGroupMemberOutput, RevokeRefreshTokensInput, RevokeRefreshTokensOutput,
+    RevokeSessionInput, RevokeSessionOutput, SimulateAuthorizationInput, SimulateAuthorizationOutput,
+    UpdateAccountStateInput, UpdateAccountStateOutput, UpdateGroupInput, UpdateGroupOutput, UpdatePermissionsInput,
+    UpdatePermissionsOutput, UpdatePolicyInput, UpdatePolicyOutput, WatchAccountEventsInput,
 };
 use log::LevelFilter;
 use memcache::Url;
@@ -30,22 +44,72 @@ use thiserror::Error;
 use tonic::transport::Server;
 use tonic::{Request, Response, Status};
 
-use crate::context::ContextKey;
+use crate::account_group::ddb_repository::DdbGroupsRepository;
+use crate::account_group::GroupsRepository;
+use crate::context::{ContextKey, RefreshTokenStoreKind};
+use crate::managed_policy::ddb_repository::DdbPoliciesRepository;
+use crate::managed_policy::PoliciesRepository;
+use crate::operations::add_group_member::add_group_member;
+use crate::operations::attach_policy::attach_policy;
 use crate::operations::authenticate::authenticate;
+use crate::operations::create_group::create_group;
+use crate::operations::create_policy::create_policy;
+use crate::operations::delete_group::delete_group;
+use crate::operations::delete_policy::delete_policy;
+use crate::operations::describe_group::describe_group;
+use crate::operations::describe_policy::describe_policy;
+use crate::operations::detach_policy::detach_policy;
 use crate::operations::generate_access_token::generate_access_token;
+use crate::operations::get_signing_keys::get_signing_keys;
+use crate::operations::list_groups::list_groups;
+use crate::operations::list_policies::list_policies;
+use crate::operations::list_sessions::list_sessions;
+use crate::operations::normalize_permissions::normalize_permissions;
+use crate::operations::remove_group_member::remove_group_member;
+use crate::operations::revoke_refresh_tokens::revoke_refresh_tokens;
+use crate::operations::revoke_session::revoke_session;
+use crate::operations::simulate_authorization::simulate_authorization;
 use crate::operations::update_account_state::update_account_state;
+use crate::operations::update_group::update_group;
+use crate::operations::update_policy::update_policy;
+use crate::operations::watch_account_events::{watch_account_events, AccountEventStream};
+use crate::refresh_token::ddb_store::DdbRefreshTokenStore;
+use crate::refresh_token::memcache_store::MemcacheRefreshTokenStore;
+use crate::refresh_token::memory_store::InMemoryRefreshTokenStore;
+use crate::refresh_token::RefreshTokenStore;
 use crate::user_account::ddb_repository::DdbAccountsRepository;
 use crate::user_account::AccountsRepository;
+use crate::utils::account_events::AccountEvents;
 use crate::utils::memcache::MemcacheConnPool;
+use crate::utils::permissions_cache::PermissionsCache;
+
+const PERMISSIONS_CACHE_REPORT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Number of account events kept for the subscribers lagging behind.
+const ACCOUNT_EVENTS_CAPACITY: usize = 1024;
 
 trait ThreadSafeAccountsRepository: AccountsRepository + Send + Sync {}
 impl<T: AccountsRepository + Send + Sync> ThreadSafeAccountsRepository for T {}
 
+trait ThreadSafePoliciesRepository: PoliciesRepository + Send + Sync {}
+impl<T: PoliciesRepository + Send + Sync> ThreadSafePoliciesRepository for T {}
 
-struct IdentityServiceImpl<T: ThreadSafeAccountsRepository> {
+trait ThreadSafeGroupsRepository: GroupsRepository + Send + Sync {}
+impl<T: GroupsRepository + Send + Sync> ThreadSafeGroupsRepository for T {}
+
+
+struct IdentityServiceImpl<
+    T: ThreadSafeAccountsRepository,
+    P: ThreadSafePoliciesRepository,
+    G: ThreadSafeGroupsRepository,
+> {
     pub ctx: Context,
-    pub refresh_token_cache: MemcacheConnPool,
+    pub refresh_token_store: Box<dyn RefreshTokenStore>,
     pub accounts_repository: T,
+    pub policies_repository: P,
+    pub groups_repository: G,
+    pub permissions_cache: Arc<PermissionsCache>,
+    pub account_events: AccountEvents,
 }
 
 #[derive(Debug, Error)]
@@ -60,24 +124,53 @@ enum ServiceInitError {
     ConnectionPool(r2d2::Error),
 }
 
-impl<T: ThreadSafeAccountsRepository> IdentityServiceImpl<T> {
-    fn new(ctx: Context, accounts_repository: T) -> Result<Self, ServiceInitError> {
-        let endpoint = Url::parse(ctx.refresh_token_cache.as_ref())
-            .map_err(|_| ServiceInitError::InvalidUrl(ctx.refresh_token_cache.clone()))?;
-        let connection_manager = memcache::ConnectionManager::new(endpoint);
-        let refresh_token_cache =
-            MemcacheConnPool::new(connection_manager).map_err(ServiceInitError::ConnectionPool)?;
+impl<T: ThreadSafeAccountsRepository, P: ThreadSafePoliciesRepository, G: ThreadSafeGroupsRepository>
+    IdentityServiceImpl<T, P, G>
+{
+    fn new(
+        ctx: Context,
+        accounts_repository: T,
+        policies_repository: P,
+        groups_repository: G,
+        permissions_cache: Arc<PermissionsCache>,
+        account_events: AccountEvents,
+    ) -> Result<Self, ServiceInitError> {
+        let refresh_token_store: Box<dyn RefreshTokenStore> = match &ctx.refresh_token_store {
+            RefreshTokenStoreKind::InMemory => {
+                log::warn!("Keeping refresh tokens in memory, they are lost on restart.");
+                Box::new(InMemoryRefreshTokenStore::default())
+            }
+            RefreshTokenStoreKind::Memcache(url) => {
+                let endpoint = Url::parse(url).map_err(|_| ServiceInitError::InvalidUrl(url.clone()))?;
+                let connection_manager = memcache::ConnectionManager::new(endpoint);
+                let pool = MemcacheConnPool::new(connection_manager).map_err(ServiceInitError::ConnectionPool)?;
+                Box::new(MemcacheRefreshTokenStore::new(pool))
+            }
+            RefreshTokenStoreKind::DynamoDb(table_name) => Box::new(DdbRefreshTokenStore::new(
+                ctx.dynamodb_adapter.clone(),
+                table_name.clone(),
+            )),
+        };
 
         Ok(Self {
             ctx,
-            refresh_token_cache,
+            refresh_token_store,
             accounts_repository,
+            policies_repository,
+            groups_repository,
+            permissions_cache,
+            account_events,
         })
     }
 }
 
 #[tonic::async_trait]
-impl<T: 'static + ThreadSafeAccountsRepository> IdentityService for IdentityServiceImpl<T> {
+impl<
+        T: 'static + ThreadSafeAccountsRepository,
+        P: 'static + ThreadSafePoliciesRepository,
+        G: 'static + ThreadSafeGroupsRepository,
+    > IdentityService for IdentityServiceImpl<T, P, G>
+{
     async fn create_account(
         &self,
         request: Request<CreateAccountInput>,
@@ -109,7 +202,23 @@ impl<T: 'static + ThreadSafeAccountsRepository> IdentityService for IdentityServ
         &self,
         request: Request<UpdatePermissionsInput>,
     ) -> Result<Response<UpdatePermissionsOutput>, Status> {
-        update_permissions(&self.ctx, &self.ctx.dynamodb_adapter, request.get_ref())
+        update_permissions(
+            &self.ctx,
+            &self.ctx.dynamodb_adapter,
+            &self.permissions_cache,
+            &self.account_events,
+            request.get_ref(),
+        )
+        .await
+        .map(Response::new)
+        .map_err(|err| err.into())
+    }
+
+    async fn normalize_permissions(
+        &self,
+        request: Request<NormalizePermissionsInput>,
+    ) -> Result<Response<NormalizePermissionsOutput>, Status> {
+        normalize_permissions(&self.ctx, request.into_inner())
             .await
             .map(Response::new)
             .map_err(|err| err.into())
@@ -119,7 +228,63 @@ impl<T: 'static + ThreadSafeAccountsRepository> IdentityService for IdentityServ
         &self,
         request: Request<UpdateAccountStateInput>,
     ) -> Result<Response<UpdateAccountStateOutput>, Status> {
-        update_account_state(&self.ctx, &self.ctx.dynamodb_adapter, request.into_inner())
+        update_account_state(
+            &self.ctx,
+            &self.ctx.dynamodb_adapter,
+            &self.permissions_cache,
+            self.refresh_token_store.as_ref(),
+            &self.account_events,
+            request.into_inner(),
+        )
+        .await
+        .map(Response::new)
+        .map_err(|err| err.into())
+    }
+
+    async fn revoke_refresh_tokens(
+        &self,
+        request: Request<RevokeRefreshTokensInput>,
+    ) -> Result<Response<RevokeRefreshTokensOutput>, Status> {
+        revoke_refresh_tokens(
+            &self.ctx,
+            &self.ctx.dynamodb_adapter,
+            self.refresh_token_store.as_ref(),
+            request.get_ref(),
+        )
+        .await
+        .map(Response::new)
+        .map_err(|err| err.into())
+    }
+
+    async fn list_sessions(&self, request: Request<ListSessionsInput>) -> Result<Response<ListSessionsOutput>, Status> {
+        list_sessions(
+            &self.ctx,
+            &self.ctx.dynamodb_adapter,
+            self.refresh_token_store.as_ref(),
+            request.get_ref(),
+        )
+        .await
+        .map(Response::new)
+        .map_err(|err| err.into())
+    }
+
+    async fn revoke_session(
+        &self,
+        request: Request<RevokeSessionInput>,
+    ) -> Result<Response<RevokeSessionOutput>, Status> {
+        revoke_session(self.refresh_token_store.as_ref(), request.get_ref())
+            .await
+            .map(Response::new)
+            .map_err(|err| err.into())
+    }
+
+    type WatchAccountEventsStream = AccountEventStream;
+
+    async fn watch_account_events(
+        &self,
+        request: Request<WatchAccountEventsInput>,
+    ) -> Result<Response<Self::WatchAccountEventsStream>, Status> {
+        watch_account_events(&self.account_events, request.get_ref())
             .await
             .map(Response::new)
             .map_err(|err| err.into())
@@ -136,10 +301,34 @@ impl<T: 'static + ThreadSafeAccountsRepository> IdentityService for IdentityServ
     }
 
     async fn authorize(&self, request: Request<AuthorizeInput>) -> Result<Response<AuthorizeOutput>, Status> {
-        authorize(&self.ctx, &self.ctx.dynamodb_adapter, request.get_ref())
-            .await
-            .map(Response::new)
-            .map_err(|err| err.into())
+        authorize(
+            &self.ctx,
+            &self.ctx.dynamodb_adapter,
+            &self.policies_repository,
+            &self.groups_repository,
+            &self.permissions_cache,
+            request.get_ref(),
+        )
+        .await
+        .map(Response::new)
+        .map_err(|err| err.into())
+    }
+
+    async fn simulate_authorization(
+        &self,
+        request: Request<SimulateAuthorizationInput>,
+    ) -> Result<Response<SimulateAuthorizationOutput>, Status> {
+        simulate_authorization(
+            &self.ctx,
+            &self.ctx.dynamodb_adapter,
+            &self.accounts_repository,
+            &self.policies_repository,
+            &self.groups_repository,
+            request.get_ref(),
+        )
+        .await
+        .map(Response::new)
+        .map_err(|err| err.into())
     }
 
     async fn authenticate(
@@ -149,7 +338,9 @@ impl<T: 'static + ThreadSafeAccountsRepository> IdentityService for IdentityServ
         authenticate(
             &self.ctx,
             &self.ctx.dynamodb_adapter,
-            &self.refresh_token_cache,
+            &self.policies_repository,
+            &self.groups_repository,
+            self.refresh_token_store.as_ref(),
             request.get_mut(),
         )
         .await
@@ -164,13 +355,184 @@ impl<T: 'static + ThreadSafeAccountsRepository> IdentityService for IdentityServ
         generate_access_token(
             &self.ctx,
             &self.ctx.dynamodb_adapter,
-            &self.refresh_token_cache,
+            &self.policies_repository,
+            &self.groups_repository,
+            self.refresh_token_store.as_ref(),
             request.get_mut(),
         )
         .await
         .map(Response::new)
         .map_err(|err| err.into())
     }
+
+    async fn get_signing_keys(
+        &self,
+        request: Request<GetSigningKeysInput>,
+    ) -> Result<Response<GetSigningKeysOutput>, Status> {
+        get_signing_keys(&self.ctx, request.get_ref())
+            .await
+            .map(Response::new)
+            .map_err(|err| err.into())
+    }
+
+    async fn create_policy(&self, request: Request<CreatePolicyInput>) -> Result<Response<CreatePolicyOutput>, Status> {
+        create_policy(&self.ctx, &self.policies_repository, request.into_inner())
+            .await
+            .map(Response::new)
+            .map_err(|err| err.into())
+    }
+
+    async fn describe_policy(
+        &self,
+        request: Request<DescribePolicyInput>,
+    ) -> Result<Response<DescribePolicyOutput>, Status> {
+        describe_policy(&self.policies_repository, request.get_ref())
+            .await
+            .map(Response::new)
+            .map_err(|err| err.into())
+    }
+
+    async fn list_policies(&self, request: Request<ListPoliciesInput>) -> Result<Response<ListPoliciesOutput>, Status> {
+        list_policies(&self.policies_repository, request.get_ref())
+            .await
+            .map(Response::new)
+            .map_err(|err| err.into())
+    }
+
+    async fn update_policy(&self, request: Request<UpdatePolicyInput>) -> Result<Response<UpdatePolicyOutput>, Status> {
+        update_policy(
+            &self.ctx,
+            &self.policies_repository,
+            &self.permissions_cache,
+            request.into_inner(),
+        )
+        .await
+        .map(Response::new)
+        .map_err(|err| err.into())
+    }
+
+    async fn delete_policy(&self, request: Request<DeletePolicyInput>) -> Result<Response<DeletePolicyOutput>, Status> {
+        delete_policy(&self.policies_repository, &self.permissions_cache, request.get_ref())
+            .await
+            .map(Response::new)
+            .map_err(|err| err.into())
+    }
+
+    async fn attach_policy(&self, request: Request<AttachPolicyInput>) -> Result<Response<AttachPolicyOutput>, Status> {
+        attach_policy(
+            &self.ctx,
+            &self.ctx.dynamodb_adapter,
+            &self.permissions_cache,
+            &self.policies_repository,
+            request.get_ref(),
+        )
+        .await
+        .map(Response::new)
+        .map_err(|err| err.into())
+    }
+
+    async fn detach_policy(&self, request: Request<DetachPolicyInput>) -> Result<Response<DetachPolicyOutput>, Status> {
+        detach_policy(
+            &self.ctx,
+            &self.ctx.dynamodb_adapter,
+            &self.permissions_cache,
+            request.get_ref(),
+        )
+        .await
+        .map(Response::new)
+        .map_err(|err| err.into())
+    }
+
+    async fn create_group(&self, request: Request<CreateGroupInput>) -> Result<Response<CreateGroupOutput>, Status> {
+        create_group(&self.ctx, &self.groups_repository, request.into_inner())
+            .await
+            .map(Response::new)
+            .map_err(|err| err.into())
+    }
+
+    async fn describe_group(
+        &self,
+        request: Request<DescribeGroupInput>,
+    ) -> Result<Response<DescribeGroupOutput>, Status> {
+        describe_group(&self.groups_repository, request.get_ref())
+            .await
+            .map(Response::new)
+            .map_err(|err| err.into())
+    }
+
+    async fn list_groups(&self, request: Request<ListGroupsInput>) -> Result<Response<ListGroupsOutput>, Status> {
+        list_groups(&self.groups_repository, request.get_ref())
+            .await
+            .map(Response::new)
+            .map_err(|err| err.into())
+    }
+
+    async fn update_group(&self, request: Request<UpdateGroupInput>) -> Result<Response<UpdateGroupOutput>, Status> {
+        update_group(
+            &self.ctx,
+            &self.groups_repository,
+            &self.permissions_cache,
+            request.into_inner(),
+        )
+        .await
+        .map(Response::new)
+        .map_err(|err| err.into())
+    }
+
+    async fn delete_group(&self, request: Request<DeleteGroupInput>) -> Result<Response<DeleteGroupOutput>, Status> {
+        delete_group(&self.groups_repository, &self.permissions_cache, request.get_ref())
+            .await
+            .map(Response::new)
+            .map_err(|err| err.into())
+    }
+
+    async fn add_group_member(
+        &self,
+        request: Request<AddGroupMemberInput>,
+    ) -> Result<Response<AddGroupMemberOutput>, Status> {
+        add_group_member(
+            &self.ctx,
+            &self.ctx.dynamodb_adapter,
+            &self.permissions_cache,
+            &self.groups_repository,
+            request.get_ref(),
+        )
+        .await
+        .map(Response::new)
+        .map_err(|err| err.into())
+    }
+
+    async fn remove_group_member(
+        &self,
+        request: Request<RemoveGroupMemberInput>,
+    ) -> Result<Response<RemoveGroupMemberOutput>, Status> {
+        remove_group_member(
+            &self.ctx,
+            &self.ctx.dynamodb_adapter,
+            &self.permissions_cache,
+            request.get_ref(),
+        )
+        .await
+        .map(Response::new)
+        .map_err(|err| err.into())
+    }
+}
+
+/// Periodically logs the permissions cache metrics and evicts its expired entries.
+async fn report_permissions_cache(permissions_cache: Arc<PermissionsCache>) {
+    let mut interval = tokio::time::interval(PERMISSIONS_CACHE_REPORT_INTERVAL);
+    loop {
+        interval.tick().await;
+        permissions_cache.evict_expired();
+
+        let stats = permissions_cache.stats();
+        log::info!(
+            "Permissions cache: {} hits, {} misses, {} invalidations.",
+            stats.hits,
+            stats.misses,
+            stats.invalidations,
+        );
+    }
 }
 
 #[tokio::main]
@@ -184,7 +546,18 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let addr = "0.0.0.0:8080".parse().unwrap();
     let ctx = Context::from_env().await;
     let accounts_repository = DdbAccountsRepository::new(ctx.dynamodb_adapter.clone(), ctx.accounts_table_name.clone());
-    let identity_service = IdentityServiceImpl::new(ctx, accounts_repository)?;
+    let policies_repository = DdbPoliciesRepository::new(ctx.dynamodb_adapter.clone(), ctx.policies_table_name.clone());
+    let groups_repository = DdbGroupsRepository::new(ctx.dynamodb_adapter.clone(), ctx.groups_table_name.clone());
+    let permissions_cache = Arc::new(PermissionsCache::new(ctx.permissions_cache_ttl));
+    tokio::spawn(report_permissions_cache(permissions_cache.clone()));
+    let identity_service = IdentityServiceImpl::new(
+        ctx,
+        accounts_repository,
+        policies_repository,
+        groups_repository,
+        permissions_cache,
+        AccountEvents::new(ACCOUNT_EVENTS_CAPACITY),
+    )?;
     let server = IdentityServiceServer::new(identity_service);
 
     Server::builder().add_service(server).serve(addr).await?;
@@ -0,0 +1,56 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::managed_policy::{ListPoliciesPage, ManagedPolicy, PoliciesRepository, PolicyStoreError};
+use crate::utils::ddb_table::{DdbTable, DdbTableError, ThreadSafeDdbClient};
+
+
+pub struct DdbPoliciesRepository<T: ThreadSafeDdbClient> {
+    policies_table: DdbTable<T>,
+}
+
+impl<T: ThreadSafeDdbClient> DdbPoliciesRepository<T> {
+    pub fn new(ddb: T, policies_table_name: impl Into<String>) -> Self {
+        Self {
+            policies_table: DdbTable::new(ddb, policies_table_name, "PolicyId"),
+        }
+    }
+}
+
+#[async_trait]
+impl<T: ThreadSafeDdbClient> PoliciesRepository for DdbPoliciesRepository<T> {
+    async fn create_policy(&self, policy: &ManagedPolicy) -> Result<(), PolicyStoreError> {
+        Ok(self.policies_table.create(policy).await?)
+    }
+
+    async fn get_policy(&self, policy_id: &Uuid) -> Result<ManagedPolicy, PolicyStoreError> {
+        Ok(self.policies_table.get(policy_id).await?)
+    }
+
+    async fn list_policies(
+        &self,
+        starting_token: Option<Uuid>,
+        page_size: u32,
+    ) -> Result<ListPoliciesPage, PolicyStoreError> {
+        let (policies, next_token) = self.policies_table.list(starting_token, page_size).await?;
+        Ok(ListPoliciesPage { policies, next_token })
+    }
+
+    async fn update_policy(&self, policy: &ManagedPolicy) -> Result<(), PolicyStoreError> {
+        Ok(self.policies_table.update(policy).await?)
+    }
+
+    async fn delete_policy(&self, policy_id: &Uuid) -> Result<(), PolicyStoreError> {
+        Ok(self.policies_table.delete(policy_id).await?)
+    }
+}
+
+impl From<DdbTableError> for PolicyStoreError {
+    fn from(err: DdbTableError) -> Self {
+        match err {
+            DdbTableError::NotFound => PolicyStoreError::NotFound,
+            DdbTableError::Serde(e) => PolicyStoreError::Serde(e),
+            DdbTableError::Other(e) => PolicyStoreError::Other(e),
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use std::error::Error;
+
+use async_trait::async_trait;
+use thiserror::Error;
+use uuid::Uuid;
+
+use super::ManagedPolicy;
+
+
+#[derive(Debug, Error)]
+pub enum PolicyStoreError {
+    #[error("Policy not found.")]
+    NotFound,
+
+    #[error(transparent)]
+    Serde(serde_ddb::Error),
+
+    #[error(transparent)]
+    Other(#[from] Box<dyn Error>),
+}
+
+
+/// A page of managed policies, together with the token of the next page, if any.
+#[derive(Clone, Debug, Default)]
+pub struct ListPoliciesPage {
+    pub policies: Vec<ManagedPolicy>,
+    pub next_token: Option<Uuid>,
+}
+
+
+#[async_trait]
+pub trait PoliciesRepository {
+    async fn create_policy(&self, policy: &ManagedPolicy) -> Result<(), PolicyStoreError>;
+
+    async fn get_policy(&self, policy_id: &Uuid) -> Result<ManagedPolicy, PolicyStoreError>;
+
+    async fn list_policies(
+        &self,
+        starting_token: Option<Uuid>,
+        page_size: u32,
+    ) -> Result<ListPoliciesPage, PolicyStoreError>;
+
+    /// Replaces an existing policy. Fails with `NotFound` if there is no policy with the same ID.
+    async fn update_policy(&self, policy: &ManagedPolicy) -> Result<(), PolicyStoreError>;
+
+    async fn delete_policy(&self, policy_id: &Uuid) -> Result<(), PolicyStoreError>;
+}
@@ -0,0 +1,64 @@
+use identity_service::pb::ManagedPolicy as ManagedPolicyModel;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::user_account::PermissionsDocument;
+
+/// A named permissions document, which can be attached to any number of accounts.
+///
+/// Accounts only hold the IDs of their attached policies, so changes to a policy apply to all of
+/// them at their next authorization.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct ManagedPolicy {
+    pub policy_id: Uuid,
+
+    pub name: String,
+
+    #[serde(default)]
+    pub description: String,
+
+    #[serde(default)]
+    pub permissions_document: PermissionsDocument,
+}
+
+
+impl From<ManagedPolicy> for ManagedPolicyModel {
+    fn from(val: ManagedPolicy) -> ManagedPolicyModel {
+        ManagedPolicyModel {
+            policy_id: val.policy_id.to_hyphenated().to_string(),
+            name: val.name,
+            description: val.description,
+            permissions_document: Some(val.permissions_document.into()),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use aws_sdk_dynamodb::model::AttributeValue;
+
+    use super::*;
+
+    #[test]
+    fn deserializes_without_optional_fields() {
+        let mut doc = HashMap::new();
+        doc.insert(
+            "PolicyId".to_string(),
+            AttributeValue::S(Uuid::nil().to_hyphenated().to_string()),
+        );
+        doc.insert("Name".to_string(), AttributeValue::S("Teacher".to_string()));
+
+        let expected = ManagedPolicy {
+            policy_id: Uuid::nil(),
+            name: "Teacher".to_string(),
+            ..Default::default()
+        };
+        let actual = serde_ddb::from_hashmap::<ManagedPolicy, _>(doc).unwrap();
+
+        assert_eq!(expected, actual);
+    }
+}
@@ -0,0 +1,70 @@
+use identity_service::pb::{AddGroupMemberInput, AddGroupMemberOutput};
+use service_core::ddb::query::Query;
+use service_core::ddb::update_item::UpdateItem;
+use service_core::endpoint_error::EndpointError;
+use service_core::operation_error::OperationError;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::account_group::{GroupStoreError, GroupsRepository};
+use crate::utils::attachments::{update_attachment, Attachment, UpdateAttachmentError};
+use crate::utils::permissions_cache::PermissionsCache;
+use crate::Context;
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum AddGroupMemberError {
+    #[error("Account not found.")]
+    AccountNotFound,
+
+    #[error("Group not found.")]
+    GroupNotFound,
+}
+
+/// Adds an account to a group. Adding an account which is already a member of the group has no
+/// effect.
+pub(crate) async fn add_group_member(
+    ctx: &Context,
+    ddb: &(impl Query + UpdateItem),
+    permissions_cache: &PermissionsCache,
+    groups_repository: &impl GroupsRepository,
+    input: &AddGroupMemberInput,
+) -> Result<AddGroupMemberOutput, EndpointError<AddGroupMemberError>> {
+    let account_id =
+        Uuid::parse_str(&input.account_id).map_err(|_| EndpointError::validation("Invalid account ID provided."))?;
+    let group_id =
+        Uuid::parse_str(&input.group_id).map_err(|_| EndpointError::validation("Invalid group ID provided."))?;
+
+    groups_repository.get_group(&group_id).await.map_err(|e| match e {
+        GroupStoreError::NotFound => EndpointError::operation(AddGroupMemberError::GroupNotFound),
+        _ => {
+            log::error!("Failed retrieving group: {:?}.", e);
+            EndpointError::internal()
+        }
+    })?;
+
+    update_attachment(
+        ctx,
+        ddb,
+        permissions_cache,
+        &account_id,
+        Attachment::Group(group_id),
+        true,
+    )
+    .await
+    .map_err(|e| match e {
+        UpdateAttachmentError::AccountNotFound => EndpointError::operation(AddGroupMemberError::AccountNotFound),
+        _ => EndpointError::internal(),
+    })?;
+
+    Ok(AddGroupMemberOutput {})
+}
+
+impl OperationError for AddGroupMemberError {
+    fn code(&self) -> tonic::Code {
+        match self {
+            Self::AccountNotFound => tonic::Code::NotFound,
+            Self::GroupNotFound => tonic::Code::NotFound,
+        }
+    }
+}
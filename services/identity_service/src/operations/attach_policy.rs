@@ -0,0 +1,70 @@
+use identity_service::pb::{AttachPolicyInput, AttachPolicyOutput};
+use service_core::ddb::query::Query;
+use service_core::ddb::update_item::UpdateItem;
+use service_core::endpoint_error::EndpointError;
+use service_core::operation_error::OperationError;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::managed_policy::{PoliciesRepository, PolicyStoreError};
+use crate::utils::attachments::{update_attachment, Attachment, UpdateAttachmentError};
+use crate::utils::permissions_cache::PermissionsCache;
+use crate::Context;
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum AttachPolicyError {
+    #[error("Account not found.")]
+    AccountNotFound,
+
+    #[error("Policy not found.")]
+    PolicyNotFound,
+}
+
+/// Attaches a managed policy to an account. Attaching a policy which is already attached has no
+/// effect.
+pub(crate) async fn attach_policy(
+    ctx: &Context,
+    ddb: &(impl Query + UpdateItem),
+    permissions_cache: &PermissionsCache,
+    policies_repository: &impl PoliciesRepository,
+    input: &AttachPolicyInput,
+) -> Result<AttachPolicyOutput, EndpointError<AttachPolicyError>> {
+    let account_id =
+        Uuid::parse_str(&input.account_id).map_err(|_| EndpointError::validation("Invalid account ID provided."))?;
+    let policy_id =
+        Uuid::parse_str(&input.policy_id).map_err(|_| EndpointError::validation("Invalid policy ID provided."))?;
+
+    policies_repository.get_policy(&policy_id).await.map_err(|e| match e {
+        PolicyStoreError::NotFound => EndpointError::operation(AttachPolicyError::PolicyNotFound),
+        _ => {
+            log::error!("Failed retrieving policy: {:?}.", e);
+            EndpointError::internal()
+        }
+    })?;
+
+    update_attachment(
+        ctx,
+        ddb,
+        permissions_cache,
+        &account_id,
+        Attachment::Policy(policy_id),
+        true,
+    )
+    .await
+    .map_err(|e| match e {
+        UpdateAttachmentError::AccountNotFound => EndpointError::operation(AttachPolicyError::AccountNotFound),
+        _ => EndpointError::internal(),
+    })?;
+
+    Ok(AttachPolicyOutput {})
+}
+
+impl OperationError for AttachPolicyError {
+    fn code(&self) -> tonic::Code {
+        match self {
+            Self::AccountNotFound => tonic::Code::NotFound,
+            Self::PolicyNotFound => tonic::Code::NotFound,
+        }
+    }
+}
@@ -1,18 +1,28 @@
-use chrono::{Duration, Utc};
+use chrono::Utc;
 use identity_service::pb::{AuthenticateInput, AuthenticateOutput};
-use memcache::Client;
+use service_core::auth::jwt::{AccessScopes, Claims, ScopePaths};
 use service_core::ddb::get_item::{GetItem, GetItemInput};
 use service_core::ddb::query::Query;
 use service_core::endpoint_error::EndpointError;
 use service_core::operation_error::OperationError;
+use service_core::resource_access::AccessKind;
 use thiserror::Error;
 use uuid::Uuid;
 use validator::validate_email;
 use zeroize::Zeroize;
 
+use crate::account_group::GroupsRepository;
+use crate::managed_policy::PoliciesRepository;
+use crate::refresh_token::RefreshTokenStore;
 use crate::user_account::{verify_password, UserAccount};
 use crate::utils::account::account_key_from_email;
-use crate::{Context, MemcacheConnPool};
+use crate::utils::permissions::{compile_subject_access_path_set, subject_variables};
+use crate::utils::refresh_tokens::issue_refresh_token;
+use crate::Context;
+
+/// Access scopes larger than this many bytes are left out of access tokens, which are sent along with
+/// every request.
+const MAX_ACCESS_SCOPES_SIZE: usize = 4096;
 
 #[non_exhaustive]
 #[derive(Error, Debug)]
@@ -27,7 +37,9 @@ pub enum AuthenticateError {
 pub(crate) async fn authenticate(
     ctx: &Context,
     ddb: &(impl GetItem + Query),
-    refresh_token_cache: &MemcacheConnPool,
+    policies_repository: &impl PoliciesRepository,
+    groups_repository: &impl GroupsRepository,
+    refresh_token_store: &dyn RefreshTokenStore,
     input: &mut AuthenticateInput,
 ) -> Result<AuthenticateOutput, EndpointError<AuthenticateError>> {
     if !validate_email(&input.email) {
@@ -78,18 +90,36 @@ pub(crate) async fn authenticate(
         }
     })?;
 
-    let refresh_token = create_refresh_token(&refresh_token_cache, &user_account.account_id);
-    let access_token = create_access_token(&ctx, user_account).map_err(|e| {
+    let refresh_token = issue_refresh_token(
+        refresh_token_store,
+        ctx.refresh_token_ttl,
+        &user_account.account_id,
+        non_empty(&input.user_agent),
+        non_empty(&input.ip_address),
+    )
+    .await
+    .map_err(|e| {
+        log::error!("Failed to issue a refresh token: {:?}", e);
+        EndpointError::internal()
+    })?;
+    let mut claims = access_token_claims(ctx, user_account, &refresh_token.family_id);
+    claims.scopes = access_scopes(ctx, ddb, policies_repository, groups_repository, &claims).await;
+    let access_token = create_access_token(&ctx, &claims).map_err(|e| {
         log::error!("Failed encoding the JWT access token: {:?}", e);
         EndpointError::internal()
     })?;
 
     Ok(AuthenticateOutput {
         access_token,
-        refresh_token: refresh_token.to_hyphenated().to_string(),
+        refresh_token: refresh_token.token_id.to_hyphenated().to_string(),
     })
 }
 
+/// Client details are optional, and left empty by frontends which do not report them.
+fn non_empty(value: &str) -> Option<String> {
+    (!value.is_empty()).then(|| value.to_owned())
+}
+
 impl OperationError for AuthenticateError {
     fn code(&self) -> tonic::Code {
         match self {
@@ -99,38 +129,84 @@ impl OperationError for AuthenticateError {
     }
 }
 
-pub(crate) fn create_access_token(ctx: &Context, user_account: UserAccount) -> jsonwebtoken::errors::Result<String> {
-    use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
-    use service_core::auth::jwt::Claims;
+/// Claims of an access token issued to the given account in the given session, valid from now on for
+/// the configured lifetime.
+pub(crate) fn access_token_claims(ctx: &Context, user_account: UserAccount, session_id: &Uuid) -> Claims {
+    let now = Utc::now().timestamp() as usize;
 
-    let exp = Utc::now()
-        .checked_add_signed(Duration::seconds(60))
-        .expect("valid timestamp")
-        .timestamp();
-    let claims = Claims {
+    Claims {
+        iss: ctx.token_issuer.clone(),
         sub: user_account.account_id.to_hyphenated().to_string(),
+        aud: ctx.token_audience.clone(),
+        exp: now + ctx.access_token_ttl.as_secs() as usize,
+        nbf: now,
+        iat: now,
+        jti: Uuid::new_v4().to_hyphenated().to_string(),
         email: user_account.email,
         first_name: user_account.first_name,
         last_name: user_account.last_name,
-        exp: exp as usize,
+        sid: Some(session_id.to_hyphenated().to_string()),
+        scopes: None,
+    }
+}
+
+/// Compiles the access scopes to be embedded into the access token with the given claims, if enabled.
+///
+/// # Notes
+///
+/// Scopes are compiled from the current permissions of the account rather than taken from the
+/// permissions cache, which may lag behind changes made through other instances of the service.
+///
+/// Scopes only spare the frontend an __Authorize__ call, so failures are logged rather than reported
+/// and result in a token without scopes. The same goes for scopes larger than
+/// `MAX_ACCESS_SCOPES_SIZE`.
+pub(crate) async fn access_scopes(
+    ctx: &Context,
+    ddb: &(impl GetItem + Query),
+    policies_repository: &impl PoliciesRepository,
+    groups_repository: &impl GroupsRepository,
+    claims: &Claims,
+) -> Option<AccessScopes> {
+    if !ctx.embed_access_scopes {
+        return None;
+    }
+
+    let account_id = Uuid::parse_str(&claims.sub).ok()?;
+    let variables = subject_variables(Some(&account_id), &claims.string_claims());
+    let mut scopes = AccessScopes {
+        issued_at: Utc::now().timestamp() as usize,
+        ..Default::default()
     };
+    for access_kind in [AccessKind::Query, AccessKind::Mutation, AccessKind::Subscription] {
+        let compiled = compile_subject_access_path_set(
+            ddb,
+            ctx.accounts_table_name.as_ref(),
+            policies_repository,
+            groups_repository,
+            Some(&account_id),
+            None,
+            access_kind,
+        )
+        .await
+        .map_err(|e| log::error!("Failed compiling access scopes: {:?}.", e))
+        .ok()?;
 
-    log::info!("Secret: {}", &ctx.access_token_secret);
+        *scopes.paths_mut(access_kind) = ScopePaths::from(&compiled.access_path_set.bind_variables(&variables));
+    }
 
-    encode(
-        &Header::new(Algorithm::HS512),
-        &claims,
-        &EncodingKey::from_base64_secret(ctx.access_token_secret.as_ref())?,
-    )
-}
+    let size = serde_json::to_vec(&scopes).map(|encoded| encoded.len()).ok()?;
+    if size > MAX_ACCESS_SCOPES_SIZE {
+        log::info!(
+            "Access scopes of account {} are too large to be embedded ({} bytes).",
+            account_id.to_hyphenated(),
+            size
+        );
+        return None;
+    }
 
-pub(crate) fn create_refresh_token(refresh_token_cache: &MemcacheConnPool, account_id: &Uuid) -> Uuid {
-    let client = Client::with_pool(refresh_token_cache.clone()).unwrap();
-    let token = Uuid::new_v4();
-    let ttl = Duration::hours(10).num_seconds();
-    client
-        .set(token.to_string().as_str(), account_id.as_bytes().as_slice(), ttl as u32)
-        .unwrap();
+    Some(scopes)
+}
 
-    token
+pub(crate) fn create_access_token(ctx: &Context, claims: &Claims) -> jsonwebtoken::errors::Result<String> {
+    ctx.signing_keys.sign(claims)
 }
@@ -1,19 +1,26 @@
 use identity_service::pb::conversion::AccessRequestParseError;
-use identity_service::pb::{AuthorizeInput, AuthorizeOutput};
+use identity_service::pb::path_denial::Reason as ReasonModel;
+use identity_service::pb::{AuthorizeInput, AuthorizeOutput, PathDenial};
 use service_core::ddb::get_item::GetItem;
 use service_core::ddb::query::Query;
 use service_core::endpoint_error::EndpointError;
 use service_core::operation_error::OperationError;
-use service_core::resource_access::types::Superset;
-use service_core::resource_access::AccessRequest;
+use service_core::resource_access::explain::DenialReason;
+use service_core::resource_access::types::PathSet;
+use service_core::resource_access::variables::PolicyVariables;
+use service_core::resource_access::{AccessKind, AccessPathSet, AccessRequest};
 use thiserror::Error;
 use uuid::Uuid;
 
+use crate::account_group::GroupsRepository;
+use crate::managed_policy::PoliciesRepository;
 use crate::operations::authorize::AuthorizeError::InvalidResourcePath;
 use crate::user_account::PermissionsDocument;
 use crate::utils::permissions::{
-    get_access_path_set, get_permissions_from_ddb, merge_access_request_paths, GetPermissionsFromDdbError,
+    get_subject_access_path_set, merge_access_request_paths, nearest_statement, subject_variables,
+    SubjectAccessPathSetError,
 };
+use crate::utils::permissions_cache::{CachedAccessPathSet, PermissionsCache};
 use crate::Context;
 
 #[non_exhaustive]
@@ -29,6 +36,9 @@ pub enum AuthorizeError {
 pub(crate) async fn authorize(
     ctx: &Context,
     ddb: &(impl GetItem + Query),
+    policies_repository: &impl PoliciesRepository,
+    groups_repository: &impl GroupsRepository,
+    permissions_cache: &PermissionsCache,
     input: &AuthorizeInput,
 ) -> Result<AuthorizeOutput, EndpointError<AuthorizeError>> {
     let account_id = input
@@ -37,47 +47,102 @@ pub(crate) async fn authorize(
         .map(|account_id| Uuid::parse_str(account_id.clone().as_ref()))
         .transpose()
         .map_err(|_| EndpointError::validation("Invalid account ID provided."))?;
-    let permissions_document = if let Some(account_id) = &account_id {
-        get_permissions_from_ddb(ddb, ctx.accounts_table_name.as_ref(), &account_id)
-            .await
-            .map_err(|e| match e {
-                GetPermissionsFromDdbError::AccountNotFound => EndpointError::operation(AuthorizeError::NotFound),
-                _ => EndpointError::internal(),
-            })?
-    } else {
-        PermissionsDocument::default()
-    };
-
     let access_request: AccessRequest = input.access_request.clone().unwrap().try_into().map_err(|e| match e {
         AccessRequestParseError::CompileError(idx, path) => EndpointError::operation(InvalidResourcePath(idx, path)),
         AccessRequestParseError::MultiRootPath(idx, path) => EndpointError::operation(InvalidResourcePath(idx, path)),
     })?;
+    let access_kind = access_request.kind;
 
-    let allowed_paths =
-        get_access_path_set(&permissions_document, access_request.kind, account_id.is_some()).map_err(|err| {
-            if let Some(account_id) = &account_id {
-                let (invalid_path, stmt_idx, path_idx) = err;
-                log::error!(
-                    "Invalid path in permissions document for account {} (statement: {}, path: {}): {}.",
-                    account_id.to_hyphenated(),
-                    stmt_idx,
-                    path_idx,
-                    invalid_path,
-                );
-            }
-            EndpointError::internal()
-        })?;
+    let CachedAccessPathSet {
+        permissions_document,
+        access_path_set,
+        ..
+    } = get_subject_access_path_set(
+        ddb,
+        ctx.accounts_table_name.as_ref(),
+        policies_repository,
+        groups_repository,
+        permissions_cache,
+        account_id.as_ref(),
+        access_kind,
+    )
+    .await
+    .map_err(|e| match e {
+        SubjectAccessPathSetError::AccountNotFound => EndpointError::operation(AuthorizeError::NotFound),
+        _ => EndpointError::internal(),
+    })?;
+
+    let variables = subject_variables(account_id.as_ref(), &input.claims);
+    let access_path_set = access_path_set.bind_variables(&variables);
     let desired_paths = merge_access_request_paths(access_request);
 
     log::info!(
-        "Allowed paths: {:?}. Desired paths: {:?}.",
-        &allowed_paths,
+        "Allowed paths: {:?}. Denied paths: {:?}. Desired paths: {:?}.",
+        &access_path_set.allowed,
+        &access_path_set.denied,
         &desired_paths
     );
 
-    let permission_granted = allowed_paths.is_superset_of(&desired_paths);
+    let permission_granted = access_path_set.permits(&desired_paths);
+    let denials = if input.explain && !permission_granted {
+        path_denials(
+            &permissions_document,
+            &access_path_set,
+            access_kind,
+            &desired_paths,
+            &variables,
+        )
+    } else {
+        vec![]
+    };
+    let granted_paths = if input.prune && !permission_granted {
+        granted_paths(&access_path_set, &desired_paths)
+    } else {
+        vec![]
+    };
+
+    Ok(AuthorizeOutput {
+        permission_granted,
+        denials,
+        granted_paths,
+    })
+}
+
+/// Explains which of the desired paths are not granted by the access path set, and why.
+pub(crate) fn path_denials(
+    permissions_document: &PermissionsDocument,
+    access_path_set: &AccessPathSet,
+    access_kind: AccessKind,
+    desired_paths: &PathSet,
+    variables: &PolicyVariables,
+) -> Vec<PathDenial> {
+    access_path_set
+        .uncovered_paths(desired_paths)
+        .into_iter()
+        .map(|uncovered| PathDenial {
+            path: uncovered.path.to_string(),
+            reason: match uncovered.reason {
+                DenialReason::NotAllowed => ReasonModel::NotAllowed,
+                DenialReason::Denied => ReasonModel::Denied,
+            } as i32,
+            closest_allowed_path: access_path_set
+                .allowed
+                .closest_path(&uncovered.path)
+                .map(|closest| closest.path.to_string()),
+            statement_index: nearest_statement(permissions_document, access_kind, &uncovered, variables)
+                .map(|stmt_idx| stmt_idx as u32),
+        })
+        .collect()
+}
 
-    Ok(AuthorizeOutput { permission_granted })
+/// Gets the subset of the desired paths which is granted by the access path set.
+pub(crate) fn granted_paths(access_path_set: &AccessPathSet, desired_paths: &PathSet) -> Vec<String> {
+    access_path_set
+        .prune(desired_paths)
+        .into_paths()
+        .into_iter()
+        .map(|path| path.to_string())
+        .collect()
 }
 
 impl OperationError for AuthorizeError {
@@ -0,0 +1,57 @@
+use identity_service::pb::{CreateGroupInput, CreateGroupOutput};
+use service_core::endpoint_error::EndpointError;
+use service_core::operation_error::OperationError;
+use uuid::Uuid;
+
+use crate::account_group::{AccountGroup, GroupsRepository};
+use crate::user_account::PermissionsDocument;
+use crate::utils::validation::validate_resource_paths;
+use crate::Context;
+
+#[non_exhaustive]
+#[derive(Debug, thiserror::Error)]
+pub enum CreateGroupError {
+    #[error("Resource path {1} in statement {0} is invalid.")]
+    InvalidResourcePath(usize, usize),
+}
+
+pub(crate) async fn create_group(
+    ctx: &Context,
+    groups_repository: &impl GroupsRepository,
+    input: CreateGroupInput,
+) -> Result<CreateGroupOutput, EndpointError<CreateGroupError>> {
+    if input.name.is_empty() {
+        return Err(EndpointError::validation("Group name is required."));
+    }
+
+    let permissions_document: PermissionsDocument = input
+        .permissions_document
+        .ok_or_else(|| EndpointError::validation("missing permissions document"))?
+        .into();
+    validate_resource_paths(ctx.schema_info.as_ref(), &permissions_document.statements).map_err(
+        |(stmt_idx, path_idx)| EndpointError::operation(CreateGroupError::InvalidResourcePath(stmt_idx, path_idx)),
+    )?;
+
+    let group = AccountGroup {
+        group_id: Uuid::new_v4(),
+        name: input.name,
+        description: input.description,
+        permissions_document,
+    };
+    groups_repository.create_group(&group).await.map_err(|e| {
+        log::error!("Create group failed: {:?}", e);
+        EndpointError::internal()
+    })?;
+
+    Ok(CreateGroupOutput {
+        group_id: group.group_id.to_hyphenated().to_string(),
+    })
+}
+
+impl OperationError for CreateGroupError {
+    fn code(&self) -> tonic::Code {
+        match self {
+            Self::InvalidResourcePath(..) => tonic::Code::InvalidArgument,
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use identity_service::pb::{CreatePolicyInput, CreatePolicyOutput};
+use service_core::endpoint_error::EndpointError;
+use service_core::operation_error::OperationError;
+use uuid::Uuid;
+
+use crate::managed_policy::{ManagedPolicy, PoliciesRepository};
+use crate::user_account::PermissionsDocument;
+use crate::utils::validation::validate_resource_paths;
+use crate::Context;
+
+#[non_exhaustive]
+#[derive(Debug, thiserror::Error)]
+pub enum CreatePolicyError {
+    #[error("Resource path {1} in statement {0} is invalid.")]
+    InvalidResourcePath(usize, usize),
+}
+
+pub(crate) async fn create_policy(
+    ctx: &Context,
+    policies_repository: &impl PoliciesRepository,
+    input: CreatePolicyInput,
+) -> Result<CreatePolicyOutput, EndpointError<CreatePolicyError>> {
+    if input.name.is_empty() {
+        return Err(EndpointError::validation("Policy name is required."));
+    }
+
+    let permissions_document: PermissionsDocument = input
+        .permissions_document
+        .ok_or_else(|| EndpointError::validation("missing permissions document"))?
+        .into();
+    validate_resource_paths(ctx.schema_info.as_ref(), &permissions_document.statements).map_err(
+        |(stmt_idx, path_idx)| EndpointError::operation(CreatePolicyError::InvalidResourcePath(stmt_idx, path_idx)),
+    )?;
+
+    let policy = ManagedPolicy {
+        policy_id: Uuid::new_v4(),
+        name: input.name,
+        description: input.description,
+        permissions_document,
+    };
+    policies_repository.create_policy(&policy).await.map_err(|e| {
+        log::error!("Create policy failed: {:?}", e);
+        EndpointError::internal()
+    })?;
+
+    Ok(CreatePolicyOutput {
+        policy_id: policy.policy_id.to_hyphenated().to_string(),
+    })
+}
+
+impl OperationError for CreatePolicyError {
+    fn code(&self) -> tonic::Code {
+        match self {
+            Self::InvalidResourcePath(..) => tonic::Code::InvalidArgument,
+        }
+    }
+}
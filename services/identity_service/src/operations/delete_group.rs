@@ -0,0 +1,41 @@
+use identity_service::pb::{DeleteGroupInput, DeleteGroupOutput};
+use service_core::endpoint_error::EndpointError;
+use service_core::operation_error::OperationError;
+use uuid::Uuid;
+
+use crate::account_group::{GroupStoreError, GroupsRepository};
+use crate::utils::permissions_cache::PermissionsCache;
+
+#[non_exhaustive]
+#[derive(Debug, thiserror::Error)]
+pub enum DeleteGroupError {
+    #[error("Group not found.")]
+    NotFound,
+}
+
+pub(crate) async fn delete_group(
+    groups_repository: &impl GroupsRepository,
+    permissions_cache: &PermissionsCache,
+    input: &DeleteGroupInput,
+) -> Result<DeleteGroupOutput, EndpointError<DeleteGroupError>> {
+    let group_id =
+        Uuid::parse_str(&input.group_id).map_err(|_| EndpointError::validation("Invalid group ID provided."))?;
+    groups_repository.delete_group(&group_id).await.map_err(|e| match e {
+        GroupStoreError::NotFound => EndpointError::operation(DeleteGroupError::NotFound),
+        _ => {
+            log::error!("Delete group failed: {:?}", e);
+            EndpointError::internal()
+        }
+    })?;
+    permissions_cache.invalidate_all();
+
+    Ok(DeleteGroupOutput {})
+}
+
+impl OperationError for DeleteGroupError {
+    fn code(&self) -> tonic::Code {
+        match self {
+            Self::NotFound => tonic::Code::NotFound,
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use identity_service::pb::{DeletePolicyInput, DeletePolicyOutput};
+use service_core::endpoint_error::EndpointError;
+use service_core::operation_error::OperationError;
+use uuid::Uuid;
+
+use crate::managed_policy::{PoliciesRepository, PolicyStoreError};
+use crate::utils::permissions_cache::PermissionsCache;
+
+#[non_exhaustive]
+#[derive(Debug, thiserror::Error)]
+pub enum DeletePolicyError {
+    #[error("Policy not found.")]
+    NotFound,
+}
+
+pub(crate) async fn delete_policy(
+    policies_repository: &impl PoliciesRepository,
+    permissions_cache: &PermissionsCache,
+    input: &DeletePolicyInput,
+) -> Result<DeletePolicyOutput, EndpointError<DeletePolicyError>> {
+    let policy_id =
+        Uuid::parse_str(&input.policy_id).map_err(|_| EndpointError::validation("Invalid policy ID provided."))?;
+    policies_repository
+        .delete_policy(&policy_id)
+        .await
+        .map_err(|e| match e {
+            PolicyStoreError::NotFound => EndpointError::operation(DeletePolicyError::NotFound),
+            _ => {
+                log::error!("Delete policy failed: {:?}", e);
+                EndpointError::internal()
+            }
+        })?;
+    permissions_cache.invalidate_all();
+
+    Ok(DeletePolicyOutput {})
+}
+
+impl OperationError for DeletePolicyError {
+    fn code(&self) -> tonic::Code {
+        match self {
+            Self::NotFound => tonic::Code::NotFound,
+        }
+    }
+}
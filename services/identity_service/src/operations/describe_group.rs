@@ -0,0 +1,40 @@
+use identity_service::pb::{DescribeGroupInput, DescribeGroupOutput};
+use service_core::endpoint_error::EndpointError;
+use service_core::operation_error::OperationError;
+use uuid::Uuid;
+
+use crate::account_group::{GroupStoreError, GroupsRepository};
+
+#[non_exhaustive]
+#[derive(thiserror::Error, Debug)]
+pub enum DescribeGroupError {
+    #[error("Group not found.")]
+    NotFound,
+}
+
+pub(crate) async fn describe_group(
+    groups_repository: &impl GroupsRepository,
+    input: &DescribeGroupInput,
+) -> Result<DescribeGroupOutput, EndpointError<DescribeGroupError>> {
+    let group_id =
+        Uuid::parse_str(&input.group_id).map_err(|_| EndpointError::validation("Invalid group ID provided."))?;
+    let group = groups_repository.get_group(&group_id).await.map_err(|e| match e {
+        GroupStoreError::NotFound => EndpointError::operation(DescribeGroupError::NotFound),
+        _ => {
+            log::error!("Failed retrieving group: {:?}.", e);
+            EndpointError::internal()
+        }
+    })?;
+
+    Ok(DescribeGroupOutput {
+        group: Some(group.into()),
+    })
+}
+
+impl OperationError for DescribeGroupError {
+    fn code(&self) -> tonic::Code {
+        match self {
+            Self::NotFound => tonic::Code::NotFound,
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use identity_service::pb::{DescribePolicyInput, DescribePolicyOutput};
+use service_core::endpoint_error::EndpointError;
+use service_core::operation_error::OperationError;
+use uuid::Uuid;
+
+use crate::managed_policy::{PoliciesRepository, PolicyStoreError};
+
+#[non_exhaustive]
+#[derive(thiserror::Error, Debug)]
+pub enum DescribePolicyError {
+    #[error("Policy not found.")]
+    NotFound,
+}
+
+pub(crate) async fn describe_policy(
+    policies_repository: &impl PoliciesRepository,
+    input: &DescribePolicyInput,
+) -> Result<DescribePolicyOutput, EndpointError<DescribePolicyError>> {
+    let policy_id =
+        Uuid::parse_str(&input.policy_id).map_err(|_| EndpointError::validation("Invalid policy ID provided."))?;
+    let policy = policies_repository.get_policy(&policy_id).await.map_err(|e| match e {
+        PolicyStoreError::NotFound => EndpointError::operation(DescribePolicyError::NotFound),
+        _ => {
+            log::error!("Failed retrieving policy: {:?}.", e);
+            EndpointError::internal()
+        }
+    })?;
+
+    Ok(DescribePolicyOutput {
+        policy: Some(policy.into()),
+    })
+}
+
+impl OperationError for DescribePolicyError {
+    fn code(&self) -> tonic::Code {
+        match self {
+            Self::NotFound => tonic::Code::NotFound,
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use identity_service::pb::{DetachPolicyInput, DetachPolicyOutput};
+use service_core::ddb::query::Query;
+use service_core::ddb::update_item::UpdateItem;
+use service_core::endpoint_error::EndpointError;
+use service_core::operation_error::OperationError;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::utils::attachments::{update_attachment, Attachment, UpdateAttachmentError};
+use crate::utils::permissions_cache::PermissionsCache;
+use crate::Context;
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum DetachPolicyError {
+    #[error("Account not found.")]
+    AccountNotFound,
+}
+
+/// Detaches a managed policy from an account. Detaching a policy which is not attached has no
+/// effect, which also allows detaching policies that were deleted.
+pub(crate) async fn detach_policy(
+    ctx: &Context,
+    ddb: &(impl Query + UpdateItem),
+    permissions_cache: &PermissionsCache,
+    input: &DetachPolicyInput,
+) -> Result<DetachPolicyOutput, EndpointError<DetachPolicyError>> {
+    let account_id =
+        Uuid::parse_str(&input.account_id).map_err(|_| EndpointError::validation("Invalid account ID provided."))?;
+    let policy_id =
+        Uuid::parse_str(&input.policy_id).map_err(|_| EndpointError::validation("Invalid policy ID provided."))?;
+
+    update_attachment(
+        ctx,
+        ddb,
+        permissions_cache,
+        &account_id,
+        Attachment::Policy(policy_id),
+        false,
+    )
+    .await
+    .map_err(|e| match e {
+        UpdateAttachmentError::AccountNotFound => EndpointError::operation(DetachPolicyError::AccountNotFound),
+        _ => EndpointError::internal(),
+    })?;
+
+    Ok(DetachPolicyOutput {})
+}
+
+impl OperationError for DetachPolicyError {
+    fn code(&self) -> tonic::Code {
+        match self {
+            Self::AccountNotFound => tonic::Code::NotFound,
+        }
+    }
+}
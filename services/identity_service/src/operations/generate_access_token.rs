@@ -1,5 +1,4 @@
 use identity_service::pb::{GenerateAccessTokenInput, GenerateAccessTokenOutput};
-use memcache::Client;
 use service_core::ddb::get_item::{GetItem, GetItemInput};
 use service_core::ddb::query::Query;
 use service_core::endpoint_error::EndpointError;
@@ -7,10 +6,14 @@ use service_core::operation_error::OperationError;
 use thiserror::Error;
 use uuid::Uuid;
 
-use crate::operations::authenticate::{create_access_token, create_refresh_token};
+use crate::account_group::GroupsRepository;
+use crate::managed_policy::PoliciesRepository;
+use crate::operations::authenticate::{access_scopes, access_token_claims, create_access_token};
+use crate::refresh_token::RefreshTokenStore;
 use crate::user_account::UserAccount;
 use crate::utils::account::{account_key_from_id, AccountKeyFromIdError};
-use crate::{Context, MemcacheConnPool};
+use crate::utils::refresh_tokens::{rotate_refresh_token, RotateRefreshTokenError};
+use crate::Context;
 
 #[non_exhaustive]
 #[derive(Error, Debug)]
@@ -25,27 +28,32 @@ pub enum GenerateAccessTokenError {
 pub(crate) async fn generate_access_token(
     ctx: &Context,
     ddb: &(impl GetItem + Query),
-    refresh_token_cache: &MemcacheConnPool,
+    policies_repository: &impl PoliciesRepository,
+    groups_repository: &impl GroupsRepository,
+    refresh_token_store: &dyn RefreshTokenStore,
     input: &mut GenerateAccessTokenInput,
 ) -> Result<GenerateAccessTokenOutput, EndpointError<GenerateAccessTokenError>> {
     let account_id =
         Uuid::parse_str(input.account_id.as_ref()).map_err(|_| EndpointError::validation("Invalid account ID"))?;
 
-    let client = Client::with_pool(refresh_token_cache.clone()).unwrap();
-    let token_owner: Vec<u8> = client
-        .get(input.refresh_token.as_ref())
-        .map_err(|e| {
-            log::error!("Memcache GET failed: {:?}", e);
+    let refresh_token = rotate_refresh_token(
+        refresh_token_store,
+        ctx.refresh_token_ttl,
+        &account_id,
+        input.refresh_token.as_ref(),
+    )
+    .await
+    .map_err(|e| match e {
+        RotateRefreshTokenError::InvalidToken => EndpointError::operation(GenerateAccessTokenError::PermissionDenied),
+        RotateRefreshTokenError::ReusedToken { .. } => {
+            log::warn!("Revoked a refresh token family of account {}. {}", account_id, e);
+            EndpointError::operation(GenerateAccessTokenError::PermissionDenied)
+        }
+        RotateRefreshTokenError::Store(e) => {
+            log::error!("Failed to rotate the refresh token: {:?}", e);
             EndpointError::internal()
-        })?
-        .ok_or_else(|| EndpointError::operation(GenerateAccessTokenError::PermissionDenied))?;
-    client.delete(input.refresh_token.as_ref()).map_err(|e| {
-        log::error!("Memcache DELETE failed: {:?}", e);
-        EndpointError::internal()
+        }
     })?;
-    if token_owner.as_slice() != account_id.as_bytes().as_slice() {
-        return Err(EndpointError::operation(GenerateAccessTokenError::PermissionDenied));
-    }
 
     let fields = ["AccountId", "Email", "FirstName", "Discoverable", "LastName"];
     let key = account_key_from_id(ddb, ctx.accounts_table_name.as_ref(), &account_id)
@@ -77,8 +85,9 @@ pub(crate) async fn generate_access_token(
         EndpointError::internal()
     })?;
 
-    let refresh_token = create_refresh_token(refresh_token_cache, &user_account.account_id);
-    let access_token = create_access_token(ctx, user_account).map_err(|e| {
+    let mut claims = access_token_claims(ctx, user_account, &refresh_token.family_id);
+    claims.scopes = access_scopes(ctx, ddb, policies_repository, groups_repository, &claims).await;
+    let access_token = create_access_token(ctx, &claims).map_err(|e| {
         log::error!("Failed encoding the JWT access token: {:?}", e);
         EndpointError::internal()
     })?;
@@ -86,7 +95,7 @@ pub(crate) async fn generate_access_token(
 
     Ok(GenerateAccessTokenOutput {
         access_token,
-        refresh_token: refresh_token.to_hyphenated().to_string(),
+        refresh_token: refresh_token.token_id.to_hyphenated().to_string(),
     })
 }
 
@@ -41,8 +41,18 @@ pub(crate) async fn get_permissions(
 
     get_permissions_from_ddb(ddb, ctx.accounts_table_name.as_ref(), &account_id)
         .await
-        .map(|permissions_document| GetPermissionsOutput {
-            permissions_document: Some(permissions_document.into()),
+        .map(|account_permissions| GetPermissionsOutput {
+            permissions_document: Some(account_permissions.permissions_document.into()),
+            attached_policy_ids: account_permissions
+                .attached_policies
+                .into_iter()
+                .map(|policy_id| policy_id.to_hyphenated().to_string())
+                .collect(),
+            group_ids: account_permissions
+                .group_ids
+                .into_iter()
+                .map(|group_id| group_id.to_hyphenated().to_string())
+                .collect(),
         })
         .map_err(|e| match e {
             GetPermissionsFromDdbError::AccountNotFound => EndpointError::operation(GetPermissionsError::NotFoundError),
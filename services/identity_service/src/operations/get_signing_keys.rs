@@ -0,0 +1,14 @@
+use identity_service::pb::{GetSigningKeysInput, GetSigningKeysOutput};
+use service_core::endpoint_error::EndpointError;
+
+use crate::Context;
+
+/// Lists the public keys which access tokens are verified with.
+pub(crate) async fn get_signing_keys(
+    ctx: &Context,
+    _input: &GetSigningKeysInput,
+) -> Result<GetSigningKeysOutput, EndpointError<!>> {
+    Ok(GetSigningKeysOutput {
+        keys: ctx.signing_keys.public_keys(),
+    })
+}
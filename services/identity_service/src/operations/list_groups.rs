@@ -0,0 +1,31 @@
+use identity_service::pb::{ListGroupsInput, ListGroupsOutput};
+use service_core::endpoint_error::EndpointError;
+use uuid::Uuid;
+
+use crate::account_group::GroupsRepository;
+
+pub(crate) async fn list_groups(
+    groups_repository: &impl GroupsRepository,
+    input: &ListGroupsInput,
+) -> Result<ListGroupsOutput, EndpointError<!>> {
+    let page_size = if input.page_size > 0 { input.page_size } else { 32 };
+    let starting_token = input
+        .starting_token
+        .as_ref()
+        .map(|token| Uuid::parse_str(token))
+        .transpose()
+        .map_err(|_| EndpointError::validation("Could not parse StartingToken."))?;
+
+    let page = groups_repository
+        .list_groups(starting_token, page_size)
+        .await
+        .map_err(|e| {
+            log::error!("Failed listing groups: {:?}.", e);
+            EndpointError::internal()
+        })?;
+
+    Ok(ListGroupsOutput {
+        next_token: page.next_token.map(|token| token.to_hyphenated().to_string()),
+        groups: page.groups.into_iter().map(Into::into).collect(),
+    })
+}
@@ -0,0 +1,31 @@
+use identity_service::pb::{ListPoliciesInput, ListPoliciesOutput};
+use service_core::endpoint_error::EndpointError;
+use uuid::Uuid;
+
+use crate::managed_policy::PoliciesRepository;
+
+pub(crate) async fn list_policies(
+    policies_repository: &impl PoliciesRepository,
+    input: &ListPoliciesInput,
+) -> Result<ListPoliciesOutput, EndpointError<!>> {
+    let page_size = if input.page_size > 0 { input.page_size } else { 32 };
+    let starting_token = input
+        .starting_token
+        .as_ref()
+        .map(|token| Uuid::parse_str(token))
+        .transpose()
+        .map_err(|_| EndpointError::validation("Could not parse StartingToken."))?;
+
+    let page = policies_repository
+        .list_policies(starting_token, page_size)
+        .await
+        .map_err(|e| {
+            log::error!("Failed listing policies: {:?}.", e);
+            EndpointError::internal()
+        })?;
+
+    Ok(ListPoliciesOutput {
+        next_token: page.next_token.map(|token| token.to_hyphenated().to_string()),
+        policies: page.policies.into_iter().map(Into::into).collect(),
+    })
+}
@@ -0,0 +1,59 @@
+use identity_service::pb::{ListSessionsInput, ListSessionsOutput};
+use service_core::ddb::query::Query;
+use service_core::endpoint_error::EndpointError;
+use service_core::operation_error::OperationError;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::refresh_token::RefreshTokenStore;
+use crate::utils::account::{account_key_from_id, AccountKeyFromIdError};
+use crate::Context;
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum ListSessionsError {
+    #[error("Account not found.")]
+    NotFound,
+}
+
+/// Lists the sessions of an account which neither expired nor were revoked, most recently refreshed
+/// first.
+pub(crate) async fn list_sessions(
+    ctx: &Context,
+    ddb: &impl Query,
+    refresh_token_store: &dyn RefreshTokenStore,
+    input: &ListSessionsInput,
+) -> Result<ListSessionsOutput, EndpointError<ListSessionsError>> {
+    let account_id =
+        Uuid::parse_str(&input.account_id).map_err(|_| EndpointError::validation("Invalid account ID provided."))?;
+
+    account_key_from_id(ddb, ctx.accounts_table_name.as_ref(), &account_id)
+        .await
+        .map_err(|e| match e {
+            AccountKeyFromIdError::AccountNotFound => EndpointError::operation(ListSessionsError::NotFound),
+            _ => {
+                log::error!("Failed to look up account by ID. Error: {:?}", e);
+                EndpointError::internal()
+            }
+        })?;
+    let mut sessions = refresh_token_store
+        .list_account_families(&account_id)
+        .await
+        .map_err(|e| {
+            log::error!("Failed to list the sessions of account {}: {:?}", account_id, e);
+            EndpointError::internal()
+        })?;
+    sessions.sort_by_key(|session| std::cmp::Reverse(session.last_refreshed_at));
+
+    Ok(ListSessionsOutput {
+        sessions: sessions.into_iter().map(Into::into).collect(),
+    })
+}
+
+impl OperationError for ListSessionsError {
+    fn code(&self) -> tonic::Code {
+        match self {
+            Self::NotFound => tonic::Code::NotFound,
+        }
+    }
+}
@@ -1,9 +1,30 @@
+pub mod add_group_member;
+pub mod attach_policy;
 pub mod authenticate;
 pub mod authorize;
 pub mod create_account;
+pub mod create_group;
+pub mod create_policy;
+pub mod delete_group;
+pub mod delete_policy;
 pub mod describe_account;
+pub mod describe_group;
+pub mod describe_policy;
+pub mod detach_policy;
 pub mod generate_access_token;
 pub mod get_permissions;
+pub mod get_signing_keys;
 pub mod list_accounts;
+pub mod list_groups;
+pub mod list_policies;
+pub mod list_sessions;
+pub mod normalize_permissions;
+pub mod remove_group_member;
+pub mod revoke_refresh_tokens;
+pub mod revoke_session;
+pub mod simulate_authorization;
 pub mod update_account_state;
+pub mod update_group;
 pub mod update_permissions;
+pub mod update_policy;
+pub mod watch_account_events;
@@ -0,0 +1,58 @@
+use identity_service::pb::{NormalizePermissionsInput, NormalizePermissionsOutput, RedundantPath};
+use service_core::endpoint_error::EndpointError;
+use service_core::operation_error::OperationError;
+
+use crate::user_account::PermissionsDocument;
+use crate::utils::normalization::{normalize_permissions as normalize, NormalizedPermissions};
+use crate::utils::validation::validate_resource_paths;
+use crate::Context;
+
+#[non_exhaustive]
+#[derive(Debug, thiserror::Error)]
+pub enum NormalizePermissionsError {
+    #[error("Resource path {1} in statement {0} is invalid.")]
+    InvalidResourcePath(usize, usize),
+}
+
+pub(crate) async fn normalize_permissions(
+    ctx: &Context,
+    input: NormalizePermissionsInput,
+) -> Result<NormalizePermissionsOutput, EndpointError<NormalizePermissionsError>> {
+    let permissions_document: PermissionsDocument = input
+        .permissions_document
+        .ok_or_else(|| EndpointError::validation("missing permissions document"))?
+        .into();
+    validate_resource_paths(ctx.schema_info.as_ref(), &permissions_document.statements).map_err(
+        |(stmt_idx, path_idx)| {
+            EndpointError::operation(NormalizePermissionsError::InvalidResourcePath(stmt_idx, path_idx))
+        },
+    )?;
+
+    let NormalizedPermissions {
+        permissions_document,
+        redundant_statements,
+        redundant_paths,
+    } = normalize(&permissions_document).map_err(|(stmt_idx, path_idx)| {
+        EndpointError::operation(NormalizePermissionsError::InvalidResourcePath(stmt_idx, path_idx))
+    })?;
+
+    Ok(NormalizePermissionsOutput {
+        permissions_document: Some(permissions_document.into()),
+        redundant_statements: redundant_statements.into_iter().map(|idx| idx as u32).collect(),
+        redundant_paths: redundant_paths
+            .into_iter()
+            .map(|(stmt_idx, path_idx)| RedundantPath {
+                statement_index: stmt_idx as u32,
+                path_index: path_idx as u32,
+            })
+            .collect(),
+    })
+}
+
+impl OperationError for NormalizePermissionsError {
+    fn code(&self) -> tonic::Code {
+        match self {
+            Self::InvalidResourcePath(..) => tonic::Code::InvalidArgument,
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use identity_service::pb::{RemoveGroupMemberInput, RemoveGroupMemberOutput};
+use service_core::ddb::query::Query;
+use service_core::ddb::update_item::UpdateItem;
+use service_core::endpoint_error::EndpointError;
+use service_core::operation_error::OperationError;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::utils::attachments::{update_attachment, Attachment, UpdateAttachmentError};
+use crate::utils::permissions_cache::PermissionsCache;
+use crate::Context;
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum RemoveGroupMemberError {
+    #[error("Account not found.")]
+    AccountNotFound,
+}
+
+/// Removes an account from a group. Removing an account which is not a member has no effect,
+/// which also allows leaving groups that were deleted.
+pub(crate) async fn remove_group_member(
+    ctx: &Context,
+    ddb: &(impl Query + UpdateItem),
+    permissions_cache: &PermissionsCache,
+    input: &RemoveGroupMemberInput,
+) -> Result<RemoveGroupMemberOutput, EndpointError<RemoveGroupMemberError>> {
+    let account_id =
+        Uuid::parse_str(&input.account_id).map_err(|_| EndpointError::validation("Invalid account ID provided."))?;
+    let group_id =
+        Uuid::parse_str(&input.group_id).map_err(|_| EndpointError::validation("Invalid group ID provided."))?;
+
+    update_attachment(
+        ctx,
+        ddb,
+        permissions_cache,
+        &account_id,
+        Attachment::Group(group_id),
+        false,
+    )
+    .await
+    .map_err(|e| match e {
+        UpdateAttachmentError::AccountNotFound => EndpointError::operation(RemoveGroupMemberError::AccountNotFound),
+        _ => EndpointError::internal(),
+    })?;
+
+    Ok(RemoveGroupMemberOutput {})
+}
+
+impl OperationError for RemoveGroupMemberError {
+    fn code(&self) -> tonic::Code {
+        match self {
+            Self::AccountNotFound => tonic::Code::NotFound,
+        }
+    }
+}
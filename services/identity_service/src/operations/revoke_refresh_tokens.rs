@@ -0,0 +1,56 @@
+use identity_service::pb::{RevokeRefreshTokensInput, RevokeRefreshTokensOutput};
+use service_core::ddb::query::Query;
+use service_core::endpoint_error::EndpointError;
+use service_core::operation_error::OperationError;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::refresh_token::RefreshTokenStore;
+use crate::utils::account::{account_key_from_id, AccountKeyFromIdError};
+use crate::utils::refresh_tokens::revoke_refresh_tokens as revoke;
+use crate::Context;
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum RevokeRefreshTokensError {
+    #[error("Account not found.")]
+    NotFound,
+}
+
+/// Revokes every refresh token family of an account. Access tokens already issued stay valid until
+/// they expire.
+pub(crate) async fn revoke_refresh_tokens(
+    ctx: &Context,
+    ddb: &impl Query,
+    refresh_token_store: &dyn RefreshTokenStore,
+    input: &RevokeRefreshTokensInput,
+) -> Result<RevokeRefreshTokensOutput, EndpointError<RevokeRefreshTokensError>> {
+    let account_id =
+        Uuid::parse_str(&input.account_id).map_err(|_| EndpointError::validation("Invalid account ID provided."))?;
+
+    account_key_from_id(ddb, ctx.accounts_table_name.as_ref(), &account_id)
+        .await
+        .map_err(|e| match e {
+            AccountKeyFromIdError::AccountNotFound => EndpointError::operation(RevokeRefreshTokensError::NotFound),
+            _ => {
+                log::error!("Failed to look up account by ID. Error: {:?}", e);
+                EndpointError::internal()
+            }
+        })?;
+    let revoked_families = revoke(refresh_token_store, &account_id).await.map_err(|e| {
+        log::error!("Failed to revoke the refresh tokens of account {}: {:?}", account_id, e);
+        EndpointError::internal()
+    })?;
+
+    Ok(RevokeRefreshTokensOutput {
+        revoked_families: revoked_families as u32,
+    })
+}
+
+impl OperationError for RevokeRefreshTokensError {
+    fn code(&self) -> tonic::Code {
+        match self {
+            Self::NotFound => tonic::Code::NotFound,
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use identity_service::pb::{RevokeSessionInput, RevokeSessionOutput};
+use service_core::endpoint_error::EndpointError;
+use service_core::operation_error::OperationError;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::refresh_token::RefreshTokenStore;
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum RevokeSessionError {
+    #[error("Session not found.")]
+    NotFound,
+}
+
+/// Revokes a session of an account, so that its refresh token stops working right away. Access
+/// tokens already issued in the session stay valid until they expire.
+pub(crate) async fn revoke_session(
+    refresh_token_store: &dyn RefreshTokenStore,
+    input: &RevokeSessionInput,
+) -> Result<RevokeSessionOutput, EndpointError<RevokeSessionError>> {
+    let account_id =
+        Uuid::parse_str(&input.account_id).map_err(|_| EndpointError::validation("Invalid account ID provided."))?;
+    let session_id =
+        Uuid::parse_str(&input.session_id).map_err(|_| EndpointError::validation("Invalid session ID provided."))?;
+
+    let session = refresh_token_store.get_family(&session_id).await.map_err(|e| {
+        log::error!("Failed to get session {}: {:?}", session_id, e);
+        EndpointError::internal()
+    })?;
+    // Sessions of other accounts are reported as missing, so as not to tell they exist.
+    if !session.map_or(false, |session| session.account_id == account_id) {
+        return Err(EndpointError::operation(RevokeSessionError::NotFound));
+    }
+
+    refresh_token_store.delete_family(&session_id).await.map_err(|e| {
+        log::error!("Failed to revoke session {}: {:?}", session_id, e);
+        EndpointError::internal()
+    })?;
+
+    Ok(RevokeSessionOutput {})
+}
+
+impl OperationError for RevokeSessionError {
+    fn code(&self) -> tonic::Code {
+        match self {
+            Self::NotFound => tonic::Code::NotFound,
+        }
+    }
+}
@@ -0,0 +1,318 @@
+use std::collections::HashMap;
+
+use identity_service::pb::conversion::AccessRequestParseError;
+use identity_service::pb::{SimulateAuthorizationInput, SimulateAuthorizationOutput, SimulatedAuthorization};
+use service_core::ddb::get_item::GetItem;
+use service_core::ddb::query::Query;
+use service_core::endpoint_error::EndpointError;
+use service_core::operation_error::OperationError;
+use service_core::resource_access::types::PathSet;
+use service_core::resource_access::variables::PolicyVariables;
+use service_core::resource_access::{AccessKind, AccessPathSet, AccessRequest};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::account_group::GroupsRepository;
+use crate::managed_policy::PoliciesRepository;
+use crate::operations::authorize::{granted_paths, path_denials};
+use crate::user_account::{
+    AccountAttributes, AccountLookup, AccountsRepository, GetAccountError, PermissionsDocument, UserAccount,
+};
+use crate::utils::permissions::{
+    compile_subject_access_path_set, merge_access_request_paths, subject_variables, SubjectAccessPathSetError,
+};
+use crate::utils::permissions_cache::CachedAccessPathSet;
+use crate::utils::validation::validate_resource_paths;
+use crate::Context;
+
+#[non_exhaustive]
+#[derive(Error, Debug)]
+pub enum SimulateAuthorizationError {
+    #[error("Account not found.")]
+    NotFound,
+
+    #[error("Resource path {1} in statement {0} is invalid.")]
+    InvalidStatementPath(usize, usize),
+
+    #[error("Resource path at index {1} of access request {0} is invalid: {2}.")]
+    InvalidResourcePath(usize, usize, String),
+}
+
+/// Authorizes each access request as __Authorize__ would, always explaining denials and pruning the
+/// requested paths. The candidate permissions document, if any, takes the place of the account's own
+/// one, while its attached policies and groups still apply.
+///
+/// The `${jwt.*}` policy variables are bound to the claims an access token of the account would carry,
+/// unless overridden by the given claims.
+pub(crate) async fn simulate_authorization(
+    ctx: &Context,
+    ddb: &(impl GetItem + Query),
+    accounts_repository: &impl AccountsRepository,
+    policies_repository: &impl PoliciesRepository,
+    groups_repository: &impl GroupsRepository,
+    input: &SimulateAuthorizationInput,
+) -> Result<SimulateAuthorizationOutput, EndpointError<SimulateAuthorizationError>> {
+    let account_id = input
+        .account_id
+        .as_ref()
+        .map(|account_id| Uuid::parse_str(account_id.clone().as_ref()))
+        .transpose()
+        .map_err(|_| EndpointError::validation("Invalid account ID provided."))?;
+    let candidate_document: Option<PermissionsDocument> = input.permissions_document.clone().map(Into::into);
+
+    if account_id.is_none() && candidate_document.is_none() {
+        return Err(EndpointError::validation(
+            "Either an account ID or a permissions document must be provided.",
+        ));
+    }
+    if let Some(candidate_document) = &candidate_document {
+        validate_resource_paths(ctx.schema_info.as_ref(), &candidate_document.statements).map_err(
+            |(stmt_idx, path_idx)| {
+                EndpointError::operation(SimulateAuthorizationError::InvalidStatementPath(stmt_idx, path_idx))
+            },
+        )?;
+    }
+
+    let access_requests = input
+        .access_requests
+        .iter()
+        .enumerate()
+        .map(|(request_idx, access_request)| {
+            AccessRequest::try_from(access_request.clone()).map_err(|e| match e {
+                AccessRequestParseError::CompileError(idx, path)
+                | AccessRequestParseError::MultiRootPath(idx, path) => {
+                    EndpointError::operation(SimulateAuthorizationError::InvalidResourcePath(request_idx, idx, path))
+                }
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut claims = match &account_id {
+        Some(account_id) => accounts_repository
+            .get_account(&AccountLookup::ById(*account_id), &AccountAttributes::Profile)
+            .await
+            .map(|user_account| account_claims(&user_account))
+            .map_err(|e| match e {
+                GetAccountError::NotFound => EndpointError::operation(SimulateAuthorizationError::NotFound),
+                _ => {
+                    log::error!("Failed retrieving account: {:?}.", e);
+                    EndpointError::internal()
+                }
+            })?,
+        None => HashMap::new(),
+    };
+    claims.extend(input.claims.clone());
+
+    let variables = subject_variables(account_id.as_ref(), &claims);
+    let mut compiled: HashMap<AccessKind, CachedAccessPathSet> = HashMap::new();
+    let mut results = Vec::with_capacity(access_requests.len());
+
+    for access_request in access_requests {
+        let access_kind = access_request.kind;
+        if !compiled.contains_key(&access_kind) {
+            let mut subject_access_path_set = compile_subject_access_path_set(
+                ddb,
+                ctx.accounts_table_name.as_ref(),
+                policies_repository,
+                groups_repository,
+                account_id.as_ref(),
+                candidate_document.clone(),
+                access_kind,
+            )
+            .await
+            .map_err(|e| match e {
+                SubjectAccessPathSetError::AccountNotFound => {
+                    EndpointError::operation(SimulateAuthorizationError::NotFound)
+                }
+                _ => EndpointError::internal(),
+            })?;
+            subject_access_path_set.access_path_set =
+                subject_access_path_set.access_path_set.bind_variables(&variables);
+            compiled.insert(access_kind, subject_access_path_set);
+        }
+
+        let CachedAccessPathSet {
+            permissions_document,
+            access_path_set,
+            ..
+        } = &compiled[&access_kind];
+        let desired_paths = merge_access_request_paths(access_request);
+        results.push(simulate_request(
+            permissions_document,
+            access_path_set,
+            access_kind,
+            &desired_paths,
+            &variables,
+        ));
+    }
+
+    Ok(SimulateAuthorizationOutput { results })
+}
+
+/// The string claims of an access token issued to the account, as `Claims::string_claims` has them.
+fn account_claims(user_account: &UserAccount) -> HashMap<String, String> {
+    HashMap::from([
+        ("sub".to_owned(), user_account.account_id.to_hyphenated().to_string()),
+        ("email".to_owned(), user_account.email.clone()),
+        ("firstName".to_owned(), user_account.first_name.clone()),
+        ("lastName".to_owned(), user_account.last_name.clone()),
+    ])
+}
+
+/// Authorizes the desired paths against an access path set whose variables are bound, explaining the
+/// denials if any.
+fn simulate_request(
+    permissions_document: &PermissionsDocument,
+    access_path_set: &AccessPathSet,
+    access_kind: AccessKind,
+    desired_paths: &PathSet,
+    variables: &PolicyVariables,
+) -> SimulatedAuthorization {
+    let permission_granted = access_path_set.permits(desired_paths);
+    if permission_granted {
+        return SimulatedAuthorization {
+            permission_granted,
+            denials: vec![],
+            granted_paths: vec![],
+        };
+    }
+
+    SimulatedAuthorization {
+        permission_granted,
+        denials: path_denials(
+            permissions_document,
+            access_path_set,
+            access_kind,
+            desired_paths,
+            variables,
+        ),
+        granted_paths: granted_paths(access_path_set, desired_paths),
+    }
+}
+
+impl OperationError for SimulateAuthorizationError {
+    fn code(&self) -> tonic::Code {
+        match self {
+            Self::NotFound => tonic::Code::NotFound,
+            Self::InvalidStatementPath(..) => tonic::Code::InvalidArgument,
+            Self::InvalidResourcePath(..) => tonic::Code::InvalidArgument,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use identity_service::pb::path_denial::Reason;
+    use service_core::resource_access::string_interop::compiler::from_string;
+    use service_core::resource_access::Effect;
+
+    use super::*;
+    use crate::user_account::types::AccountState;
+    use crate::user_account::RenderedPolicyStatement;
+    use crate::utils::permissions::get_access_path_set;
+
+    fn statement(effect: Effect, paths: &[&str]) -> RenderedPolicyStatement {
+        RenderedPolicyStatement {
+            effect,
+            access_kind: AccessKind::Query,
+            paths: paths.iter().map(ToString::to_string).collect(),
+            not_before: None,
+            not_after: None,
+            active_accounts_only: false,
+        }
+    }
+
+    fn simulate(
+        statements: Vec<RenderedPolicyStatement>,
+        variables: &PolicyVariables,
+        path: &str,
+    ) -> SimulatedAuthorization {
+        let permissions_document = PermissionsDocument { statements };
+        let access_path_set = get_access_path_set(
+            &permissions_document,
+            &[],
+            &[],
+            AccessKind::Query,
+            true,
+            0,
+            &AccountState::Active,
+        )
+        .unwrap()
+        .bind_variables(variables);
+        let desired_paths = merge_access_request_paths(AccessRequest {
+            kind: AccessKind::Query,
+            paths: from_string(path).unwrap().into_paths(),
+        });
+
+        simulate_request(
+            &permissions_document,
+            &access_path_set,
+            AccessKind::Query,
+            &desired_paths,
+            variables,
+        )
+    }
+
+    fn account() -> UserAccount {
+        UserAccount {
+            account_id: Uuid::new_v4(),
+            email: "jane@uni.edu".to_owned(),
+            first_name: "Jane".to_owned(),
+            last_name: "Doe".to_owned(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn allowed_requests_are_granted() {
+        let result = simulate(
+            vec![statement(Effect::Allow, &["accounts::{id, email}"])],
+            &PolicyVariables::default(),
+            "accounts::email",
+        );
+
+        assert!(result.permission_granted);
+        assert!(result.denials.is_empty());
+    }
+
+    #[test]
+    fn denied_requests_are_explained() {
+        let result = simulate(
+            vec![
+                statement(Effect::Allow, &["accounts::*"]),
+                statement(Effect::Deny, &["accounts::password"]),
+            ],
+            &PolicyVariables::default(),
+            "accounts::{email, password}",
+        );
+
+        assert!(!result.permission_granted);
+        assert_eq!(result.denials.len(), 1);
+        assert_eq!(result.denials[0].reason, Reason::Denied as i32);
+        assert_eq!(result.granted_paths, vec!["accounts::email"]);
+    }
+
+    #[test]
+    fn variables_are_bound_to_the_claims_of_the_account() {
+        let account = account();
+        let variables = subject_variables(Some(&account.account_id), &account_claims(&account));
+        let statements = || {
+            vec![statement(
+                Effect::Allow,
+                &["accounts(email: ${jwt.email})::{id, email}"],
+            )]
+        };
+
+        assert!(simulate(statements(), &variables, "accounts(email: \"jane@uni.edu\")::id").permission_granted);
+        assert!(!simulate(statements(), &variables, "accounts(email: \"john@uni.edu\")::id").permission_granted);
+        assert!(
+            !simulate(
+                statements(),
+                &PolicyVariables::default(),
+                "accounts(email: \"jane@uni.edu\")::id"
+            )
+            .permission_granted
+        );
+    }
+}
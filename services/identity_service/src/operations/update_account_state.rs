@@ -1,6 +1,9 @@
-use aws_sdk_dynamodb::model::AttributeValue;
+use aws_sdk_dynamodb::model::{AttributeValue, ReturnValue};
 use common_macros::hash_map;
-use identity_service::pb::{UpdateAccountStateInput, UpdateAccountStateOutput};
+use identity_service::pb::account_event::Event;
+use identity_service::pb::{
+    AccountEvent, AccountState as AccountStateModel, UpdateAccountStateInput, UpdateAccountStateOutput,
+};
 use service_core::ddb::query::Query;
 use service_core::ddb::update_item::{UpdateItem, UpdateItemInput};
 use service_core::endpoint_error::EndpointError;
@@ -8,8 +11,13 @@ use service_core::operation_error::OperationError;
 use thiserror::Error;
 use uuid::Uuid;
 
+use crate::refresh_token::RefreshTokenStore;
 use crate::user_account::types::AccountState;
 use crate::utils::account::{account_key_from_id, AccountKeyFromIdError};
+use crate::utils::account_events::AccountEvents;
+use crate::utils::permissions::updated_permissions_version;
+use crate::utils::permissions_cache::PermissionsCache;
+use crate::utils::refresh_tokens::revoke_refresh_tokens;
 use crate::Context;
 
 #[non_exhaustive]
@@ -19,9 +27,15 @@ pub enum UpdateAccountStateError {
     NotFound,
 }
 
+/// Updates the state of an account. Since statements may be restricted to active accounts, this also
+/// increases the permissions version of the account. Deactivating an account revokes its refresh
+/// tokens.
 pub(crate) async fn update_account_state(
     ctx: &Context,
     ddb: &(impl Query + UpdateItem),
+    permissions_cache: &PermissionsCache,
+    refresh_token_store: &dyn RefreshTokenStore,
+    account_events: &AccountEvents,
     mut input: UpdateAccountStateInput,
 ) -> Result<UpdateAccountStateOutput, EndpointError<UpdateAccountStateError>> {
     let account_id = Uuid::parse_str(input.account_id.as_mut())
@@ -41,19 +55,34 @@ pub(crate) async fn update_account_state(
     let update_item_input = UpdateItemInput::builder()
         .table_name(ctx.accounts_table_name.clone())
         .key(key)
-        .update_expression("SET AccountState = :account_state")
+        .update_expression("SET AccountState = :account_state ADD PermissionsVersion :one")
         .expression_attribute_values(hash_map! {
             ":account_state".to_owned() => AttributeValue::M(
                 serde_ddb::to_hashmap(&account_state)
                     .expect("failed permissions document serialization")
             ),
+            ":one".to_owned() => AttributeValue::N("1".to_owned()),
         })
+        .return_values(ReturnValue::UpdatedNew)
         .build();
 
-    ddb.update_item(update_item_input).await.map_err(|e| {
+    let output = ddb.update_item(update_item_input).await.map_err(|e| {
         log::error!("Failed to update item in DynamoDB. Original error: {:?}.", e);
         EndpointError::internal()
     })?;
+    permissions_cache.invalidate(&account_id, updated_permissions_version(output.attributes.as_ref()));
+    if account_state == AccountState::Deactivated {
+        revoke_refresh_tokens(refresh_token_store, &account_id)
+            .await
+            .map_err(|e| {
+                log::error!("Failed to revoke the refresh tokens of account {}: {:?}", account_id, e);
+                EndpointError::internal()
+            })?;
+    }
+    account_events.publish(AccountEvent {
+        account_id: account_id.to_hyphenated().to_string(),
+        event: Some(Event::AccountStateChanged(AccountStateModel::from(account_state) as i32)),
+    });
 
     Ok(UpdateAccountStateOutput {})
 }
@@ -0,0 +1,67 @@
+use identity_service::pb::{UpdateGroupInput, UpdateGroupOutput};
+use service_core::endpoint_error::EndpointError;
+use service_core::operation_error::OperationError;
+use uuid::Uuid;
+
+use crate::account_group::{AccountGroup, GroupStoreError, GroupsRepository};
+use crate::user_account::PermissionsDocument;
+use crate::utils::permissions_cache::PermissionsCache;
+use crate::utils::validation::validate_resource_paths;
+use crate::Context;
+
+#[non_exhaustive]
+#[derive(Debug, thiserror::Error)]
+pub enum UpdateGroupError {
+    #[error("Group not found.")]
+    NotFound,
+
+    #[error("Resource path {1} in statement {0} is invalid.")]
+    InvalidResourcePath(usize, usize),
+}
+
+pub(crate) async fn update_group(
+    ctx: &Context,
+    groups_repository: &impl GroupsRepository,
+    permissions_cache: &PermissionsCache,
+    input: UpdateGroupInput,
+) -> Result<UpdateGroupOutput, EndpointError<UpdateGroupError>> {
+    let group_id =
+        Uuid::parse_str(&input.group_id).map_err(|_| EndpointError::validation("Invalid group ID provided."))?;
+    if input.name.is_empty() {
+        return Err(EndpointError::validation("Group name is required."));
+    }
+
+    let permissions_document: PermissionsDocument = input
+        .permissions_document
+        .ok_or_else(|| EndpointError::validation("missing permissions document"))?
+        .into();
+    validate_resource_paths(ctx.schema_info.as_ref(), &permissions_document.statements).map_err(
+        |(stmt_idx, path_idx)| EndpointError::operation(UpdateGroupError::InvalidResourcePath(stmt_idx, path_idx)),
+    )?;
+
+    let group = AccountGroup {
+        group_id,
+        name: input.name,
+        description: input.description,
+        permissions_document,
+    };
+    groups_repository.update_group(&group).await.map_err(|e| match e {
+        GroupStoreError::NotFound => EndpointError::operation(UpdateGroupError::NotFound),
+        _ => {
+            log::error!("Update group failed: {:?}", e);
+            EndpointError::internal()
+        }
+    })?;
+    permissions_cache.invalidate_all();
+
+    Ok(UpdateGroupOutput {})
+}
+
+impl OperationError for UpdateGroupError {
+    fn code(&self) -> tonic::Code {
+        match self {
+            Self::NotFound => tonic::Code::NotFound,
+            Self::InvalidResourcePath(..) => tonic::Code::InvalidArgument,
+        }
+    }
+}
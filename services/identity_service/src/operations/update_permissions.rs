@@ -1,6 +1,9 @@
-use aws_sdk_dynamodb::model::AttributeValue;
+use std::collections::HashMap;
+
+use aws_sdk_dynamodb::model::{AttributeValue, ReturnValue};
 use common_macros::hash_map;
-use identity_service::pb::{UpdatePermissionsInput, UpdatePermissionsOutput};
+use identity_service::pb::account_event::Event;
+use identity_service::pb::{AccountEvent, PermissionsChanged, UpdatePermissionsInput, UpdatePermissionsOutput};
 use service_core::ddb::query::Query;
 use service_core::ddb::update_item::{UpdateItem, UpdateItemInput};
 use service_core::endpoint_error::EndpointError;
@@ -8,8 +11,15 @@ use service_core::operation_error::OperationError;
 use thiserror::Error;
 use uuid::Uuid;
 
+use crate::user_account::types::AccountAttr;
 use crate::user_account::PermissionsDocument;
 use crate::utils::account::{account_key_from_id, AccountKeyFromIdError};
+use crate::utils::account_events::AccountEvents;
+use crate::utils::audit::log_permissions_changes;
+use crate::utils::normalization::normalize_permissions;
+use crate::utils::permissions::next_permissions_version;
+use crate::utils::permissions_cache::PermissionsCache;
+use crate::utils::permissions_diff::permissions_changes;
 use crate::utils::validation::validate_resource_paths;
 use crate::Context;
 
@@ -26,20 +36,37 @@ pub enum UpdatePermissionsError {
 pub(crate) async fn update_permissions(
     ctx: &Context,
     ddb: &(impl Query + UpdateItem),
+    permissions_cache: &PermissionsCache,
+    account_events: &AccountEvents,
     input: &UpdatePermissionsInput,
 ) -> Result<UpdatePermissionsOutput, EndpointError<UpdatePermissionsError>> {
     let account_id = Uuid::parse_str(input.account_id.clone().as_mut())
         .map_err(|_| EndpointError::validation("Invalid account ID provided."))?;
-    let permissions_document: PermissionsDocument = input
+    let mut permissions_document: PermissionsDocument = input
         .permissions_document
         .as_ref()
         .map(|s| s.clone())
         .ok_or_else(|| EndpointError::validation("missing permissions document"))?
         .into();
 
-    validate_resource_paths(&permissions_document.statements).map_err(|(stmt_idx, path_idx)| {
-        EndpointError::operation(UpdatePermissionsError::InvalidResourcePath(stmt_idx, path_idx))
-    })?;
+    validate_resource_paths(ctx.schema_info.as_ref(), &permissions_document.statements).map_err(
+        |(stmt_idx, path_idx)| {
+            EndpointError::operation(UpdatePermissionsError::InvalidResourcePath(stmt_idx, path_idx))
+        },
+    )?;
+    if input.normalize {
+        permissions_document = normalize_permissions(&permissions_document)
+            .map_err(|(stmt_idx, path_idx)| {
+                EndpointError::operation(UpdatePermissionsError::InvalidResourcePath(stmt_idx, path_idx))
+            })?
+            .permissions_document;
+        // Normalization rewrites paths, so what it produces has to pass validation as well.
+        validate_resource_paths(ctx.schema_info.as_ref(), &permissions_document.statements).map_err(
+            |(stmt_idx, path_idx)| {
+                EndpointError::operation(UpdatePermissionsError::InvalidResourcePath(stmt_idx, path_idx))
+            },
+        )?;
+    }
 
     let key = account_key_from_id(ddb, ctx.accounts_table_name.as_ref(), &account_id)
         .await
@@ -50,21 +77,51 @@ pub(crate) async fn update_permissions(
     let update_item_input = UpdateItemInput::builder()
         .table_name(ctx.accounts_table_name.clone())
         .key(key)
-        .update_expression("SET PermissionsDocument = :permissions_document")
+        .update_expression("SET PermissionsDocument = :permissions_document ADD PermissionsVersion :one")
         .expression_attribute_values(hash_map! {
             ":permissions_document".to_owned() => AttributeValue::M(
                 serde_ddb::to_hashmap(&permissions_document)
                     .expect("failed permissions document serialization")
             ),
+            ":one".to_owned() => AttributeValue::N("1".to_owned()),
         })
+        .return_values(ReturnValue::UpdatedOld)
         .build();
 
-    ddb.update_item(update_item_input).await.map_err(|e| {
+    let output = ddb.update_item(update_item_input).await.map_err(|e| {
         log::error!("Failed to update item in DynamoDB. Original error: {:?}.", e);
         EndpointError::internal()
     })?;
+    permissions_cache.invalidate(&account_id, next_permissions_version(output.attributes.as_ref()));
 
-    Ok(UpdatePermissionsOutput {})
+    let previous_document = previous_permissions_document(output.attributes.as_ref());
+    let changes = permissions_changes(&previous_document, &permissions_document);
+    log_permissions_changes(&account_id, &changes);
+
+    let changes: Vec<_> = changes.into_iter().map(Into::into).collect();
+    if !changes.is_empty() {
+        account_events.publish(AccountEvent {
+            account_id: account_id.to_hyphenated().to_string(),
+            event: Some(Event::PermissionsChanged(PermissionsChanged {
+                changes: changes.clone(),
+            })),
+        });
+    }
+
+    Ok(UpdatePermissionsOutput { changes })
+}
+
+/// Gets the permissions document an account had before an update, given the attributes returned with
+/// `ReturnValue::UpdatedOld`.
+fn previous_permissions_document(attributes: Option<&HashMap<String, AttributeValue>>) -> PermissionsDocument {
+    let attribute_name = AccountAttr::PermissionsDocument.to_string();
+    match attributes.and_then(|attributes| attributes.get(&attribute_name)) {
+        Some(AttributeValue::M(document)) => serde_ddb::from_hashmap(document.clone()).unwrap_or_else(|e| {
+            log::error!("Failed to deserialize the previous permissions document: {:?}.", e);
+            PermissionsDocument::default()
+        }),
+        _ => PermissionsDocument::default(),
+    }
 }
 
 impl OperationError for UpdatePermissionsError {
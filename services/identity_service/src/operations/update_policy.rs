@@ -0,0 +1,67 @@
+use identity_service::pb::{UpdatePolicyInput, UpdatePolicyOutput};
+use service_core::endpoint_error::EndpointError;
+use service_core::operation_error::OperationError;
+use uuid::Uuid;
+
+use crate::managed_policy::{ManagedPolicy, PoliciesRepository, PolicyStoreError};
+use crate::user_account::PermissionsDocument;
+use crate::utils::permissions_cache::PermissionsCache;
+use crate::utils::validation::validate_resource_paths;
+use crate::Context;
+
+#[non_exhaustive]
+#[derive(Debug, thiserror::Error)]
+pub enum UpdatePolicyError {
+    #[error("Policy not found.")]
+    NotFound,
+
+    #[error("Resource path {1} in statement {0} is invalid.")]
+    InvalidResourcePath(usize, usize),
+}
+
+pub(crate) async fn update_policy(
+    ctx: &Context,
+    policies_repository: &impl PoliciesRepository,
+    permissions_cache: &PermissionsCache,
+    input: UpdatePolicyInput,
+) -> Result<UpdatePolicyOutput, EndpointError<UpdatePolicyError>> {
+    let policy_id =
+        Uuid::parse_str(&input.policy_id).map_err(|_| EndpointError::validation("Invalid policy ID provided."))?;
+    if input.name.is_empty() {
+        return Err(EndpointError::validation("Policy name is required."));
+    }
+
+    let permissions_document: PermissionsDocument = input
+        .permissions_document
+        .ok_or_else(|| EndpointError::validation("missing permissions document"))?
+        .into();
+    validate_resource_paths(ctx.schema_info.as_ref(), &permissions_document.statements).map_err(
+        |(stmt_idx, path_idx)| EndpointError::operation(UpdatePolicyError::InvalidResourcePath(stmt_idx, path_idx)),
+    )?;
+
+    let policy = ManagedPolicy {
+        policy_id,
+        name: input.name,
+        description: input.description,
+        permissions_document,
+    };
+    policies_repository.update_policy(&policy).await.map_err(|e| match e {
+        PolicyStoreError::NotFound => EndpointError::operation(UpdatePolicyError::NotFound),
+        _ => {
+            log::error!("Update policy failed: {:?}", e);
+            EndpointError::internal()
+        }
+    })?;
+    permissions_cache.invalidate_all();
+
+    Ok(UpdatePolicyOutput {})
+}
+
+impl OperationError for UpdatePolicyError {
+    fn code(&self) -> tonic::Code {
+        match self {
+            Self::NotFound => tonic::Code::NotFound,
+            Self::InvalidResourcePath(..) => tonic::Code::InvalidArgument,
+        }
+    }
+}
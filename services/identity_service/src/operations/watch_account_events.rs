@@ -0,0 +1,23 @@
+use std::pin::Pin;
+
+use futures_util::stream::{Stream, StreamExt};
+use identity_service::pb::{AccountEvent, WatchAccountEventsInput};
+use service_core::endpoint_error::EndpointError;
+use tonic::Status;
+use uuid::Uuid;
+
+use crate::utils::account_events::AccountEvents;
+
+pub(crate) type AccountEventStream = Pin<Box<dyn Stream<Item = Result<AccountEvent, Status>> + Send>>;
+
+/// Streams the changes made to an account from now on. The account is not looked up, so that
+/// watching an account does not tell whether it exists.
+pub(crate) async fn watch_account_events(
+    account_events: &AccountEvents,
+    input: &WatchAccountEventsInput,
+) -> Result<AccountEventStream, EndpointError<!>> {
+    let account_id =
+        Uuid::parse_str(&input.account_id).map_err(|_| EndpointError::validation("Invalid account ID provided."))?;
+
+    Ok(Box::pin(account_events.subscribe(&account_id).map(Ok)))
+}
@@ -4,7 +4,6 @@ use thiserror::Error;
 use super::identity_service::access_request::AccessKind as AccessKindPb;
 use super::identity_service::AccessRequest;
 
-
 #[derive(Debug, Error)]
 pub enum AccessRequestParseError {
     #[error("Path {0} is invalid: {1}.")]
@@ -39,6 +38,8 @@ impl TryFrom<AccessRequest> for service_core::resource_access::AccessRequest {
             paths,
             kind: if model.access_kind == AccessKindModel::Mutation as i32 {
                 AccessKind::Mutation
+            } else if model.access_kind == AccessKindModel::Subscription as i32 {
+                AccessKind::Subscription
             } else {
                 AccessKind::Query
             },
@@ -52,6 +53,7 @@ impl From<service_core::resource_access::AccessRequest> for AccessRequest {
             access_kind: match val.kind {
                 AccessKindModel::Query => AccessKindPb::Query,
                 AccessKindModel::Mutation => AccessKindPb::Mutation,
+                AccessKindModel::Subscription => AccessKindPb::Subscription,
             } as i32,
             paths: val.paths.into_iter().map(|v| v.to_string()).collect(),
         }
@@ -1,6 +1,6 @@
 use std::sync::LazyLock;
 
-use service_core::resource_access::{AccessKind, PolicyStatement};
+use service_core::resource_access::{AccessKind, Effect, PolicyStatement};
 
 use crate::permissions::helper::compose_statement;
 
@@ -8,5 +8,9 @@ use crate::permissions::helper::compose_statement;
 pub static ANONYMOUS_PERMISSIONS: LazyLock<Vec<PolicyStatement>> = LazyLock::new(|| {
     const ALLOWED_MUTATIONS: [&str; 1] = ["authenticate(email: *, password: *)::*"];
 
-    vec![compose_statement(AccessKind::Mutation, ALLOWED_MUTATIONS)]
+    vec![compose_statement(
+        Effect::Allow,
+        AccessKind::Mutation,
+        ALLOWED_MUTATIONS,
+    )]
 });
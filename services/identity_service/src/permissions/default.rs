@@ -1,13 +1,30 @@
 use std::sync::LazyLock;
 
-use service_core::resource_access::{AccessKind, PolicyStatement};
+use service_core::resource_access::{AccessKind, Effect, PolicyStatement};
 
 use crate::permissions::helper::compose_statement;
 
 
-/// Permissions given to authenticated entities by default.
+/// Permissions given to authenticated entities by default, including self-service access to their
+/// own account.
 pub static DEFAULT_PERMISSIONS: LazyLock<Vec<PolicyStatement>> = LazyLock::new(|| {
-    const ALLOWED_MUTATIONS: [&str; 1] = ["generateAccessToken(refreshToken: *)::*"];
+    const ALLOWED_QUERIES: [&str; 2] = [
+        "account(id: ${self.accountId})::*",
+        "sessions(accountId: ${self.accountId})::*",
+    ];
+    const ALLOWED_MUTATIONS: [&str; 3] = [
+        "generateAccessToken(refreshToken: *)::*",
+        "revokeRefreshTokens(accountId: ${self.accountId})",
+        "revokeSession(accountId: ${self.accountId}, sessionId: *)",
+    ];
+    const ALLOWED_SUBSCRIPTIONS: [&str; 2] = [
+        "accountStateChanged(accountId: ${self.accountId})::*",
+        "permissionsChanged(accountId: ${self.accountId})::*",
+    ];
 
-    vec![compose_statement(AccessKind::Mutation, ALLOWED_MUTATIONS)]
+    vec![
+        compose_statement(Effect::Allow, AccessKind::Query, ALLOWED_QUERIES),
+        compose_statement(Effect::Allow, AccessKind::Mutation, ALLOWED_MUTATIONS),
+        compose_statement(Effect::Allow, AccessKind::Subscription, ALLOWED_SUBSCRIPTIONS),
+    ]
 });
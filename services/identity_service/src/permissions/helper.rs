@@ -1,8 +1,9 @@
 use service_core::resource_access::string_interop::compiler::from_string;
-use service_core::resource_access::{AccessKind, PolicyStatement};
+use service_core::resource_access::{AccessKind, Effect, PolicyStatement};
 
-pub fn compose_statement<const N: usize>(access_kind: AccessKind, paths: [&str; N]) -> PolicyStatement {
+pub fn compose_statement<const N: usize>(effect: Effect, access_kind: AccessKind, paths: [&str; N]) -> PolicyStatement {
     PolicyStatement {
+        effect,
         kind: access_kind,
         paths: paths
             .iter()
@@ -0,0 +1,303 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use aws_sdk_dynamodb::error::{PutItemError, PutItemErrorKind, UpdateItemError, UpdateItemErrorKind};
+use aws_sdk_dynamodb::model::{AttributeValue, ReturnValue};
+use aws_sdk_dynamodb::types::SdkError;
+use chrono::Utc;
+use common_macros::hash_map;
+use service_core::ddb::delete_item::{DeleteItem, DeleteItemInput};
+use service_core::ddb::get_item::{GetItem, GetItemInput};
+use service_core::ddb::put_item::{PutItem, PutItemInput};
+use service_core::ddb::update_item::{UpdateItem, UpdateItemInput};
+use uuid::Uuid;
+
+use super::{RefreshToken, RefreshTokenFamily, RefreshTokenStore, RefreshTokenStoreError};
+
+
+pub trait ThreadSafeDdbClient: PutItem + GetItem + UpdateItem + DeleteItem + Send + Sync {}
+impl<T: PutItem + GetItem + UpdateItem + DeleteItem + Send + Sync> ThreadSafeDdbClient for T {}
+
+
+/// Keeps refresh tokens in a DynamoDB table keyed by the `Key` string attribute, with `ExpiresAt` as
+/// its TTL attribute. Tokens, families and the list of families of every account are items of their
+/// own, told apart by the prefix of their key.
+///
+/// # Notes
+///
+/// DynamoDB deletes expired items lazily, so expiry is also checked on every read. The families of
+/// an account expire with the latest of them, so they are extended whenever a family is added or
+/// rotated, and the ones which expired or were revoked are dropped whenever they are listed or added
+/// to.
+pub struct DdbRefreshTokenStore<T: ThreadSafeDdbClient> {
+    ddb: T,
+    refresh_tokens_table_name: String,
+}
+
+impl<T: ThreadSafeDdbClient> DdbRefreshTokenStore<T> {
+    pub fn new(ddb: T, refresh_tokens_table_name: impl Into<String>) -> Self {
+        Self {
+            ddb,
+            refresh_tokens_table_name: refresh_tokens_table_name.into(),
+        }
+    }
+
+    /// Creates the map to be used as key to the Refresh Tokens datastore.
+    fn key(&self, prefix: &str, id: &Uuid) -> HashMap<String, AttributeValue> {
+        hash_map! {
+            "Key".to_string() => AttributeValue::S(format!("{}#{}", prefix, id.to_hyphenated())),
+        }
+    }
+
+    /// Writes an item under the given key.
+    async fn put(
+        &self,
+        key: HashMap<String, AttributeValue>,
+        mut item: HashMap<String, AttributeValue>,
+    ) -> Result<(), RefreshTokenStoreError> {
+        item.extend(key);
+        let put_item_input = PutItemInput::builder()
+            .table_name(self.refresh_tokens_table_name.as_str())
+            .item(item)
+            .build();
+
+        self.ddb
+            .put_item(put_item_input)
+            .await
+            .map_err(|e| RefreshTokenStoreError::Other(e.into()))?;
+
+        Ok(())
+    }
+
+    /// Deletes the item under the given key, returning its attributes if it existed.
+    async fn delete(
+        &self,
+        key: HashMap<String, AttributeValue>,
+    ) -> Result<Option<HashMap<String, AttributeValue>>, RefreshTokenStoreError> {
+        let delete_item_input = DeleteItemInput::builder()
+            .table_name(self.refresh_tokens_table_name.as_str())
+            .key(key)
+            .return_values(ReturnValue::AllOld)
+            .build();
+        let output = self
+            .ddb
+            .delete_item(delete_item_input)
+            .await
+            .map_err(|e| RefreshTokenStoreError::Other(e.into()))?;
+
+        Ok(output.attributes)
+    }
+
+    /// Adds a family to the families of its account, extending them to the expiry of the family
+    /// unless they already outlive it, and drops the ones which expired or were revoked.
+    async fn add_account_family(&self, family: &RefreshTokenFamily) -> Result<(), RefreshTokenStoreError> {
+        let key = self.key("AccountFamilies", &family.account_id);
+        let new_family_ids = AttributeValue::Ss(vec![family.family_id.to_hyphenated().to_string()]);
+        let update_item_input = UpdateItemInput::builder()
+            .table_name(self.refresh_tokens_table_name.as_str())
+            .key(key.clone())
+            .update_expression("ADD Families :family SET ExpiresAt = :expires_at")
+            .condition_expression("attribute_not_exists(ExpiresAt) OR ExpiresAt < :expires_at")
+            .expression_attribute_values(hash_map! {
+                ":family".to_owned() => new_family_ids.clone(),
+                ":expires_at".to_owned() => AttributeValue::N(family.expires_at.to_string()),
+            })
+            .return_values(ReturnValue::AllNew)
+            .build();
+
+        let output = match self.ddb.update_item(update_item_input).await {
+            Ok(output) => output,
+            // The families already outlive this one, which only has to be added to them.
+            Err(SdkError::ServiceError {
+                err:
+                    UpdateItemError {
+                        kind: UpdateItemErrorKind::ConditionalCheckFailedException(_),
+                        ..
+                    },
+                ..
+            }) => {
+                let update_item_input = UpdateItemInput::builder()
+                    .table_name(self.refresh_tokens_table_name.as_str())
+                    .key(key)
+                    .update_expression("ADD Families :family")
+                    .expression_attribute_values(hash_map! {
+                        ":family".to_owned() => new_family_ids,
+                    })
+                    .return_values(ReturnValue::AllNew)
+                    .build();
+                self.ddb
+                    .update_item(update_item_input)
+                    .await
+                    .map_err(|e| RefreshTokenStoreError::Other(e.into()))?
+            }
+            Err(e) => return Err(RefreshTokenStoreError::Other(e.into())),
+        };
+
+        let family_ids = match output.attributes {
+            Some(item) => family_ids(&item)?,
+            None => vec![],
+        };
+        self.prune_account_families(&family.account_id, family_ids).await?;
+        Ok(())
+    }
+
+    /// Gets the families of an account with the given IDs, dropping the ones which expired or were
+    /// revoked from the families of the account.
+    async fn prune_account_families(
+        &self,
+        account_id: &Uuid,
+        family_ids: Vec<Uuid>,
+    ) -> Result<Vec<RefreshTokenFamily>, RefreshTokenStoreError> {
+        let mut families = vec![];
+        let mut stale_family_ids = vec![];
+        for family_id in family_ids {
+            match self.get_family(&family_id).await? {
+                Some(family) => families.push(family),
+                None => stale_family_ids.push(family_id.to_hyphenated().to_string()),
+            }
+        }
+        if stale_family_ids.is_empty() {
+            return Ok(families);
+        }
+
+        // The families of the account may have been revoked meanwhile, in which case they stay deleted.
+        let update_item_input = UpdateItemInput::builder()
+            .table_name(self.refresh_tokens_table_name.as_str())
+            .key(self.key("AccountFamilies", account_id))
+            .update_expression("DELETE Families :stale")
+            .condition_expression("attribute_exists(Families)")
+            .expression_attribute_values(hash_map! {
+                ":stale".to_owned() => AttributeValue::Ss(stale_family_ids),
+            })
+            .build();
+        match self.ddb.update_item(update_item_input).await {
+            Ok(_)
+            | Err(SdkError::ServiceError {
+                err:
+                    UpdateItemError {
+                        kind: UpdateItemErrorKind::ConditionalCheckFailedException(_),
+                        ..
+                    },
+                ..
+            }) => Ok(families),
+            Err(e) => Err(RefreshTokenStoreError::Other(e.into())),
+        }
+    }
+}
+
+#[async_trait]
+impl<T: ThreadSafeDdbClient> RefreshTokenStore for DdbRefreshTokenStore<T> {
+    async fn put_token(&self, token: &RefreshToken) -> Result<(), RefreshTokenStoreError> {
+        let item = serde_ddb::to_hashmap(token).map_err(RefreshTokenStoreError::Serde)?;
+        self.put(self.key("Token", &token.token_id), item).await
+    }
+
+    async fn take_token(&self, token_id: &Uuid) -> Result<Option<RefreshToken>, RefreshTokenStoreError> {
+        // Deleting is atomic, so only one of the concurrent calls gets the old attributes back.
+        let item = self.delete(self.key("Token", token_id)).await?;
+        let Some(item) = item else { return Ok(None) };
+
+        let token: RefreshToken = serde_ddb::from_hashmap(item).map_err(RefreshTokenStoreError::Serde)?;
+        Ok(Some(token).filter(|token| token.expires_at > Utc::now().timestamp()))
+    }
+
+    async fn put_family(&self, family: &RefreshTokenFamily) -> Result<(), RefreshTokenStoreError> {
+        let item = serde_ddb::to_hashmap(family).map_err(RefreshTokenStoreError::Serde)?;
+        self.put(self.key("Family", &family.family_id), item).await?;
+        self.add_account_family(family).await
+    }
+
+    async fn replace_family(&self, family: &RefreshTokenFamily) -> Result<bool, RefreshTokenStoreError> {
+        let mut item = serde_ddb::to_hashmap(family).map_err(RefreshTokenStoreError::Serde)?;
+        item.extend(self.key("Family", &family.family_id));
+        let put_item_input = PutItemInput::builder()
+            .table_name(self.refresh_tokens_table_name.as_str())
+            .item(item)
+            .condition_expression("attribute_exists(FamilyId)")
+            .build();
+
+        match self.ddb.put_item(put_item_input).await {
+            Ok(_) => {
+                self.add_account_family(family).await?;
+                Ok(true)
+            }
+            Err(SdkError::ServiceError {
+                err:
+                    PutItemError {
+                        kind: PutItemErrorKind::ConditionalCheckFailedException(_),
+                        ..
+                    },
+                ..
+            }) => Ok(false),
+            Err(e) => Err(RefreshTokenStoreError::Other(e.into())),
+        }
+    }
+
+    async fn get_family(&self, family_id: &Uuid) -> Result<Option<RefreshTokenFamily>, RefreshTokenStoreError> {
+        let get_item_input = GetItemInput::builder()
+            .table_name(self.refresh_tokens_table_name.as_str())
+            .key(self.key("Family", family_id))
+            .consistent_read(true)
+            .build();
+        let output = self
+            .ddb
+            .get_item(get_item_input)
+            .await
+            .map_err(|e| RefreshTokenStoreError::Other(e.into()))?;
+        let Some(item) = output.item else { return Ok(None) };
+
+        let family: RefreshTokenFamily = serde_ddb::from_hashmap(item).map_err(RefreshTokenStoreError::Serde)?;
+        Ok(Some(family).filter(|family| family.expires_at > Utc::now().timestamp()))
+    }
+
+    async fn list_account_families(
+        &self,
+        account_id: &Uuid,
+    ) -> Result<Vec<RefreshTokenFamily>, RefreshTokenStoreError> {
+        let get_item_input = GetItemInput::builder()
+            .table_name(self.refresh_tokens_table_name.as_str())
+            .key(self.key("AccountFamilies", account_id))
+            .consistent_read(true)
+            .build();
+        let output = self
+            .ddb
+            .get_item(get_item_input)
+            .await
+            .map_err(|e| RefreshTokenStoreError::Other(e.into()))?;
+        let Some(item) = output.item else { return Ok(vec![]) };
+
+        let family_ids = family_ids(&item)?;
+        self.prune_account_families(account_id, family_ids).await
+    }
+
+    async fn delete_family(&self, family_id: &Uuid) -> Result<(), RefreshTokenStoreError> {
+        self.delete(self.key("Family", family_id)).await?;
+        Ok(())
+    }
+
+    async fn delete_account_families(&self, account_id: &Uuid) -> Result<usize, RefreshTokenStoreError> {
+        let item = self.delete(self.key("AccountFamilies", account_id)).await?;
+        let Some(item) = item else { return Ok(0) };
+        let family_ids = family_ids(&item)?;
+
+        for family_id in &family_ids {
+            self.delete_family(family_id).await?;
+        }
+
+        Ok(family_ids.len())
+    }
+}
+
+/// Reads the family IDs of an item listing the families of an account. DynamoDB drops sets once they
+/// are empty, so the item may have no families at all.
+fn family_ids(item: &HashMap<String, AttributeValue>) -> Result<Vec<Uuid>, RefreshTokenStoreError> {
+    match item.get("Families") {
+        None => Ok(vec![]),
+        Some(AttributeValue::Ss(family_ids)) => family_ids
+            .iter()
+            .map(|family_id| Uuid::parse_str(family_id))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| RefreshTokenStoreError::Other(e.into())),
+        _ => Err(RefreshTokenStoreError::Other("Malformed item: invalid families".into())),
+    }
+}
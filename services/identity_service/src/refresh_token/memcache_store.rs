@@ -0,0 +1,232 @@
+use std::collections::HashSet;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use memcache::{Client, MemcacheError};
+use uuid::Uuid;
+
+use super::{RefreshToken, RefreshTokenFamily, RefreshTokenStore, RefreshTokenStoreError};
+use crate::MemcacheConnPool;
+
+const UUID_LEN: usize = 16;
+const MAX_UPDATE_ATTEMPTS: usize = 5;
+
+/// Keeps refresh tokens in memcache, which expires them on its own. The families of an account are
+/// listed under a key of their own, so that they can all be revoked. The families which expired or
+/// were revoked are dropped from the list whenever it is listed or added to.
+pub struct MemcacheRefreshTokenStore {
+    pool: MemcacheConnPool,
+}
+
+impl MemcacheRefreshTokenStore {
+    pub fn new(pool: MemcacheConnPool) -> Self {
+        Self { pool }
+    }
+
+    fn client(&self) -> Result<Client, RefreshTokenStoreError> {
+        Client::with_pool(self.pool.clone()).map_err(|e| RefreshTokenStoreError::Other(e.into()))
+    }
+}
+
+#[async_trait]
+impl RefreshTokenStore for MemcacheRefreshTokenStore {
+    async fn put_token(&self, token: &RefreshToken) -> Result<(), RefreshTokenStoreError> {
+        let value = serde_json::to_vec(token).map_err(|e| RefreshTokenStoreError::Other(e.into()))?;
+        self.client()?
+            .set(
+                token_key(&token.token_id).as_str(),
+                value.as_slice(),
+                ttl(token.expires_at),
+            )
+            .map_err(|e| RefreshTokenStoreError::Other(e.into()))
+    }
+
+    async fn take_token(&self, token_id: &Uuid) -> Result<Option<RefreshToken>, RefreshTokenStoreError> {
+        let client = self.client()?;
+        let key = token_key(token_id);
+        let value = client
+            .get::<Vec<u8>>(key.as_str())
+            .map_err(|e| RefreshTokenStoreError::Other(e.into()))?;
+        let Some(value) = value else { return Ok(None) };
+
+        // Only one of the concurrent calls which got the token manages to delete it.
+        let deleted = client
+            .delete(key.as_str())
+            .map_err(|e| RefreshTokenStoreError::Other(e.into()))?;
+        if !deleted {
+            return Ok(None);
+        }
+
+        let token: RefreshToken =
+            serde_json::from_slice(&value).map_err(|e| RefreshTokenStoreError::Other(e.into()))?;
+        Ok(Some(token).filter(|token| token.expires_at > Utc::now().timestamp()))
+    }
+
+    async fn put_family(&self, family: &RefreshTokenFamily) -> Result<(), RefreshTokenStoreError> {
+        let client = self.client()?;
+        let value = serde_json::to_vec(family).map_err(|e| RefreshTokenStoreError::Other(e.into()))?;
+        client
+            .set(
+                family_key(&family.family_id).as_str(),
+                value.as_slice(),
+                ttl(family.expires_at),
+            )
+            .map_err(|e| RefreshTokenStoreError::Other(e.into()))?;
+
+        update_account_families(&client, &family.account_id, Some(family))?;
+        Ok(())
+    }
+
+    async fn replace_family(&self, family: &RefreshTokenFamily) -> Result<bool, RefreshTokenStoreError> {
+        let client = self.client()?;
+        let value = serde_json::to_vec(family).map_err(|e| RefreshTokenStoreError::Other(e.into()))?;
+
+        // Replacing fails when the family does not exist, i.e. it was revoked or expired.
+        match client.replace(
+            family_key(&family.family_id).as_str(),
+            value.as_slice(),
+            ttl(family.expires_at),
+        ) {
+            Ok(()) => {
+                update_account_families(&client, &family.account_id, Some(family))?;
+                Ok(true)
+            }
+            Err(MemcacheError::CommandError(_)) => Ok(false),
+            Err(e) => Err(RefreshTokenStoreError::Other(e.into())),
+        }
+    }
+
+    async fn get_family(&self, family_id: &Uuid) -> Result<Option<RefreshTokenFamily>, RefreshTokenStoreError> {
+        get_family(&self.client()?, family_id)
+    }
+
+    async fn list_account_families(
+        &self,
+        account_id: &Uuid,
+    ) -> Result<Vec<RefreshTokenFamily>, RefreshTokenStoreError> {
+        update_account_families(&self.client()?, account_id, None)
+    }
+
+    async fn delete_family(&self, family_id: &Uuid) -> Result<(), RefreshTokenStoreError> {
+        self.client()?
+            .delete(family_key(family_id).as_str())
+            .map(|_| ())
+            .map_err(|e| RefreshTokenStoreError::Other(e.into()))
+    }
+
+    async fn delete_account_families(&self, account_id: &Uuid) -> Result<usize, RefreshTokenStoreError> {
+        let client = self.client()?;
+        let family_ids = account_family_ids(&client, account_id)?;
+        for family_id in &family_ids {
+            client
+                .delete(family_key(family_id).as_str())
+                .map_err(|e| RefreshTokenStoreError::Other(e.into()))?;
+        }
+        client
+            .delete(account_families_key(account_id).as_str())
+            .map_err(|e| RefreshTokenStoreError::Other(e.into()))?;
+
+        Ok(family_ids.len())
+    }
+}
+
+/// Gets a family, unless it does not exist, expired or was revoked.
+fn get_family(client: &Client, family_id: &Uuid) -> Result<Option<RefreshTokenFamily>, RefreshTokenStoreError> {
+    let value = client
+        .get::<Vec<u8>>(family_key(family_id).as_str())
+        .map_err(|e| RefreshTokenStoreError::Other(e.into()))?;
+    let Some(value) = value else { return Ok(None) };
+
+    let family: RefreshTokenFamily =
+        serde_json::from_slice(&value).map_err(|e| RefreshTokenStoreError::Other(e.into()))?;
+    Ok(Some(family).filter(|family| family.expires_at > Utc::now().timestamp()))
+}
+
+/// Gets the IDs of the families of an account, including the ones which expired or were revoked.
+fn account_family_ids(client: &Client, account_id: &Uuid) -> Result<HashSet<Uuid>, RefreshTokenStoreError> {
+    let families = client
+        .get::<Vec<u8>>(account_families_key(account_id).as_str())
+        .map_err(|e| RefreshTokenStoreError::Other(e.into()))?
+        .unwrap_or_default();
+
+    Ok(parse_family_ids(&families))
+}
+
+/// Keeps track of the families of an account, adding the given family, if any, and dropping the ones
+/// which expired or were revoked. The list lives as long as the latest family in it. Returns the
+/// families left in the list.
+fn update_account_families(
+    client: &Client,
+    account_id: &Uuid,
+    family: Option<&RefreshTokenFamily>,
+) -> Result<Vec<RefreshTokenFamily>, RefreshTokenStoreError> {
+    let families_key = account_families_key(account_id);
+
+    // Concurrent updates are detected with check-and-set, after which the list is read again.
+    for _ in 0..MAX_UPDATE_ATTEMPTS {
+        let mut existing = client
+            .gets::<(Vec<u8>, u32, Option<u64>)>(&[families_key.as_str()])
+            .map_err(|e| RefreshTokenStoreError::Other(e.into()))?;
+        let existing = existing.remove(&families_key);
+        let family_ids = existing
+            .as_ref()
+            .map(|(value, ..)| parse_family_ids(value))
+            .unwrap_or_default();
+
+        let mut families = vec![];
+        for family_id in family_ids.iter().filter(|&id| Some(id) != family.map(|f| &f.family_id)) {
+            families.extend(get_family(client, family_id)?);
+        }
+        families.extend(family.cloned());
+
+        // Nothing to write when no family was added and none of them went away.
+        if family.is_none() && families.len() == family_ids.len() {
+            return Ok(families);
+        }
+
+        let value: Vec<u8> = families.iter().flat_map(|f| *f.family_id.as_bytes()).collect();
+        let expires_at = families.iter().map(|f| f.expires_at).max().unwrap_or_default();
+        let written = match existing {
+            Some((_, _, Some(cas_id))) => client
+                .cas(families_key.as_str(), value.as_slice(), ttl(expires_at), cas_id)
+                .map_err(|e| RefreshTokenStoreError::Other(e.into()))?,
+            Some((_, _, None)) => return Err(RefreshTokenStoreError::Other("Malformed reply: missing CAS".into())),
+            // Adding fails when the list was created meanwhile.
+            None => client
+                .add(families_key.as_str(), value.as_slice(), ttl(expires_at))
+                .is_ok(),
+        };
+        if written {
+            return Ok(families);
+        }
+    }
+
+    Err(RefreshTokenStoreError::Other(
+        "Too many concurrent updates of the families of the account.".into(),
+    ))
+}
+
+fn parse_family_ids(value: &[u8]) -> HashSet<Uuid> {
+    value
+        .chunks_exact(UUID_LEN)
+        .filter_map(|family_id| Uuid::from_slice(family_id).ok())
+        .collect()
+}
+
+/// Seconds left until the given timestamp. Memcache takes zero as never expiring, so this is at least
+/// one.
+fn ttl(expires_at: i64) -> u32 {
+    (expires_at - Utc::now().timestamp()).max(1) as u32
+}
+
+fn token_key(token_id: &Uuid) -> String {
+    format!("refresh_token:{}", token_id.to_simple())
+}
+
+fn family_key(family_id: &Uuid) -> String {
+    format!("refresh_token_family:{}", family_id.to_simple())
+}
+
+fn account_families_key(account_id: &Uuid) -> String {
+    format!("refresh_token_families:{}", account_id.to_simple())
+}
@@ -0,0 +1,242 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use uuid::Uuid;
+
+use super::{RefreshToken, RefreshTokenFamily, RefreshTokenStore, RefreshTokenStoreError};
+
+/// Keeps refresh tokens in the memory of the service, so they are lost on restart and not shared
+/// between replicas. Meant for tests and local development.
+///
+/// # Notes
+///
+/// The families of an account expire like in the other stores, with the latest family added to or
+/// rotated in them, and the ones which expired or were revoked are dropped whenever they are listed or
+/// added to.
+#[derive(Debug, Default)]
+pub struct InMemoryRefreshTokenStore {
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    tokens: HashMap<Uuid, RefreshToken>,
+    families: HashMap<Uuid, RefreshTokenFamily>,
+    account_families: HashMap<Uuid, AccountFamilies>,
+}
+
+#[derive(Debug, Default)]
+struct AccountFamilies {
+    family_ids: HashSet<Uuid>,
+    expires_at: i64,
+}
+
+impl State {
+    /// Adds a family to the families of its account, extending them to the expiry of the family.
+    fn add_account_family(&mut self, family: &RefreshTokenFamily) {
+        let now = Utc::now().timestamp();
+        let account_families = self.account_families.entry(family.account_id).or_default();
+        if account_families.expires_at <= now {
+            *account_families = AccountFamilies::default();
+        }
+        account_families.family_ids.insert(family.family_id);
+        account_families.expires_at = account_families.expires_at.max(family.expires_at);
+        self.prune_account_families(&family.account_id);
+    }
+
+    /// Drops the families of an account which expired or were revoked, and forgets the expired ones.
+    fn prune_account_families(&mut self, account_id: &Uuid) {
+        let now = Utc::now().timestamp();
+        let Some(account_families) = self.account_families.get_mut(account_id) else { return };
+        let families = &mut self.families;
+        account_families
+            .family_ids
+            .retain(|family_id| match families.get(family_id) {
+                Some(family) if family.expires_at > now => true,
+                Some(_) => {
+                    families.remove(family_id);
+                    false
+                }
+                None => false,
+            });
+    }
+
+    /// Removes the families of an account, returning their IDs unless they expired.
+    fn take_account_families(&mut self, account_id: &Uuid) -> HashSet<Uuid> {
+        let now = Utc::now().timestamp();
+        self.account_families
+            .remove(account_id)
+            .filter(|account_families| account_families.expires_at > now)
+            .map(|account_families| account_families.family_ids)
+            .unwrap_or_default()
+    }
+}
+
+
+#[async_trait]
+impl RefreshTokenStore for InMemoryRefreshTokenStore {
+    async fn put_token(&self, token: &RefreshToken) -> Result<(), RefreshTokenStoreError> {
+        let mut state = self.state.lock().unwrap();
+        state.tokens.insert(token.token_id, token.clone());
+        Ok(())
+    }
+
+    async fn take_token(&self, token_id: &Uuid) -> Result<Option<RefreshToken>, RefreshTokenStoreError> {
+        let mut state = self.state.lock().unwrap();
+        let now = Utc::now().timestamp();
+        Ok(state.tokens.remove(token_id).filter(|token| token.expires_at > now))
+    }
+
+    async fn put_family(&self, family: &RefreshTokenFamily) -> Result<(), RefreshTokenStoreError> {
+        let mut state = self.state.lock().unwrap();
+        state.families.insert(family.family_id, family.clone());
+        state.add_account_family(family);
+        Ok(())
+    }
+
+    async fn replace_family(&self, family: &RefreshTokenFamily) -> Result<bool, RefreshTokenStoreError> {
+        let mut state = self.state.lock().unwrap();
+        if !state.families.contains_key(&family.family_id) {
+            return Ok(false);
+        }
+
+        state.families.insert(family.family_id, family.clone());
+        state.add_account_family(family);
+        Ok(true)
+    }
+
+    async fn get_family(&self, family_id: &Uuid) -> Result<Option<RefreshTokenFamily>, RefreshTokenStoreError> {
+        let state = self.state.lock().unwrap();
+        let now = Utc::now().timestamp();
+        Ok(state
+            .families
+            .get(family_id)
+            .filter(|family| family.expires_at > now)
+            .cloned())
+    }
+
+    async fn list_account_families(
+        &self,
+        account_id: &Uuid,
+    ) -> Result<Vec<RefreshTokenFamily>, RefreshTokenStoreError> {
+        let mut state = self.state.lock().unwrap();
+        state.prune_account_families(account_id);
+        let now = Utc::now().timestamp();
+        Ok(state
+            .account_families
+            .get(account_id)
+            .filter(|account_families| account_families.expires_at > now)
+            .into_iter()
+            .flat_map(|account_families| &account_families.family_ids)
+            .filter_map(|family_id| state.families.get(family_id))
+            .filter(|family| family.expires_at > now)
+            .cloned()
+            .collect())
+    }
+
+    async fn delete_family(&self, family_id: &Uuid) -> Result<(), RefreshTokenStoreError> {
+        let mut state = self.state.lock().unwrap();
+        state.families.remove(family_id);
+        Ok(())
+    }
+
+    async fn delete_account_families(&self, account_id: &Uuid) -> Result<usize, RefreshTokenStoreError> {
+        let mut state = self.state.lock().unwrap();
+        let family_ids = state.take_account_families(account_id);
+        for family_id in &family_ids {
+            state.families.remove(family_id);
+        }
+        Ok(family_ids.len())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn expired_tokens_are_not_taken() {
+        let store = InMemoryRefreshTokenStore::default();
+        let token = RefreshToken {
+            token_id: Uuid::new_v4(),
+            account_id: Uuid::new_v4(),
+            family_id: Uuid::new_v4(),
+            rotated: false,
+            expires_at: Utc::now().timestamp() - 1,
+        };
+        store.put_token(&token).await.unwrap();
+
+        assert_eq!(store.take_token(&token.token_id).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn tokens_are_taken_once() {
+        let store = InMemoryRefreshTokenStore::default();
+        let token = RefreshToken {
+            token_id: Uuid::new_v4(),
+            account_id: Uuid::new_v4(),
+            family_id: Uuid::new_v4(),
+            rotated: false,
+            expires_at: Utc::now().timestamp() + 60,
+        };
+        store.put_token(&token).await.unwrap();
+
+        assert_eq!(store.take_token(&token.token_id).await.unwrap(), Some(token.clone()));
+        assert_eq!(store.take_token(&token.token_id).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn revoked_families_are_not_replaced() {
+        let store = InMemoryRefreshTokenStore::default();
+        let now = Utc::now().timestamp();
+        let mut family = RefreshTokenFamily {
+            family_id: Uuid::new_v4(),
+            account_id: Uuid::new_v4(),
+            created_at: now,
+            last_refreshed_at: now,
+            expires_at: now + 60,
+            user_agent: None,
+            ip_address: None,
+        };
+        store.put_family(&family).await.unwrap();
+        family.expires_at += 60;
+        assert!(store.replace_family(&family).await.unwrap());
+
+        store.delete_family(&family.family_id).await.unwrap();
+
+        assert!(!store.replace_family(&family).await.unwrap());
+        assert_eq!(store.get_family(&family.family_id).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn revoked_families_are_dropped_from_the_account() {
+        let store = InMemoryRefreshTokenStore::default();
+        let account_id = Uuid::new_v4();
+        let now = Utc::now().timestamp();
+        let family = |expires_at| RefreshTokenFamily {
+            family_id: Uuid::new_v4(),
+            account_id,
+            created_at: now,
+            last_refreshed_at: now,
+            expires_at,
+            user_agent: None,
+            ip_address: None,
+        };
+        let (revoked, expired, kept) = (family(now + 60), family(now - 1), family(now + 60));
+        store.put_family(&revoked).await.unwrap();
+        store.put_family(&expired).await.unwrap();
+        store.delete_family(&revoked.family_id).await.unwrap();
+
+        store.put_family(&kept).await.unwrap();
+
+        let state = store.state.lock().unwrap();
+        assert_eq!(
+            state.account_families[&account_id].family_ids,
+            HashSet::from([kept.family_id])
+        );
+        assert!(!state.families.contains_key(&expired.family_id));
+    }
+}
@@ -0,0 +1,8 @@
+pub mod ddb_store;
+pub mod memcache_store;
+pub mod memory_store;
+pub mod store;
+pub mod types;
+
+pub use store::{RefreshTokenStore, RefreshTokenStoreError};
+pub use types::{RefreshToken, RefreshTokenFamily};
@@ -0,0 +1,57 @@
+use std::error::Error;
+
+use async_trait::async_trait;
+use thiserror::Error;
+use uuid::Uuid;
+
+use super::{RefreshToken, RefreshTokenFamily};
+
+
+#[derive(Debug, Error)]
+pub enum RefreshTokenStoreError {
+    #[error(transparent)]
+    Serde(serde_ddb::Error),
+
+    #[error(transparent)]
+    Other(#[from] Box<dyn Error>),
+}
+
+
+/// Where refresh tokens and their families are kept until they expire. Stores are shared by all the
+/// requests of the service, so the backend can be chosen at startup.
+#[async_trait]
+pub trait RefreshTokenStore: Send + Sync {
+    /// Stores a refresh token, replacing the token with the same ID, if any.
+    async fn put_token(&self, token: &RefreshToken) -> Result<(), RefreshTokenStoreError>;
+
+    /// Removes a refresh token and returns it, unless it does not exist or expired.
+    ///
+    /// # Notes
+    ///
+    /// Getting and deleting the token is atomic: of concurrent calls taking the same token, at most
+    /// one gets it.
+    async fn take_token(&self, token_id: &Uuid) -> Result<Option<RefreshToken>, RefreshTokenStoreError>;
+
+    /// Stores a refresh token family and adds it to the families of its account, replacing the family
+    /// with the same ID, if any.
+    async fn put_family(&self, family: &RefreshTokenFamily) -> Result<(), RefreshTokenStoreError>;
+
+    /// Replaces a refresh token family, as long as it was not revoked in the meantime, and extends the
+    /// families of its account to its expiry. Returns whether the family was replaced.
+    async fn replace_family(&self, family: &RefreshTokenFamily) -> Result<bool, RefreshTokenStoreError>;
+
+    /// Gets a refresh token family, unless it does not exist, expired or was revoked.
+    async fn get_family(&self, family_id: &Uuid) -> Result<Option<RefreshTokenFamily>, RefreshTokenStoreError>;
+
+    /// Lists the refresh token families of an account which neither expired nor were revoked, and
+    /// drops the other ones from the families of the account.
+    async fn list_account_families(&self, account_id: &Uuid)
+        -> Result<Vec<RefreshTokenFamily>, RefreshTokenStoreError>;
+
+    /// Revokes a refresh token family. Revoking a family which does not exist is not an error.
+    async fn delete_family(&self, family_id: &Uuid) -> Result<(), RefreshTokenStoreError>;
+
+    /// Revokes every refresh token family of an account. Returns the number of families revoked, which
+    /// may include ones which expired since the families of the account were last listed or added to.
+    async fn delete_account_families(&self, account_id: &Uuid) -> Result<usize, RefreshTokenStoreError>;
+}
@@ -0,0 +1,65 @@
+use identity_service::pb::Session as SessionModel;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A refresh token, which is exchanged for an access token and a new refresh token of the same
+/// family.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct RefreshToken {
+    pub token_id: Uuid,
+
+    pub account_id: Uuid,
+
+    pub family_id: Uuid,
+
+    /// Set once the token was exchanged. The token is kept afterwards, so that presenting it again is
+    /// told apart from presenting an unknown token.
+    #[serde(default)]
+    pub rotated: bool,
+
+    /// Unix timestamp, in seconds, after which the token is expired.
+    pub expires_at: i64,
+}
+
+/// The refresh tokens issued from a single authentication, which are revoked together. Families are
+/// the sessions of an account, and their ID is the session ID in the claims of access tokens.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct RefreshTokenFamily {
+    pub family_id: Uuid,
+
+    pub account_id: Uuid,
+
+    /// Unix timestamp, in seconds, of the authentication which started the family.
+    pub created_at: i64,
+
+    /// Unix timestamp, in seconds, of the latest token issued in the family.
+    pub last_refreshed_at: i64,
+
+    /// Unix timestamp, in seconds, after which the family is expired. Every token issued in the family
+    /// moves it forward.
+    pub expires_at: i64,
+
+    /// User agent of the client which authenticated, as reported by the frontend.
+    #[serde(default)]
+    pub user_agent: Option<String>,
+
+    /// IP address of the client which authenticated, as reported by the frontend.
+    #[serde(default)]
+    pub ip_address: Option<String>,
+}
+
+
+impl From<RefreshTokenFamily> for SessionModel {
+    fn from(val: RefreshTokenFamily) -> SessionModel {
+        SessionModel {
+            session_id: val.family_id.to_hyphenated().to_string(),
+            created_at: val.created_at,
+            last_refreshed_at: val.last_refreshed_at,
+            expires_at: val.expires_at,
+            user_agent: val.user_agent.unwrap_or_default(),
+            ip_address: val.ip_address.unwrap_or_default(),
+        }
+    }
+}
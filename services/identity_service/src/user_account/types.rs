@@ -3,7 +3,7 @@ use std::fmt::{Display, Formatter};
 
 use identity_service::pb::{AccountState as AccountStateModel, PermissionsDocument as PermissionsDocumentModel};
 use serde::{Deserialize, Serialize};
-use service_core::resource_access::AccessKind;
+use service_core::resource_access::{AccessKind, Effect};
 use typed_builder::TypedBuilder;
 use uuid::Uuid;
 
@@ -38,6 +38,23 @@ pub struct UserAccount {
     #[serde(default)]
     #[builder(default)]
     pub permissions_document: PermissionsDocument,
+
+    /// IDs of the managed policies attached to the account. Stored as a string set, which cannot be
+    /// empty, hence never written when there are none.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[builder(default)]
+    pub attached_policies: Vec<Uuid>,
+
+    /// IDs of the groups the account is a member of. Stored as a string set, like the attached
+    /// policies.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[builder(default)]
+    pub group_ids: Vec<Uuid>,
+
+    /// Increased on every change to the permissions document, attached policies or groups.
+    #[serde(default)]
+    #[builder(default)]
+    pub permissions_version: u64,
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
@@ -57,8 +74,22 @@ pub struct PermissionsDocument {
 #[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
 #[serde(rename_all = "PascalCase")]
 pub struct RenderedPolicyStatement {
+    #[serde(default)]
+    pub effect: Effect,
     pub access_kind: AccessKind,
     pub paths: Vec<String>,
+
+    /// Unix timestamp, in seconds, before which the statement does not apply.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_before: Option<i64>,
+
+    /// Unix timestamp, in seconds, after which the statement no longer applies.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_after: Option<i64>,
+
+    /// Whether the statement applies to active accounts only.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub active_accounts_only: bool,
 }
 
 // FIXME Generate this automatically from the UserAccount structure.
@@ -72,6 +103,9 @@ pub enum AccountAttr {
     Discoverable,
     AccountState,
     PermissionsDocument,
+    AttachedPolicies,
+    GroupIds,
+    PermissionsVersion,
 }
 
 
@@ -110,9 +144,19 @@ impl TryFrom<i32> for AccountState {
     }
 }
 
+impl RenderedPolicyStatement {
+    /// Checks whether the conditions of the statement hold at the given Unix timestamp, in seconds, for
+    /// an account in the given state.
+    pub fn applies(&self, now: i64, account_state: &AccountState) -> bool {
+        self.not_before.map_or(true, |not_before| now >= not_before)
+            && self.not_after.map_or(true, |not_after| now <= not_after)
+            && (!self.active_accounts_only || *account_state == AccountState::Active)
+    }
+}
+
 impl From<PermissionsDocument> for PermissionsDocumentModel {
     fn from(val: PermissionsDocument) -> PermissionsDocumentModel {
-        use identity_service::pb::policy_statement::AccessKind as AccessKindModel;
+        use identity_service::pb::policy_statement::{AccessKind as AccessKindModel, Effect as EffectModel};
         use identity_service::pb::PolicyStatement;
 
         PermissionsDocumentModel {
@@ -122,9 +166,17 @@ impl From<PermissionsDocument> for PermissionsDocumentModel {
                 .map(|s| PolicyStatement {
                     access_kind: match s.access_kind {
                         AccessKind::Mutation => AccessKindModel::Mutation,
+                        AccessKind::Subscription => AccessKindModel::Subscription,
                         AccessKind::Query => AccessKindModel::Query,
                     } as i32,
                     paths: s.paths,
+                    effect: match s.effect {
+                        Effect::Allow => EffectModel::Allow,
+                        Effect::Deny => EffectModel::Deny,
+                    } as i32,
+                    not_before: s.not_before,
+                    not_after: s.not_after,
+                    active_accounts_only: s.active_accounts_only,
                 })
                 .collect(),
         }
@@ -133,19 +185,29 @@ impl From<PermissionsDocument> for PermissionsDocumentModel {
 
 impl From<PermissionsDocumentModel> for PermissionsDocument {
     fn from(val: PermissionsDocumentModel) -> PermissionsDocument {
-        use identity_service::pb::policy_statement::AccessKind as AccessKindModel;
+        use identity_service::pb::policy_statement::{AccessKind as AccessKindModel, Effect as EffectModel};
 
         PermissionsDocument {
             statements: val
                 .statements
                 .into_iter()
                 .map(|s| RenderedPolicyStatement {
+                    effect: if s.effect == EffectModel::Deny as i32 {
+                        Effect::Deny
+                    } else {
+                        Effect::Allow
+                    },
                     access_kind: if s.access_kind == AccessKindModel::Mutation as i32 {
                         AccessKind::Mutation
+                    } else if s.access_kind == AccessKindModel::Subscription as i32 {
+                        AccessKind::Subscription
                     } else {
                         AccessKind::Query
                     },
                     paths: s.paths,
+                    not_before: s.not_before,
+                    not_after: s.not_after,
+                    active_accounts_only: s.active_accounts_only,
                 })
                 .collect(),
         }
@@ -160,7 +222,7 @@ impl Display for AccountAttr {
 
 #[cfg(test)]
 mod tests {
-
+    use super::*;
 
     #[test]
     fn deserializes_without_required_fields() {
@@ -249,4 +311,57 @@ mod tests {
         let serialized_password_attr = serialized.get(&"Password".to_string()).unwrap();
         assert!(serialized_password_attr.is_s());
     }
+
+    #[test]
+    fn policy_statement_effect_defaults_to_allow() {
+        use serde_json::json;
+
+        use super::*;
+
+        let input = json!({
+            "AccessKind": "Query",
+            "Paths": ["accounts::*"]
+        })
+        .to_string();
+
+        let statement: RenderedPolicyStatement = serde_json::from_str(input.as_str()).unwrap();
+        assert_eq!(statement.effect, Effect::Allow);
+    }
+
+    #[test]
+    fn policy_statement_conditions() {
+        use serde_json::json;
+
+        let input = json!({
+            "AccessKind": "Query",
+            "Paths": ["accounts::*"],
+            "NotBefore": 100,
+            "NotAfter": 200,
+            "ActiveAccountsOnly": true
+        })
+        .to_string();
+
+        let statement: RenderedPolicyStatement = serde_json::from_str(input.as_str()).unwrap();
+        assert!(!statement.applies(99, &AccountState::Active));
+        assert!(statement.applies(100, &AccountState::Active));
+        assert!(statement.applies(200, &AccountState::Active));
+        assert!(!statement.applies(201, &AccountState::Active));
+        assert!(!statement.applies(150, &AccountState::Deactivated));
+
+        let unconditional = RenderedPolicyStatement {
+            not_before: None,
+            not_after: None,
+            active_accounts_only: false,
+            ..statement
+        };
+        assert!(unconditional.applies(0, &AccountState::PendingActivation));
+        assert_eq!(
+            serde_json::to_value(&unconditional).unwrap(),
+            json!({
+                "Effect": "Allow",
+                "AccessKind": "Query",
+                "Paths": ["accounts::*"]
+            })
+        );
+    }
 }
@@ -0,0 +1,87 @@
+use futures_util::stream::{self, Stream};
+use identity_service::pb::AccountEvent;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::broadcast::{self, Sender};
+use uuid::Uuid;
+
+/// In-process broadcast of the changes made to accounts, which __WatchAccountEvents__ streams to its
+/// callers.
+///
+/// # Notes
+///
+/// Only changes made through this instance of the service are broadcast, since there is no transport
+/// between instances. The identity service therefore has to run as a single replica, or the
+/// subscribers of one replica miss the changes made through the others, which is why its deployment
+/// is recreated rather than rolled out. Subscribers lagging behind by more than the capacity of the
+/// broadcast miss the oldest events.
+#[derive(Debug)]
+pub struct AccountEvents {
+    sender: Sender<AccountEvent>,
+}
+
+
+impl AccountEvents {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self { sender }
+    }
+
+    /// Broadcasts an event to the current subscribers. Events nobody subscribed to are dropped.
+    pub fn publish(&self, event: AccountEvent) {
+        // Sending only fails when there are no subscribers.
+        let _ = self.sender.send(event);
+    }
+
+    /// Subscribes to the events of the given account, published from now on.
+    pub fn subscribe(&self, account_id: &Uuid) -> impl Stream<Item = AccountEvent> + Send + 'static {
+        let account_id = account_id.to_hyphenated().to_string();
+        let receiver = self.sender.subscribe();
+
+        stream::unfold(receiver, move |mut receiver| {
+            let account_id = account_id.clone();
+            async move {
+                loop {
+                    match receiver.recv().await {
+                        Ok(event) if event.account_id == account_id => return Some((event, receiver)),
+                        Ok(_) => {}
+                        Err(RecvError::Lagged(skipped)) => {
+                            log::warn!("Subscriber of account {} missed {} events.", account_id, skipped);
+                        }
+                        Err(RecvError::Closed) => return None,
+                    }
+                }
+            }
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use futures_util::StreamExt;
+    use identity_service::pb::account_event::Event;
+
+    use super::*;
+
+    fn event(account_id: &Uuid, account_state: i32) -> AccountEvent {
+        AccountEvent {
+            account_id: account_id.to_hyphenated().to_string(),
+            event: Some(Event::AccountStateChanged(account_state)),
+        }
+    }
+
+    #[tokio::test]
+    async fn streams_events_of_the_account() {
+        let account_events = AccountEvents::new(16);
+        let account_id = Uuid::new_v4();
+        account_events.publish(event(&account_id, 0));
+
+        let subscription = account_events.subscribe(&account_id);
+        account_events.publish(event(&Uuid::new_v4(), 1));
+        account_events.publish(event(&account_id, 2));
+        drop(account_events);
+
+        let received: Vec<AccountEvent> = subscription.collect().await;
+        assert_eq!(received, vec![event(&account_id, 2)]);
+    }
+}
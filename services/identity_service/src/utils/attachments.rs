@@ -0,0 +1,94 @@
+use std::error::Error;
+
+use aws_sdk_dynamodb::model::{AttributeValue, ReturnValue};
+use common_macros::hash_map;
+use service_core::ddb::query::Query;
+use service_core::ddb::update_item::{UpdateItem, UpdateItemInput};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::utils::account::{account_key_from_id, AccountKeyFromIdError};
+use crate::utils::permissions::updated_permissions_version;
+use crate::utils::permissions_cache::PermissionsCache;
+use crate::Context;
+
+#[derive(Debug, Error)]
+pub enum UpdateAttachmentError {
+    #[error("Account not found.")]
+    AccountNotFound,
+
+    #[error("Underlying datastore error: {0}")]
+    Datastore(Box<dyn Error>),
+}
+
+
+/// Something whose permissions documents apply to the accounts it is attached to.
+pub enum Attachment {
+    /// A managed policy, attached by ID.
+    Policy(Uuid),
+
+    /// An account group, which the account is a member of.
+    Group(Uuid),
+}
+
+impl Attachment {
+    /// The set attribute of the account which holds the IDs of its attachments of this kind.
+    fn attribute_name(&self) -> &'static str {
+        match self {
+            Self::Policy(_) => "AttachedPolicies",
+            Self::Group(_) => "GroupIds",
+        }
+    }
+
+    fn id(&self) -> &Uuid {
+        match self {
+            Self::Policy(id) | Self::Group(id) => id,
+        }
+    }
+}
+
+
+/// Attaches to or detaches from an account, bumping the permissions version of the account so that
+/// its cached permissions are invalidated. Attaching twice, or detaching something which is not
+/// attached, has no effect besides the version bump.
+pub(crate) async fn update_attachment(
+    ctx: &Context,
+    ddb: &(impl Query + UpdateItem),
+    permissions_cache: &PermissionsCache,
+    account_id: &Uuid,
+    attachment: Attachment,
+    attach: bool,
+) -> Result<(), UpdateAttachmentError> {
+    let key = account_key_from_id(ddb, ctx.accounts_table_name.as_ref(), account_id)
+        .await
+        .map_err(|e| match e {
+            AccountKeyFromIdError::AccountNotFound => UpdateAttachmentError::AccountNotFound,
+            AccountKeyFromIdError::Datastore(e) => UpdateAttachmentError::Datastore(e),
+        })?;
+    let update_expression = if attach {
+        format!("ADD {} :ids, PermissionsVersion :one", attachment.attribute_name())
+    } else {
+        format!(
+            "DELETE {} :ids ADD PermissionsVersion :one",
+            attachment.attribute_name()
+        )
+    };
+    let update_item_input = UpdateItemInput::builder()
+        .table_name(ctx.accounts_table_name.clone())
+        .key(key)
+        .update_expression(update_expression)
+        .expression_attribute_values(hash_map! {
+            ":ids".to_owned() => AttributeValue::Ss(vec![attachment.id().to_hyphenated().to_string()]),
+            ":one".to_owned() => AttributeValue::N("1".to_owned()),
+        })
+        .return_values(ReturnValue::UpdatedNew)
+        .build();
+
+    let output = ddb.update_item(update_item_input).await.map_err(|e| {
+        log::error!("Failed to update item in DynamoDB. Original error: {:?}.", e);
+        UpdateAttachmentError::Datastore(e.into())
+    })?;
+    permissions_cache.invalidate(account_id, updated_permissions_version(output.attributes.as_ref()));
+
+    Ok(())
+}
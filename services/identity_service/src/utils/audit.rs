@@ -0,0 +1,30 @@
+use uuid::Uuid;
+
+use crate::utils::permissions_diff::PermissionsChange;
+
+/// Target of the log records making up the audit log, so that they can be told apart from the rest.
+pub const AUDIT_LOG_TARGET: &str = "audit";
+
+/// Records the changes made to the permissions document of an account, one record for each effect,
+/// access kind and conditions whose paths changed.
+pub fn log_permissions_changes(account_id: &Uuid, changes: &[PermissionsChange]) {
+    if changes.is_empty() {
+        log::info!(
+            target: AUDIT_LOG_TARGET,
+            "Permissions document of account {} updated without changes.",
+            account_id.to_hyphenated()
+        );
+    }
+
+    for change in changes {
+        log::info!(
+            target: AUDIT_LOG_TARGET,
+            "Permissions document of account {} updated ({:?} {:?}{}):\n{}",
+            account_id.to_hyphenated(),
+            change.effect,
+            change.access_kind,
+            change.conditions,
+            change.diff
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+use std::error::Error;
+
+use aws_sdk_dynamodb::error::{DeleteItemError, DeleteItemErrorKind, PutItemError, PutItemErrorKind};
+use aws_sdk_dynamodb::model::AttributeValue;
+use aws_sdk_dynamodb::types::SdkError;
+use common_macros::hash_map;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use service_core::ddb::delete_item::{DeleteItem, DeleteItemInput};
+use service_core::ddb::get_item::{GetItem, GetItemInput};
+use service_core::ddb::put_item::{PutItem, PutItemInput};
+use service_core::ddb::scan::{Scan, ScanInput};
+use thiserror::Error;
+use uuid::Uuid;
+
+
+pub trait ThreadSafeDdbClient: PutItem + GetItem + Scan + DeleteItem + Send + Sync {}
+impl<T: PutItem + GetItem + Scan + DeleteItem + Send + Sync> ThreadSafeDdbClient for T {}
+
+
+#[derive(Debug, Error)]
+pub enum DdbTableError {
+    #[error("Item not found.")]
+    NotFound,
+
+    #[error(transparent)]
+    Serde(serde_ddb::Error),
+
+    #[error(transparent)]
+    Other(#[from] Box<dyn Error>),
+}
+
+
+/// A DynamoDB table whose items are keyed by a random UUID, e.g. the managed policies by their
+/// `PolicyId`.
+pub struct DdbTable<T: ThreadSafeDdbClient> {
+    ddb: T,
+    table_name: String,
+    key_attribute: &'static str,
+}
+
+impl<T: ThreadSafeDdbClient> DdbTable<T> {
+    pub fn new(ddb: T, table_name: impl Into<String>, key_attribute: &'static str) -> Self {
+        Self {
+            ddb,
+            table_name: table_name.into(),
+            key_attribute,
+        }
+    }
+
+    /// Creates the map to be used as key to the items of the table.
+    fn key(&self, id: &Uuid) -> HashMap<String, AttributeValue> {
+        hash_map! {
+            self.key_attribute.to_owned() => AttributeValue::S(id.to_hyphenated().to_string()),
+        }
+    }
+
+    /// Writes a new item.
+    pub async fn create(&self, item: &impl Serialize) -> Result<(), DdbTableError> {
+        // IDs are random, so a collision is not worth reporting as anything but an error.
+        let condition_expression = format!("attribute_not_exists({})", self.key_attribute);
+        self.put(item, &condition_expression).await.map_err(|e| match e {
+            DdbTableError::NotFound => DdbTableError::Other("ID collision.".into()),
+            e => e,
+        })
+    }
+
+    pub async fn get<I: DeserializeOwned>(&self, id: &Uuid) -> Result<I, DdbTableError> {
+        let get_item_input = GetItemInput::builder()
+            .table_name(self.table_name.as_str())
+            .key(self.key(id))
+            .build();
+        let output = self
+            .ddb
+            .get_item(get_item_input)
+            .await
+            .map_err(|e| DdbTableError::Other(e.into()))?;
+
+        match output.item {
+            None => Err(DdbTableError::NotFound),
+            Some(item) => serde_ddb::from_hashmap(item).map_err(DdbTableError::Serde),
+        }
+    }
+
+    /// Lists a page of items, together with the ID to start the next page from, if any.
+    pub async fn list<I: DeserializeOwned>(
+        &self,
+        starting_token: Option<Uuid>,
+        page_size: u32,
+    ) -> Result<(Vec<I>, Option<Uuid>), DdbTableError> {
+        let scan_input = ScanInput::builder()
+            .table_name(self.table_name.as_str())
+            .limit(page_size as i32)
+            .exclusive_start_key(starting_token.as_ref().map(|id| self.key(id)))
+            .build();
+        let output = self
+            .ddb
+            .scan(scan_input)
+            .await
+            .map_err(|e| DdbTableError::Other(e.into()))?;
+
+        let items = output
+            .items
+            .unwrap_or_default()
+            .into_iter()
+            .map(|item| serde_ddb::from_hashmap(item).map_err(DdbTableError::Serde))
+            .collect::<Result<Vec<I>, _>>()?;
+        let next_token = match output
+            .last_evaluated_key
+            .as_ref()
+            .and_then(|key| key.get(self.key_attribute))
+        {
+            None => None,
+            Some(AttributeValue::S(id)) => {
+                Some(Uuid::parse_str(id).map_err(|_| DdbTableError::Other("Malformed reply: invalid key".into()))?)
+            }
+            Some(_) => return Err(DdbTableError::Other("Malformed reply: invalid key".into())),
+        };
+
+        Ok((items, next_token))
+    }
+
+    /// Replaces an existing item. Fails with `NotFound` if there is no item with the same ID.
+    pub async fn update(&self, item: &impl Serialize) -> Result<(), DdbTableError> {
+        self.put(item, &format!("attribute_exists({})", self.key_attribute))
+            .await
+    }
+
+    pub async fn delete(&self, id: &Uuid) -> Result<(), DdbTableError> {
+        let delete_item_input = DeleteItemInput::builder()
+            .table_name(self.table_name.as_str())
+            .key(self.key(id))
+            .condition_expression(format!("attribute_exists({})", self.key_attribute))
+            .build();
+
+        self.ddb.delete_item(delete_item_input).await.map_err(|err| match err {
+            SdkError::ServiceError {
+                err:
+                    DeleteItemError {
+                        kind: DeleteItemErrorKind::ConditionalCheckFailedException(_),
+                        ..
+                    },
+                ..
+            } => DdbTableError::NotFound,
+            e => DdbTableError::Other(e.into()),
+        })?;
+
+        Ok(())
+    }
+
+    /// Writes the item, as long as the condition on the existing item holds.
+    async fn put(&self, item: &impl Serialize, condition_expression: &str) -> Result<(), DdbTableError> {
+        let put_item_input = PutItemInput::builder()
+            .table_name(self.table_name.as_str())
+            .item(serde_ddb::to_hashmap(item).map_err(DdbTableError::Serde)?)
+            .condition_expression(condition_expression)
+            .build();
+
+        self.ddb.put_item(put_item_input).await.map_err(|err| match err {
+            SdkError::ServiceError {
+                err:
+                    PutItemError {
+                        kind: PutItemErrorKind::ConditionalCheckFailedException(_),
+                        ..
+                    },
+                ..
+            } => DdbTableError::NotFound,
+            e => DdbTableError::Other(e.into()),
+        })?;
+
+        Ok(())
+    }
+}
@@ -1,4 +1,13 @@
 pub mod account;
+pub mod account_events;
+pub mod attachments;
+pub mod audit;
+pub mod ddb_table;
 pub mod memcache;
+pub mod normalization;
 pub mod permissions;
+pub mod permissions_cache;
+pub mod permissions_diff;
+pub mod refresh_tokens;
+pub mod signing_keys;
 pub mod validation;
@@ -0,0 +1,199 @@
+use service_core::resource_access::normalize::{merge_paths, redundant_paths};
+use service_core::resource_access::string_interop::compiler::from_string;
+use service_core::resource_access::types::PathSet;
+
+use crate::user_account::{PermissionsDocument, RenderedPolicyStatement};
+
+/// The minimal equivalent form of a permissions document, together with the parts of the original
+/// document which are redundant.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NormalizedPermissions {
+    /// One statement for each effect, access kind and set of conditions found in the original
+    /// document, in the order they first appear in.
+    pub permissions_document: PermissionsDocument,
+
+    /// Indices of the statements whose paths are all redundant.
+    pub redundant_statements: Vec<usize>,
+
+    /// Statement and path indices of the paths subsumed by other paths with the same effect, access
+    /// kind and conditions.
+    pub redundant_paths: Vec<(usize, usize)>,
+}
+
+/// Paths of the statements sharing an effect, an access kind and conditions, tagged with their
+/// statement and path indices.
+struct StatementGroup {
+    /// The first statement of the group, which the merged statement inherits all but the paths of.
+    statement: RenderedPolicyStatement,
+    paths: Vec<((usize, usize), PathSet)>,
+}
+
+
+/// Normalizes a permissions document, whose paths are expected to be valid.
+///
+/// On failure, returns the statement and path index of the first path which fails parsing.
+pub fn normalize_permissions(
+    permissions_document: &PermissionsDocument,
+) -> Result<NormalizedPermissions, (usize, usize)> {
+    let mut groups: Vec<StatementGroup> = vec![];
+    for (stmt_idx, stmt) in permissions_document.statements.iter().enumerate() {
+        let group_idx = match groups.iter().position(|group| same_group(&group.statement, stmt)) {
+            Some(group_idx) => group_idx,
+            None => {
+                groups.push(StatementGroup {
+                    statement: stmt.clone(),
+                    paths: vec![],
+                });
+                groups.len() - 1
+            }
+        };
+
+        for (path_idx, path) in stmt.paths.iter().enumerate() {
+            let path_set = from_string(path).map_err(|_| (stmt_idx, path_idx))?;
+            groups[group_idx].paths.push(((stmt_idx, path_idx), path_set));
+        }
+    }
+
+    let mut statements = Vec::with_capacity(groups.len());
+    let mut redundant = vec![];
+    for group in groups {
+        let path_sets: Vec<PathSet> = group.paths.iter().map(|(_, path_set)| path_set.clone()).collect();
+        redundant.extend(
+            redundant_paths(group.statement.effect, &path_sets)
+                .into_iter()
+                .map(|idx| group.paths[idx].0),
+        );
+
+        let merged = merge_paths(group.statement.effect, path_sets);
+        statements.push(RenderedPolicyStatement {
+            paths: merged.paths().into_iter().map(ToString::to_string).collect(),
+            ..group.statement
+        });
+    }
+    redundant.sort_unstable();
+
+    let redundant_statements = permissions_document
+        .statements
+        .iter()
+        .enumerate()
+        .filter(|(stmt_idx, stmt)| {
+            (0..stmt.paths.len()).all(|path_idx| redundant.binary_search(&(*stmt_idx, path_idx)).is_ok())
+        })
+        .map(|(stmt_idx, _)| stmt_idx)
+        .collect();
+
+    Ok(NormalizedPermissions {
+        permissions_document: PermissionsDocument { statements },
+        redundant_statements,
+        redundant_paths: redundant,
+    })
+}
+
+/// Checks whether two statements can be merged, i.e. whether they only differ by their paths.
+fn same_group(lhs: &RenderedPolicyStatement, rhs: &RenderedPolicyStatement) -> bool {
+    lhs.effect == rhs.effect
+        && lhs.access_kind == rhs.access_kind
+        && lhs.not_before == rhs.not_before
+        && lhs.not_after == rhs.not_after
+        && lhs.active_accounts_only == rhs.active_accounts_only
+}
+
+
+#[cfg(test)]
+mod tests {
+    use service_core::resource_access::{AccessKind, Effect};
+
+    use super::*;
+
+    fn statement(effect: Effect, access_kind: AccessKind, paths: &[&str]) -> RenderedPolicyStatement {
+        RenderedPolicyStatement {
+            effect,
+            access_kind,
+            paths: paths.iter().map(|path| path.to_string()).collect(),
+            not_before: None,
+            not_after: None,
+            active_accounts_only: false,
+        }
+    }
+
+    #[test]
+    fn normalizes_overlapping_statements() {
+        let permissions_document = PermissionsDocument {
+            statements: vec![
+                statement(Effect::Allow, AccessKind::Query, &["accounts::id"]),
+                statement(
+                    Effect::Allow,
+                    AccessKind::Mutation,
+                    &["updatePermissions(accountId: *)"],
+                ),
+                statement(
+                    Effect::Allow,
+                    AccessKind::Query,
+                    &["accounts::*", "account(id: *)::email"],
+                ),
+                statement(Effect::Deny, AccessKind::Query, &["accounts::password"]),
+                statement(Effect::Allow, AccessKind::Query, &["accounts::{id, email}"]),
+            ],
+        };
+
+        let normalized = normalize_permissions(&permissions_document).unwrap();
+
+        assert_eq!(
+            normalized.permissions_document,
+            PermissionsDocument {
+                statements: vec![
+                    statement(
+                        Effect::Allow,
+                        AccessKind::Query,
+                        &["account(id: *)::email", "accounts::*"]
+                    ),
+                    statement(
+                        Effect::Allow,
+                        AccessKind::Mutation,
+                        &["updatePermissions(accountId: *)"]
+                    ),
+                    statement(Effect::Deny, AccessKind::Query, &["accounts::password::*"]),
+                ],
+            }
+        );
+        assert_eq!(normalized.redundant_statements, vec![0, 4]);
+        assert_eq!(normalized.redundant_paths, vec![(0, 0), (4, 0)]);
+    }
+
+    #[test]
+    fn keeps_conditional_statements_apart() {
+        let expiring = RenderedPolicyStatement {
+            not_after: Some(100),
+            ..statement(Effect::Allow, AccessKind::Query, &["accounts::email"])
+        };
+        let permissions_document = PermissionsDocument {
+            statements: vec![
+                statement(Effect::Allow, AccessKind::Query, &["accounts::id"]),
+                expiring.clone(),
+                statement(Effect::Allow, AccessKind::Query, &["accounts::{id, firstName}"]),
+            ],
+        };
+
+        let normalized = normalize_permissions(&permissions_document).unwrap();
+
+        assert_eq!(
+            normalized.permissions_document,
+            PermissionsDocument {
+                statements: vec![
+                    statement(Effect::Allow, AccessKind::Query, &["accounts::{firstName, id}"]),
+                    expiring,
+                ],
+            }
+        );
+        assert_eq!(normalized.redundant_statements, vec![0]);
+    }
+
+    #[test]
+    fn reports_invalid_paths() {
+        let permissions_document = PermissionsDocument {
+            statements: vec![statement(Effect::Allow, AccessKind::Query, &["accounts", "accounts::"])],
+        };
+
+        assert_eq!(normalize_permissions(&permissions_document), Err((0, 1)));
+    }
+}
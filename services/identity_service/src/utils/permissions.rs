@@ -1,18 +1,44 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
 use std::error::Error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use aws_sdk_dynamodb::model::AttributeValue;
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use service_core::ddb::get_item::{GetItem, GetItemInput};
 use service_core::ddb::query::Query;
+use service_core::resource_access::explain::{DenialReason, UncoveredPath};
 use service_core::resource_access::string_interop::compiler::from_string;
-use service_core::resource_access::types::PathSet;
-use service_core::resource_access::{AccessKind, AccessRequest};
+use service_core::resource_access::types::{ArgumentValue, Intersects, PathSet};
+use service_core::resource_access::variables::PolicyVariables;
+use service_core::resource_access::{AccessKind, AccessPathSet, AccessRequest, Effect, PolicyStatement};
 use thiserror::Error;
 use uuid::Uuid;
 
+use crate::account_group::{AccountGroup, GroupStoreError, GroupsRepository};
+use crate::managed_policy::{ManagedPolicy, PoliciesRepository, PolicyStoreError};
 use crate::permissions::anonymous::ANONYMOUS_PERMISSIONS;
 use crate::permissions::default::DEFAULT_PERMISSIONS;
-use crate::user_account::PermissionsDocument;
+use crate::user_account::types::AccountState;
+use crate::user_account::{PermissionsDocument, RenderedPolicyStatement};
 use crate::utils::account::{account_key_from_id, AccountKeyFromIdError};
+use crate::utils::permissions_cache::{CachedAccessPathSet, PermissionsCache};
+
+/// Attribute of an account holding the version of its permissions, which is increased on every change
+/// to its permissions document, attached policies or groups.
+pub const PERMISSIONS_VERSION_ATTR: &str = "PermissionsVersion";
+
+/// Policy variable bound to the ID of the subject account.
+pub const SELF_ACCOUNT_ID_VARIABLE: &str = "self.accountId";
+
+/// Prefix of the policy variables bound to the claims of the subject's access token.
+pub const JWT_VARIABLE_PREFIX: &str = "jwt.";
+
+/// Claims which can be referred to with `${jwt.<claim>}`.
+const JWT_VARIABLE_CLAIMS: [&str; 4] = ["sub", "email", "firstName", "lastName"];
 
 #[derive(Error, Debug)]
 pub enum GetPermissionsFromDdbError {
@@ -25,6 +51,50 @@ pub enum GetPermissionsFromDdbError {
     Datastore(Box<dyn Error>),
 }
 
+#[derive(Error, Debug)]
+pub enum SubjectAccessPathSetError {
+    /// The account does not exist.
+    #[error("Account not found.")]
+    AccountNotFound,
+
+    /// The permissions of the account contain an invalid resource path.
+    #[error("Invalid resource path in permissions.")]
+    InvalidPath,
+
+    /// There was an error when communicating to a datastore.
+    #[error("Underlying datastore error: {0}.")]
+    Datastore(Box<dyn Error>),
+}
+
+/// A resource path of a permissions document which fails parsing.
+#[derive(Error, Debug)]
+#[error("Invalid path {path} in {document} (statement: {stmt_idx}, path: {path_idx}).")]
+pub struct InvalidPathError<'a> {
+    pub document: PermissionsDocumentSource,
+    pub path: &'a String,
+    pub stmt_idx: usize,
+    pub path_idx: usize,
+}
+
+/// The owner of a permissions document which an access path set is compiled from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PermissionsDocumentSource {
+    /// The permissions document of the subject itself.
+    Subject,
+    ManagedPolicy(Uuid),
+    Group(Uuid),
+}
+
+impl Display for PermissionsDocumentSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Subject => write!(f, "the permissions document of the subject"),
+            Self::ManagedPolicy(policy_id) => write!(f, "managed policy {}", policy_id.to_hyphenated()),
+            Self::Group(group_id) => write!(f, "group {}", group_id.to_hyphenated()),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 #[serde(deny_unknown_fields)]
@@ -33,19 +103,36 @@ struct AccountIdIndexProjection {
     email: String,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+/// The permissions of an account: its own permissions document, the IDs of the managed policies
+/// attached to it and the IDs of the groups it is a member of, together with their version and the
+/// state of the account, which statements may be conditioned on.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
 #[serde(rename_all = "PascalCase")]
-struct PermissionsDocumentItem {
-    permissions_document: PermissionsDocument,
+pub struct AccountPermissions {
+    #[serde(default)]
+    pub permissions_document: PermissionsDocument,
+
+    #[serde(default)]
+    pub attached_policies: Vec<Uuid>,
+
+    #[serde(default)]
+    pub group_ids: Vec<Uuid>,
+
+    #[serde(default)]
+    pub permissions_version: u64,
+
+    #[serde(default)]
+    pub account_state: AccountState,
 }
 
 
-/// Get the permissions document for the given account ID from the DynamoDB table.
+/// Get the permissions document, the attached policy IDs, the group IDs, the permissions version and
+/// the account state for the given account ID from the DynamoDB table.
 pub async fn get_permissions_from_ddb(
     ddb: &(impl GetItem + Query),
     table_name: &str,
     account_id: &Uuid,
-) -> Result<PermissionsDocument, GetPermissionsFromDdbError> {
+) -> Result<AccountPermissions, GetPermissionsFromDdbError> {
     let key = account_key_from_id(ddb, table_name, &account_id)
         .await
         .map_err(|e| match e {
@@ -54,7 +141,7 @@ pub async fn get_permissions_from_ddb(
         })?;
     let get_item_input = GetItemInput::builder()
         .table_name(table_name)
-        .projection_expression("PermissionsDocument")
+        .projection_expression("PermissionsDocument, AttachedPolicies, GroupIds, PermissionsVersion, AccountState")
         .key(key)
         .build();
     let output = ddb.get_item(get_item_input).await.map_err(|e| {
@@ -64,12 +151,12 @@ pub async fn get_permissions_from_ddb(
 
     match output.item {
         Some(item) => {
-            let item: PermissionsDocumentItem = serde_ddb::from_hashmap(item).map_err(|e| {
+            let item: AccountPermissions = serde_ddb::from_hashmap(item).map_err(|e| {
                 log::error!("Invalid record in DynamoDB. Original error: {:?}.", &e);
                 GetPermissionsFromDdbError::Datastore(e.into())
             })?;
 
-            Ok(item.permissions_document)
+            Ok(item)
         }
         None => {
             log::warn!(
@@ -82,64 +169,457 @@ pub async fn get_permissions_from_ddb(
 }
 
 
-/// Computes a single path set from the given permissions document. This function skips any statement
-/// in the permissions document that does not match the desired access kind.
+/// Reads the permissions version from the attributes returned by an update which increased it.
+///
+/// # Notes
+///
+/// Falls back to the greatest version if it is missing, which keeps the account out of the
+/// permissions cache until the invalidation expires.
+pub fn updated_permissions_version(attributes: Option<&HashMap<String, AttributeValue>>) -> u64 {
+    match attributes.and_then(|attributes| attributes.get(PERMISSIONS_VERSION_ATTR)) {
+        Some(AttributeValue::N(version)) => version.parse().unwrap_or(u64::MAX),
+        _ => {
+            log::warn!("Permissions version missing from the updated attributes.");
+            u64::MAX
+        }
+    }
+}
+
+/// Gets the permissions version of an account after an update, given the attributes returned with
+/// `ReturnValue::UpdatedOld`. Accounts whose permissions never changed have no version stored.
+pub fn next_permissions_version(attributes: Option<&HashMap<String, AttributeValue>>) -> u64 {
+    match attributes.and_then(|attributes| attributes.get(PERMISSIONS_VERSION_ATTR)) {
+        Some(AttributeValue::N(version)) => version
+            .parse::<u64>()
+            .map_or(u64::MAX, |version| version.saturating_add(1)),
+        _ => 1,
+    }
+}
+
+
+/// Retrieves the managed policies with the given IDs.
+///
+/// # Notes
+///
+/// Policies which no longer exist are skipped, since deleting a policy does not detach it from the
+/// accounts holding it.
+pub async fn get_attached_policies(
+    policies_repository: &impl PoliciesRepository,
+    policy_ids: &[Uuid],
+) -> Result<Vec<ManagedPolicy>, PolicyStoreError> {
+    let mut policies = Vec::with_capacity(policy_ids.len());
+    for policy_id in policy_ids {
+        match policies_repository.get_policy(policy_id).await {
+            Ok(policy) => policies.push(policy),
+            Err(PolicyStoreError::NotFound) => {
+                log::warn!("Attached policy {} not found.", policy_id.to_hyphenated());
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(policies)
+}
+
+
+/// Retrieves the groups with the given IDs.
+///
+/// # Notes
+///
+/// Groups which no longer exist are skipped, since deleting a group does not remove its members.
+pub async fn get_account_groups(
+    groups_repository: &impl GroupsRepository,
+    group_ids: &[Uuid],
+) -> Result<Vec<AccountGroup>, GroupStoreError> {
+    let mut groups = Vec::with_capacity(group_ids.len());
+    for group_id in group_ids {
+        match groups_repository.get_group(group_id).await {
+            Ok(group) => groups.push(group),
+            Err(GroupStoreError::NotFound) => {
+                log::warn!("Group {} not found.", group_id.to_hyphenated());
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(groups)
+}
+
+
+/// Computes the allowed and denied path sets from the given permissions document, attached policies
+/// and groups. This function skips any statement that does not match the desired access kind, or
+/// whose conditions do not hold.
 ///
 /// # Notes
 ///
 /// This function merges permissions for anonymous entities, permissions in the given permissions
-/// document and, if the subject entity is authenticated, default permissions for authenticated
-/// entities.
+/// document, attached policies and groups and, if the subject entity is authenticated, default
+/// permissions for authenticated entities. Deny statements from any of these sources override allow
+/// statements from all of them.
 ///
 /// # Arguments
 ///
 /// * `permissions_document` - the permissions document to be used.
+/// * `attached_policies` - the managed policies attached to the subject entity.
+/// * `groups` - the groups the subject entity is a member of.
 /// * `access_kind` - the desired access kind. The statements in the permissions document will be
 /// processed only if they match this.
 /// * `is_authenticated` - whether the subject entity is authenticated. Setting this to true will
 /// result in including the default permissions for authenticated entities into the result.
+/// * `now` - the Unix timestamp, in seconds, which the time bounds of the statements are checked
+/// against.
+/// * `account_state` - the state of the subject account, which the statements restricted to active
+/// accounts are checked against.
 ///
 /// # Returns
 ///
-/// On success, returns the computed path set. On failure, returns a tuple of statement index and
-/// path index (within that statement) indicating which path failed parsing.
-pub fn get_access_path_set(
-    permissions_document: &PermissionsDocument,
+/// On success, returns the computed access path set. On failure, returns the path which failed
+/// parsing, together with the document it belongs to and its indices within that document.
+pub fn get_access_path_set<'a>(
+    permissions_document: &'a PermissionsDocument,
+    attached_policies: &'a [ManagedPolicy],
+    groups: &'a [AccountGroup],
     access_kind: AccessKind,
     is_authenticated: bool,
-) -> Result<PathSet, (&String, usize, usize)> {
-    let mut path_set = PathSet::default();
+    now: i64,
+    account_state: &AccountState,
+) -> Result<AccessPathSet, InvalidPathError<'a>> {
+    let mut access_path_set = AccessPathSet::default();
 
-    ANONYMOUS_PERMISSIONS
-        .iter()
-        .filter(|stmt| stmt.kind == access_kind)
-        .flat_map(|stmt| &stmt.paths)
-        .for_each(|path| path_set.merge_path_node(path.clone()));
+    merge_builtin_statements(&mut access_path_set, &ANONYMOUS_PERMISSIONS, access_kind);
 
     if is_authenticated {
-        DEFAULT_PERMISSIONS
-            .iter()
-            .filter(|stmt| stmt.kind == access_kind)
-            .flat_map(|stmt| &stmt.paths)
-            .for_each(|path| path_set.merge_path_node(path.clone()));
+        merge_builtin_statements(&mut access_path_set, &DEFAULT_PERMISSIONS, access_kind);
     }
 
+    let applies = |stmt: &RenderedPolicyStatement| stmt.access_kind == access_kind && stmt.applies(now, account_state);
+
+    merge_document_statements(
+        &mut access_path_set,
+        PermissionsDocumentSource::Subject,
+        permissions_document,
+        applies,
+    )?;
+
+    for policy in attached_policies {
+        merge_document_statements(
+            &mut access_path_set,
+            PermissionsDocumentSource::ManagedPolicy(policy.policy_id),
+            &policy.permissions_document,
+            applies,
+        )?;
+    }
+
+    for group in groups {
+        merge_document_statements(
+            &mut access_path_set,
+            PermissionsDocumentSource::Group(group.group_id),
+            &group.permissions_document,
+            applies,
+        )?;
+    }
+
+    Ok(access_path_set)
+}
+
+/// Gets the access path set of the given kind for the subject entity, with the policy variables left
+/// unbound.
+///
+/// # Notes
+///
+/// The access path sets of authenticated entities are taken from the permissions cache when possible.
+/// Otherwise, they are compiled from the permissions of the account, then cached.
+///
+/// # Arguments
+///
+/// * `table_name` - the name of the accounts table.
+/// * `account_id` - the ID of the subject account, if it is authenticated.
+/// * `access_kind` - the desired access kind.
+pub async fn get_subject_access_path_set(
+    ddb: &(impl GetItem + Query),
+    table_name: &str,
+    policies_repository: &impl PoliciesRepository,
+    groups_repository: &impl GroupsRepository,
+    permissions_cache: &PermissionsCache,
+    account_id: Option<&Uuid>,
+    access_kind: AccessKind,
+) -> Result<CachedAccessPathSet, SubjectAccessPathSetError> {
+    if let Some(cached) = account_id.and_then(|account_id| permissions_cache.get(account_id, access_kind)) {
+        return Ok(cached);
+    }
+
+    // Read before compiling, so that policies or groups changing meanwhile keep it from being cached.
+    let generation = permissions_cache.generation();
+    let compiled = compile_subject_access_path_set(
+        ddb,
+        table_name,
+        policies_repository,
+        groups_repository,
+        account_id,
+        None,
+        access_kind,
+    )
+    .await?;
+    if let Some(account_id) = account_id {
+        permissions_cache.insert(account_id, access_kind, generation, compiled.clone());
+    }
+
+    Ok(compiled)
+}
+
+/// Reads the permissions of the subject account, if any, and compiles them into the access path set
+/// of the given kind, bypassing the permissions cache.
+///
+/// # Arguments
+///
+/// * `account_id` - the ID of the subject account, if it is authenticated.
+/// * `candidate_document` - a permissions document compiled in place of the account's own one. The
+/// subject is considered authenticated when one is given.
+pub async fn compile_subject_access_path_set(
+    ddb: &(impl GetItem + Query),
+    table_name: &str,
+    policies_repository: &impl PoliciesRepository,
+    groups_repository: &impl GroupsRepository,
+    account_id: Option<&Uuid>,
+    candidate_document: Option<PermissionsDocument>,
+    access_kind: AccessKind,
+) -> Result<CachedAccessPathSet, SubjectAccessPathSetError> {
+    let account_permissions = if let Some(account_id) = account_id {
+        get_permissions_from_ddb(ddb, table_name, account_id)
+            .await
+            .map_err(|e| match e {
+                GetPermissionsFromDdbError::AccountNotFound => SubjectAccessPathSetError::AccountNotFound,
+                GetPermissionsFromDdbError::Datastore(e) => SubjectAccessPathSetError::Datastore(e),
+            })?
+    } else {
+        AccountPermissions::default()
+    };
+    let attached_policies = get_attached_policies(policies_repository, &account_permissions.attached_policies)
+        .await
+        .map_err(|e| {
+            log::error!("Failed retrieving attached policies: {:?}.", e);
+            SubjectAccessPathSetError::Datastore(e.into())
+        })?;
+    let groups = get_account_groups(groups_repository, &account_permissions.group_ids)
+        .await
+        .map_err(|e| {
+            log::error!("Failed retrieving groups: {:?}.", e);
+            SubjectAccessPathSetError::Datastore(e.into())
+        })?;
+
+    let is_authenticated = account_id.is_some() || candidate_document.is_some();
+    // Candidate documents simulated without an account are evaluated as if for an active one.
+    let account_state = match account_id {
+        Some(_) => account_permissions.account_state,
+        None => AccountState::Active,
+    };
+    let permissions_document = candidate_document.unwrap_or(account_permissions.permissions_document);
+    let now = Utc::now().timestamp();
+    let access_path_set = get_access_path_set(
+        &permissions_document,
+        &attached_policies,
+        &groups,
+        access_kind,
+        is_authenticated,
+        now,
+        &account_state,
+    )
+    .map_err(|err| {
+        match account_id {
+            Some(account_id) => log::error!("{} Subject account: {}.", err, account_id.to_hyphenated()),
+            None => log::error!("{}", err),
+        }
+        SubjectAccessPathSetError::InvalidPath
+    })?;
+
+    let documents = attached_policies
+        .iter()
+        .map(|policy| &policy.permissions_document)
+        .chain(groups.iter().map(|group| &group.permissions_document))
+        .chain([&permissions_document]);
+    let valid_until = next_transition(documents, now)
+        .map(|transition| Instant::now() + Duration::from_secs(transition.saturating_sub(now) as u64));
+
+    Ok(CachedAccessPathSet {
+        permissions_version: account_permissions.permissions_version,
+        permissions_document: Arc::new(permissions_document),
+        access_path_set,
+        valid_until,
+    })
+}
+
+/// Finds the earliest time, after the given Unix timestamp in seconds, at which a statement of the
+/// given permissions documents starts or stops applying.
+fn next_transition<'a>(documents: impl IntoIterator<Item = &'a PermissionsDocument>, now: i64) -> Option<i64> {
+    documents
+        .into_iter()
+        .flat_map(|document| document.statements.iter())
+        .flat_map(|stmt| {
+            [
+                stmt.not_before,
+                stmt.not_after.map(|not_after| not_after.saturating_add(1)),
+            ]
+        })
+        .flatten()
+        .filter(|transition| *transition > now)
+        .min()
+}
+
+/// Merges the statements of a permissions document matching the given predicate into the access path
+/// set. Fails on the first invalid path.
+fn merge_document_statements<'a>(
+    access_path_set: &mut AccessPathSet,
+    document: PermissionsDocumentSource,
+    permissions_document: &'a PermissionsDocument,
+    is_applicable: impl Fn(&RenderedPolicyStatement) -> bool,
+) -> Result<(), InvalidPathError<'a>> {
     for (stmt_idx, stmt) in permissions_document.statements.iter().enumerate() {
-        if stmt.access_kind != access_kind {
+        if !is_applicable(stmt) {
             continue;
         }
 
         for (path_idx, raw) in stmt.paths.iter().enumerate() {
             let curr_path_set = from_string(raw.as_ref()).map_err(|e| {
                 log::error!("Invalid resource path in document: {}. Error: {:?}", &raw, e);
-                (raw, stmt_idx, path_idx)
+                InvalidPathError {
+                    document,
+                    path: raw,
+                    stmt_idx,
+                    path_idx,
+                }
             })?;
 
-            path_set.merge_path_set(curr_path_set);
+            for path in curr_path_set.into_paths() {
+                match stmt.effect {
+                    Effect::Allow => access_path_set.allow(path),
+                    Effect::Deny => access_path_set.deny(path),
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Computes the values of the policy variables for the subject of an authorization.
+///
+/// # Arguments
+///
+/// * `account_id` - the ID of the subject account, if it is authenticated.
+/// * `claims` - the string claims of the subject's access token. Only claims which can be referred
+/// to by a policy variable are bound, and only if the subject is authenticated.
+pub fn subject_variables(account_id: Option<&Uuid>, claims: &HashMap<String, String>) -> PolicyVariables {
+    let mut variables = PolicyVariables::default();
+    let Some(account_id) = account_id else { return variables };
+
+    variables.insert(
+        SELF_ACCOUNT_ID_VARIABLE,
+        ArgumentValue::StringLiteral(account_id.to_hyphenated().to_string()),
+    );
+    for claim in JWT_VARIABLE_CLAIMS {
+        if let Some(value) = claims.get(claim) {
+            variables.insert(
+                format!("{JWT_VARIABLE_PREFIX}{claim}"),
+                ArgumentValue::StringLiteral(value.clone()),
+            );
+        }
+    }
+
+    variables
+}
+
+/// Checks whether a policy variable can be bound by `subject_variables`.
+pub fn is_known_variable(name: &str) -> bool {
+    name == SELF_ACCOUNT_ID_VARIABLE
+        || name
+            .strip_prefix(JWT_VARIABLE_PREFIX)
+            .map_or(false, |claim| JWT_VARIABLE_CLAIMS.contains(&claim))
+}
+
+/// Merges the statements of built-in permissions (i.e. anonymous or default) matching the given
+/// access kind into the access path set.
+fn merge_builtin_statements(
+    access_path_set: &mut AccessPathSet,
+    statements: &[PolicyStatement],
+    access_kind: AccessKind,
+) {
+    for stmt in statements.iter().filter(|stmt| stmt.kind == access_kind) {
+        for path in &stmt.paths {
+            match stmt.effect {
+                Effect::Allow => access_path_set.allow(path.clone()),
+                Effect::Deny => access_path_set.deny(path.clone()),
+            }
         }
     }
+}
+
 
-    Ok(path_set)
+/// Finds the statement in the permissions document that came nearest to granting the given path.
+///
+/// # Notes
+///
+/// For explicitly denied paths, this is the first deny statement matching the path. Otherwise, it is
+/// the allow statement with the closest path. Built-in permissions, attached policies and groups are
+/// not part of the document, so they are never reported.
+///
+/// # Arguments
+///
+/// * `permissions_document` - the permissions document used for authorization.
+/// * `access_kind` - the access kind of the request.
+/// * `uncovered` - the path which was not granted, as reported by `AccessPathSet::uncovered_paths`.
+/// * `variables` - values of the policy variables for the subject entity.
+///
+/// # Returns
+///
+/// The index of the statement, if any statement of the desired access kind and effect is related to
+/// the path. Statements with invalid paths are skipped.
+pub fn nearest_statement(
+    permissions_document: &PermissionsDocument,
+    access_kind: AccessKind,
+    uncovered: &UncoveredPath,
+    variables: &PolicyVariables,
+) -> Option<usize> {
+    let mut branch_set = PathSet::default();
+    branch_set.merge_path_node(uncovered.path.clone());
+
+    let effect = match uncovered.reason {
+        DenialReason::Denied => Effect::Deny,
+        DenialReason::NotAllowed => Effect::Allow,
+    };
+    let candidates = permissions_document
+        .statements
+        .iter()
+        .enumerate()
+        .filter(|(_, stmt)| stmt.access_kind == access_kind && stmt.effect == effect)
+        .filter_map(|(stmt_idx, stmt)| {
+            let mut stmt_access_path_set = AccessPathSet::default();
+            for raw in &stmt.paths {
+                for path in from_string(raw.as_ref()).ok()?.into_paths() {
+                    match stmt.effect {
+                        Effect::Allow => stmt_access_path_set.allow(path),
+                        Effect::Deny => stmt_access_path_set.deny(path),
+                    }
+                }
+            }
+            Some((stmt_idx, stmt_access_path_set.bind_variables(variables)))
+        });
+
+    match uncovered.reason {
+        DenialReason::Denied => candidates
+            .filter(|(_, stmt_access_path_set)| stmt_access_path_set.denied.intersects(&branch_set))
+            .map(|(stmt_idx, _)| stmt_idx)
+            .next(),
+        DenialReason::NotAllowed => candidates
+            .filter_map(|(stmt_idx, stmt_access_path_set)| {
+                stmt_access_path_set
+                    .allowed
+                    .closest_path(&uncovered.path)
+                    .map(|closest| (closest.score, stmt_idx))
+            })
+            // Prefer the earliest statement on ties.
+            .min_by_key(|(score, stmt_idx)| (Reverse(*score), *stmt_idx))
+            .map(|(_, stmt_idx)| stmt_idx),
+    }
 }
 
 
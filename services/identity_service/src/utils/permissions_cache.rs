@@ -0,0 +1,382 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use service_core::resource_access::{AccessKind, AccessPathSet};
+use uuid::Uuid;
+
+use crate::user_account::PermissionsDocument;
+
+/// In-process cache of the compiled access path sets of accounts, which spares __Authorize__ from
+/// reading and compiling the permissions of an account on every request.
+///
+/// # Notes
+///
+/// Entries are keyed by account ID and tagged with the permissions version they were compiled from.
+/// Invalidating an account records the new version, so that path sets compiled from older versions
+/// by concurrent requests are not cached anymore. Invalidating all the accounts, which have no
+/// version in common, moves the cache to a new generation instead: path sets are only cached under
+/// the generation they were compiled in. Changes made through other instances of the service are only
+/// picked up once entries expire.
+#[derive(Debug)]
+pub struct PermissionsCache {
+    ttl: Duration,
+    entries: RwLock<HashMap<Uuid, CacheEntry>>,
+    /// Only changed while holding the lock of the entries.
+    generation: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    invalidations: AtomicU64,
+}
+
+/// A compiled access path set, together with the permissions document and version it was compiled
+/// from.
+#[derive(Debug, Clone)]
+pub struct CachedAccessPathSet {
+    pub permissions_version: u64,
+    pub permissions_document: Arc<PermissionsDocument>,
+    pub access_path_set: AccessPathSet,
+    /// When a time-bounded statement starts or stops applying, after which the path set must not be
+    /// cached anymore. Not reported by lookups.
+    pub valid_until: Option<Instant>,
+}
+
+/// Counters of the cache lookups and invalidations since the cache was created.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub invalidations: u64,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    version: u64,
+    expires_at: Instant,
+    /// Missing for entries recording an invalidation.
+    permissions_document: Option<Arc<PermissionsDocument>>,
+    access_path_sets: HashMap<AccessKind, AccessPathSet>,
+}
+
+
+impl PermissionsCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: RwLock::new(HashMap::new()),
+            generation: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            invalidations: AtomicU64::new(0),
+        }
+    }
+
+    /// Looks up the access path set of the given kind compiled for an account.
+    pub fn get(&self, account_id: &Uuid, access_kind: AccessKind) -> Option<CachedAccessPathSet> {
+        let cached = self.entries.read().unwrap().get(account_id).and_then(|entry| {
+            if entry.expires_at <= Instant::now() {
+                return None;
+            }
+
+            Some(CachedAccessPathSet {
+                permissions_version: entry.version,
+                permissions_document: entry.permissions_document.clone()?,
+                access_path_set: entry.access_path_sets.get(&access_kind)?.clone(),
+                valid_until: None,
+            })
+        });
+
+        match &cached {
+            Some(_) => self.hits.fetch_add(1, Ordering::Relaxed),
+            None => self.misses.fetch_add(1, Ordering::Relaxed),
+        };
+
+        cached
+    }
+
+    /// The current generation of the cache, to be read before compiling a path set to be cached.
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    /// Caches the access path set of the given kind compiled for an account, unless the permissions
+    /// of the account are known to have changed since the version it was compiled from, or all the
+    /// accounts were invalidated since the given generation.
+    pub fn insert(&self, account_id: &Uuid, access_kind: AccessKind, generation: u64, compiled: CachedAccessPathSet) {
+        let CachedAccessPathSet {
+            permissions_version: version,
+            permissions_document,
+            access_path_set,
+            valid_until,
+        } = compiled;
+        let now = Instant::now();
+        if valid_until.map_or(false, |valid_until| valid_until <= now) {
+            return;
+        }
+
+        let mut entries = self.entries.write().unwrap();
+        if self.generation.load(Ordering::SeqCst) != generation {
+            return;
+        }
+        let entry = entries
+            .entry(*account_id)
+            .or_insert_with(|| CacheEntry::invalidated(version, now));
+
+        if entry.expires_at > now && entry.version > version {
+            return;
+        }
+        if entry.expires_at <= now || entry.version < version || entry.permissions_document.is_none() {
+            *entry = CacheEntry {
+                permissions_document: Some(permissions_document),
+                ..CacheEntry::invalidated(version, now + self.ttl)
+            };
+        }
+        if let Some(valid_until) = valid_until {
+            entry.expires_at = entry.expires_at.min(valid_until);
+        }
+
+        entry.access_path_sets.insert(access_kind, access_path_set);
+    }
+
+    /// Drops the access path sets of an account, whose permissions changed to the given version.
+    pub fn invalidate(&self, account_id: &Uuid, version: u64) {
+        let expires_at = Instant::now() + self.ttl;
+        self.entries
+            .write()
+            .unwrap()
+            .insert(*account_id, CacheEntry::invalidated(version, expires_at));
+        self.invalidations.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Drops the access path sets of all accounts, e.g. when a managed policy or a group changes.
+    pub fn invalidate_all(&self) {
+        let mut entries = self.entries.write().unwrap();
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        entries.clear();
+        self.invalidations.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Drops the entries which expired.
+    pub fn evict_expired(&self) {
+        let now = Instant::now();
+        self.entries.write().unwrap().retain(|_, entry| entry.expires_at > now);
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            invalidations: self.invalidations.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl CacheEntry {
+    fn invalidated(version: u64, expires_at: Instant) -> Self {
+        Self {
+            version,
+            expires_at,
+            permissions_document: None,
+            access_path_sets: HashMap::new(),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use service_core::resource_access::string_interop::compiler::from_string;
+
+    use super::*;
+
+    const TTL: Duration = Duration::from_secs(60);
+
+    fn access_path_set(allowed: &str) -> AccessPathSet {
+        let mut access_path_set = AccessPathSet::default();
+        from_string(allowed)
+            .unwrap()
+            .into_paths()
+            .into_iter()
+            .for_each(|p| access_path_set.allow(p));
+        access_path_set
+    }
+
+    fn cached_paths(cache: &PermissionsCache, account_id: &Uuid, access_kind: AccessKind) -> Option<String> {
+        cache.get(account_id, access_kind).map(|cached| {
+            let paths: Vec<String> = cached
+                .access_path_set
+                .allowed
+                .paths()
+                .into_iter()
+                .map(ToString::to_string)
+                .collect();
+            paths.join(", ")
+        })
+    }
+
+    fn insert(cache: &PermissionsCache, account_id: &Uuid, version: u64, access_kind: AccessKind, allowed: &str) {
+        cache.insert(
+            account_id,
+            access_kind,
+            cache.generation(),
+            CachedAccessPathSet {
+                permissions_version: version,
+                permissions_document: Arc::new(PermissionsDocument::default()),
+                access_path_set: access_path_set(allowed),
+                valid_until: None,
+            },
+        );
+    }
+
+    #[test]
+    fn caches_per_access_kind() {
+        let cache = PermissionsCache::new(TTL);
+        let account_id = Uuid::new_v4();
+
+        assert_eq!(cached_paths(&cache, &account_id, AccessKind::Query), None);
+
+        insert(&cache, &account_id, 0, AccessKind::Query, "a");
+        insert(&cache, &account_id, 0, AccessKind::Mutation, "b");
+
+        assert_eq!(
+            cached_paths(&cache, &account_id, AccessKind::Query).as_deref(),
+            Some("a")
+        );
+        assert_eq!(
+            cached_paths(&cache, &account_id, AccessKind::Mutation).as_deref(),
+            Some("b")
+        );
+        assert_eq!(cached_paths(&cache, &Uuid::new_v4(), AccessKind::Query), None);
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 2,
+                misses: 2,
+                invalidations: 0
+            }
+        );
+    }
+
+    #[test]
+    fn insert_after_invalidation() {
+        for (version, expected) in [(1, None), (2, Some("b")), (3, Some("b"))] {
+            let cache = PermissionsCache::new(TTL);
+            let account_id = Uuid::new_v4();
+            insert(&cache, &account_id, 1, AccessKind::Query, "a");
+
+            cache.invalidate(&account_id, 2);
+            assert_eq!(cached_paths(&cache, &account_id, AccessKind::Query), None);
+
+            insert(&cache, &account_id, version, AccessKind::Query, "b");
+            assert_eq!(
+                cached_paths(&cache, &account_id, AccessKind::Query).as_deref(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn newer_version_replaces_all_access_kinds() {
+        let cache = PermissionsCache::new(TTL);
+        let account_id = Uuid::new_v4();
+        insert(&cache, &account_id, 1, AccessKind::Query, "a");
+        insert(&cache, &account_id, 1, AccessKind::Mutation, "b");
+
+        insert(&cache, &account_id, 2, AccessKind::Query, "c");
+
+        assert_eq!(
+            cached_paths(&cache, &account_id, AccessKind::Query).as_deref(),
+            Some("c")
+        );
+        assert_eq!(cached_paths(&cache, &account_id, AccessKind::Mutation), None);
+    }
+
+    #[test]
+    fn invalidate_all() {
+        let cache = PermissionsCache::new(TTL);
+        let account_id = Uuid::new_v4();
+        insert(&cache, &account_id, 1, AccessKind::Query, "a");
+
+        cache.invalidate_all();
+
+        assert_eq!(cached_paths(&cache, &account_id, AccessKind::Query), None);
+        assert_eq!(cache.stats().invalidations, 1);
+    }
+
+    #[test]
+    fn path_sets_compiled_before_invalidate_all_are_not_cached() {
+        let cache = PermissionsCache::new(TTL);
+        let account_id = Uuid::new_v4();
+        let compiled = CachedAccessPathSet {
+            permissions_version: 1,
+            permissions_document: Arc::new(PermissionsDocument::default()),
+            access_path_set: access_path_set("a"),
+            valid_until: None,
+        };
+
+        // A request reads the generation, then compiles while a policy of the account changes.
+        let generation = cache.generation();
+        cache.invalidate_all();
+        cache.insert(&account_id, AccessKind::Query, generation, compiled.clone());
+        assert_eq!(cached_paths(&cache, &account_id, AccessKind::Query), None);
+
+        cache.insert(&account_id, AccessKind::Query, cache.generation(), compiled);
+        assert_eq!(
+            cached_paths(&cache, &account_id, AccessKind::Query).as_deref(),
+            Some("a")
+        );
+    }
+
+    #[test]
+    fn entries_expire_with_time_bounded_statements() {
+        let cache = PermissionsCache::new(TTL);
+        let account_id = Uuid::new_v4();
+        let compiled = |valid_until| CachedAccessPathSet {
+            permissions_version: 1,
+            permissions_document: Arc::new(PermissionsDocument::default()),
+            access_path_set: access_path_set("a"),
+            valid_until: Some(valid_until),
+        };
+
+        cache.insert(
+            &account_id,
+            AccessKind::Query,
+            cache.generation(),
+            compiled(Instant::now() + TTL),
+        );
+        assert_eq!(
+            cached_paths(&cache, &account_id, AccessKind::Query).as_deref(),
+            Some("a")
+        );
+
+        cache.insert(
+            &account_id,
+            AccessKind::Mutation,
+            cache.generation(),
+            compiled(Instant::now()),
+        );
+        assert_eq!(cached_paths(&cache, &account_id, AccessKind::Mutation), None);
+
+        cache.insert(
+            &account_id,
+            AccessKind::Query,
+            cache.generation(),
+            compiled(Instant::now() + Duration::from_millis(1)),
+        );
+        std::thread::sleep(Duration::from_millis(2));
+        assert_eq!(cached_paths(&cache, &account_id, AccessKind::Query), None);
+    }
+
+    #[test]
+    fn expired_entries_are_missed() {
+        let cache = PermissionsCache::new(Duration::ZERO);
+        let account_id = Uuid::new_v4();
+        insert(&cache, &account_id, 1, AccessKind::Query, "a");
+
+        assert_eq!(cached_paths(&cache, &account_id, AccessKind::Query), None);
+
+        cache.evict_expired();
+        assert!(cache.entries.read().unwrap().is_empty());
+    }
+}
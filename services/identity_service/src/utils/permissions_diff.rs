@@ -0,0 +1,203 @@
+use std::collections::BTreeSet;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use identity_service::pb::policy_statement::{AccessKind as AccessKindModel, Effect as EffectModel};
+use identity_service::pb::PermissionsChange as PermissionsChangeModel;
+use service_core::resource_access::diff::{diff, PathSetDiff};
+use service_core::resource_access::normalize::merge_paths;
+use service_core::resource_access::string_interop::compiler::from_string;
+use service_core::resource_access::types::PathSet;
+use service_core::resource_access::{AccessKind, Effect};
+
+use crate::user_account::{PermissionsDocument, RenderedPolicyStatement};
+
+/// Effects and access kinds which the changes of a permissions document are reported for, in order.
+const CHANGE_KINDS: [(Effect, AccessKind); 6] = [
+    (Effect::Allow, AccessKind::Query),
+    (Effect::Deny, AccessKind::Query),
+    (Effect::Allow, AccessKind::Mutation),
+    (Effect::Deny, AccessKind::Mutation),
+    (Effect::Allow, AccessKind::Subscription),
+    (Effect::Deny, AccessKind::Subscription),
+];
+
+/// The change in the paths matched by the statements of a permissions document with the same effect,
+/// access kind and conditions.
+#[derive(Debug, Clone)]
+pub struct PermissionsChange {
+    pub effect: Effect,
+    pub access_kind: AccessKind,
+    pub conditions: StatementConditions,
+    pub diff: PathSetDiff,
+}
+
+/// The conditions under which a statement applies. Paths granted under different conditions are
+/// diffed separately, so that e.g. bounding a statement in time reports its paths as changed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct StatementConditions {
+    pub not_before: Option<i64>,
+    pub not_after: Option<i64>,
+    pub active_accounts_only: bool,
+}
+
+
+/// Computes what the statements of a permissions document match after a change, compared to before
+/// it. Effects, access kinds and conditions whose paths did not change are left out.
+pub fn permissions_changes(before: &PermissionsDocument, after: &PermissionsDocument) -> Vec<PermissionsChange> {
+    CHANGE_KINDS
+        .into_iter()
+        .flat_map(|(effect, access_kind)| {
+            let conditions: BTreeSet<_> = before
+                .statements
+                .iter()
+                .chain(after.statements.iter())
+                .filter(|stmt| stmt.effect == effect && stmt.access_kind == access_kind)
+                .map(StatementConditions::from)
+                .collect();
+
+            conditions.into_iter().map(move |conditions| PermissionsChange {
+                effect,
+                access_kind,
+                conditions,
+                diff: diff(
+                    &statement_paths(before, effect, access_kind, conditions),
+                    &statement_paths(after, effect, access_kind, conditions),
+                ),
+            })
+        })
+        .filter(|change| !change.diff.is_empty())
+        .collect()
+}
+
+/// Merges the paths of the statements with the given effect, access kind and conditions. Paths which
+/// fail parsing, e.g. stored before being validated, are skipped.
+fn statement_paths(
+    permissions_document: &PermissionsDocument,
+    effect: Effect,
+    access_kind: AccessKind,
+    conditions: StatementConditions,
+) -> PathSet {
+    let paths = permissions_document
+        .statements
+        .iter()
+        .filter(|stmt| {
+            stmt.effect == effect && stmt.access_kind == access_kind && StatementConditions::from(*stmt) == conditions
+        })
+        .flat_map(|stmt| stmt.paths.iter())
+        .filter_map(|path| match from_string(path) {
+            Ok(path_set) => Some(path_set),
+            Err(e) => {
+                log::warn!("Skipping invalid path {} from the diff: {:?}.", path, e);
+                None
+            }
+        });
+
+    merge_paths(effect, paths)
+}
+
+impl From<&RenderedPolicyStatement> for StatementConditions {
+    fn from(statement: &RenderedPolicyStatement) -> Self {
+        StatementConditions {
+            not_before: statement.not_before,
+            not_after: statement.not_after,
+            active_accounts_only: statement.active_accounts_only,
+        }
+    }
+}
+
+/// Renders the conditions which are set, e.g. `, not before 100, active accounts only`, to follow the
+/// effect and access kind of a change.
+impl Display for StatementConditions {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        if let Some(not_before) = self.not_before {
+            write!(f, ", not before {}", not_before)?;
+        }
+        if let Some(not_after) = self.not_after {
+            write!(f, ", not after {}", not_after)?;
+        }
+        if self.active_accounts_only {
+            write!(f, ", active accounts only")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl From<PermissionsChange> for PermissionsChangeModel {
+    fn from(change: PermissionsChange) -> Self {
+        PermissionsChangeModel {
+            effect: match change.effect {
+                Effect::Allow => EffectModel::Allow,
+                Effect::Deny => EffectModel::Deny,
+            } as i32,
+            access_kind: match change.access_kind {
+                AccessKind::Query => AccessKindModel::Query,
+                AccessKind::Mutation => AccessKindModel::Mutation,
+                AccessKind::Subscription => AccessKindModel::Subscription,
+            } as i32,
+            added_paths: change.diff.added.paths().into_iter().map(ToString::to_string).collect(),
+            removed_paths: change
+                .diff
+                .removed
+                .paths()
+                .into_iter()
+                .map(ToString::to_string)
+                .collect(),
+            not_before: change.conditions.not_before,
+            not_after: change.conditions.not_after,
+            active_accounts_only: change.conditions.active_accounts_only,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn statement(paths: &[&str], not_after: Option<i64>) -> RenderedPolicyStatement {
+        RenderedPolicyStatement {
+            effect: Effect::Allow,
+            access_kind: AccessKind::Query,
+            paths: paths.iter().map(ToString::to_string).collect(),
+            not_before: None,
+            not_after,
+            active_accounts_only: false,
+        }
+    }
+
+    fn document(statements: Vec<RenderedPolicyStatement>) -> PermissionsDocument {
+        PermissionsDocument { statements }
+    }
+
+    fn rendered(changes: &[PermissionsChange]) -> Vec<(Option<i64>, String)> {
+        changes
+            .iter()
+            .map(|change| (change.conditions.not_after, change.diff.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn unchanged_documents_have_no_changes() {
+        let before = document(vec![statement(&["a", "b"], Some(100))]);
+        let after = document(vec![statement(&["b"], Some(100)), statement(&["a"], Some(100))]);
+
+        assert!(permissions_changes(&before, &after).is_empty());
+    }
+
+    #[test]
+    fn changed_conditions_are_reported() {
+        let before = document(vec![statement(&["a"], None), statement(&["b"], Some(100))]);
+        let after = document(vec![statement(&["a"], Some(200)), statement(&["b"], Some(300))]);
+
+        assert_eq!(
+            rendered(&permissions_changes(&before, &after)),
+            vec![
+                (None, "- a".to_owned()),
+                (Some(100), "- b".to_owned()),
+                (Some(200), "+ a".to_owned()),
+                (Some(300), "+ b".to_owned()),
+            ]
+        );
+    }
+}
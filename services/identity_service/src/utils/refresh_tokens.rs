@@ -0,0 +1,297 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::refresh_token::{RefreshToken, RefreshTokenFamily, RefreshTokenStore, RefreshTokenStoreError};
+
+#[derive(Debug, Error)]
+pub enum RotateRefreshTokenError {
+    #[error("Refresh token is invalid, expired or revoked.")]
+    InvalidToken,
+
+    #[error("Refresh token {token_id} of family {family_id} was already rotated.")]
+    ReusedToken { token_id: Uuid, family_id: Uuid },
+
+    #[error(transparent)]
+    Store(#[from] RefreshTokenStoreError),
+}
+
+/// Issues a refresh token starting a new family, i.e. a new session of the account, with the client
+/// details reported by the frontend. The token, and its family, expire unless used within `ttl`.
+///
+/// Refresh tokens are grouped in families: authenticating starts a new family, and the token issued
+/// in exchange of a refresh token joins the family of the latter.
+pub async fn issue_refresh_token(
+    refresh_token_store: &dyn RefreshTokenStore,
+    ttl: Duration,
+    account_id: &Uuid,
+    user_agent: Option<String>,
+    ip_address: Option<String>,
+) -> Result<RefreshToken, RefreshTokenStoreError> {
+    let now = Utc::now().timestamp();
+    let family = RefreshTokenFamily {
+        family_id: Uuid::new_v4(),
+        account_id: *account_id,
+        created_at: now,
+        last_refreshed_at: now,
+        expires_at: now + ttl.as_secs() as i64,
+        user_agent,
+        ip_address,
+    };
+    refresh_token_store.put_family(&family).await?;
+
+    issue_family_token(refresh_token_store, &family).await
+}
+
+/// Exchanges a refresh token of an account for a new token of the same family, extending the family
+/// by `ttl`.
+///
+/// # Notes
+///
+/// Only the latest token of a family can be exchanged. Presenting a token which was already rotated
+/// means that either the account or someone who got hold of the token used it before, so the whole
+/// family is revoked and the account has to authenticate again.
+pub async fn rotate_refresh_token(
+    refresh_token_store: &dyn RefreshTokenStore,
+    ttl: Duration,
+    account_id: &Uuid,
+    token_id: &str,
+) -> Result<RefreshToken, RotateRefreshTokenError> {
+    let token_id = Uuid::parse_str(token_id).map_err(|_| RotateRefreshTokenError::InvalidToken)?;
+    let token = refresh_token_store.take_token(&token_id).await?;
+    let Some(mut token) = token else { return Err(RotateRefreshTokenError::InvalidToken) };
+
+    if &token.account_id != account_id {
+        refresh_token_store.put_token(&token).await?;
+        return Err(RotateRefreshTokenError::InvalidToken);
+    }
+    if token.rotated {
+        refresh_token_store.put_token(&token).await?;
+        refresh_token_store.delete_family(&token.family_id).await?;
+        return Err(RotateRefreshTokenError::ReusedToken {
+            token_id,
+            family_id: token.family_id,
+        });
+    }
+    let family = refresh_token_store.get_family(&token.family_id).await?;
+    let Some(mut family) = family else { return Err(RotateRefreshTokenError::InvalidToken) };
+
+    // The rotated token is kept as long as its family, to detect its reuse.
+    let now = Utc::now().timestamp();
+    family.last_refreshed_at = now;
+    family.expires_at = now + ttl.as_secs() as i64;
+    // The family may have been revoked since it was read, in which case it must not come back.
+    if !refresh_token_store.replace_family(&family).await? {
+        return Err(RotateRefreshTokenError::InvalidToken);
+    }
+    token.rotated = true;
+    token.expires_at = family.expires_at;
+    refresh_token_store.put_token(&token).await?;
+
+    Ok(issue_family_token(refresh_token_store, &family).await?)
+}
+
+/// Revokes every refresh token family of an account. Returns the number of families revoked.
+pub async fn revoke_refresh_tokens(
+    refresh_token_store: &dyn RefreshTokenStore,
+    account_id: &Uuid,
+) -> Result<usize, RefreshTokenStoreError> {
+    refresh_token_store.delete_account_families(account_id).await
+}
+
+async fn issue_family_token(
+    refresh_token_store: &dyn RefreshTokenStore,
+    family: &RefreshTokenFamily,
+) -> Result<RefreshToken, RefreshTokenStoreError> {
+    let token = RefreshToken {
+        token_id: Uuid::new_v4(),
+        account_id: family.account_id,
+        family_id: family.family_id,
+        rotated: false,
+        expires_at: family.expires_at,
+    };
+    refresh_token_store.put_token(&token).await?;
+
+    Ok(token)
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::refresh_token::memory_store::InMemoryRefreshTokenStore;
+
+    const TTL: Duration = Duration::from_secs(60);
+
+    /// Revokes every family right after it is read, as a concurrent `RevokeSession` would.
+    #[derive(Default)]
+    struct RevokingStore(InMemoryRefreshTokenStore);
+
+    #[async_trait]
+    impl RefreshTokenStore for RevokingStore {
+        async fn put_token(&self, token: &RefreshToken) -> Result<(), RefreshTokenStoreError> {
+            self.0.put_token(token).await
+        }
+
+        async fn take_token(&self, token_id: &Uuid) -> Result<Option<RefreshToken>, RefreshTokenStoreError> {
+            self.0.take_token(token_id).await
+        }
+
+        async fn put_family(&self, family: &RefreshTokenFamily) -> Result<(), RefreshTokenStoreError> {
+            self.0.put_family(family).await
+        }
+
+        async fn replace_family(&self, family: &RefreshTokenFamily) -> Result<bool, RefreshTokenStoreError> {
+            self.0.replace_family(family).await
+        }
+
+        async fn get_family(&self, family_id: &Uuid) -> Result<Option<RefreshTokenFamily>, RefreshTokenStoreError> {
+            let family = self.0.get_family(family_id).await?;
+            self.0.delete_family(family_id).await?;
+            Ok(family)
+        }
+
+        async fn list_account_families(
+            &self,
+            account_id: &Uuid,
+        ) -> Result<Vec<RefreshTokenFamily>, RefreshTokenStoreError> {
+            self.0.list_account_families(account_id).await
+        }
+
+        async fn delete_family(&self, family_id: &Uuid) -> Result<(), RefreshTokenStoreError> {
+            self.0.delete_family(family_id).await
+        }
+
+        async fn delete_account_families(&self, account_id: &Uuid) -> Result<usize, RefreshTokenStoreError> {
+            self.0.delete_account_families(account_id).await
+        }
+    }
+
+    async fn issue(store: &InMemoryRefreshTokenStore, account_id: &Uuid) -> Uuid {
+        issue_refresh_token(store, TTL, account_id, None, None)
+            .await
+            .unwrap()
+            .token_id
+    }
+
+    #[tokio::test]
+    async fn rotates_tokens() {
+        let store = InMemoryRefreshTokenStore::default();
+        let account_id = Uuid::new_v4();
+
+        let token = issue(&store, &account_id).await;
+        let rotated = rotate_refresh_token(&store, TTL, &account_id, &token.to_string())
+            .await
+            .unwrap()
+            .token_id;
+
+        assert_ne!(token, rotated);
+        assert!(rotate_refresh_token(&store, TTL, &account_id, &rotated.to_string())
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn rejects_tokens_of_other_accounts() {
+        let store = InMemoryRefreshTokenStore::default();
+        let account_id = Uuid::new_v4();
+        let token = issue(&store, &account_id).await;
+
+        let result = rotate_refresh_token(&store, TTL, &Uuid::new_v4(), &token.to_string()).await;
+
+        assert!(matches!(result, Err(RotateRefreshTokenError::InvalidToken)));
+        assert!(rotate_refresh_token(&store, TTL, &account_id, &token.to_string())
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn reuse_revokes_the_family() {
+        let store = InMemoryRefreshTokenStore::default();
+        let account_id = Uuid::new_v4();
+        let token = issue(&store, &account_id).await;
+        let other_family_token = issue(&store, &account_id).await;
+        let rotated = rotate_refresh_token(&store, TTL, &account_id, &token.to_string())
+            .await
+            .unwrap()
+            .token_id;
+
+        let reuse = rotate_refresh_token(&store, TTL, &account_id, &token.to_string()).await;
+
+        assert!(matches!(reuse, Err(RotateRefreshTokenError::ReusedToken { .. })));
+        let result = rotate_refresh_token(&store, TTL, &account_id, &rotated.to_string()).await;
+        assert!(matches!(result, Err(RotateRefreshTokenError::InvalidToken)));
+        assert!(
+            rotate_refresh_token(&store, TTL, &account_id, &other_family_token.to_string())
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn revokes_every_family_of_the_account() {
+        let store = InMemoryRefreshTokenStore::default();
+        let account_id = Uuid::new_v4();
+        let tokens = [issue(&store, &account_id).await, issue(&store, &account_id).await];
+
+        assert_eq!(revoke_refresh_tokens(&store, &account_id).await.unwrap(), 2);
+        for token in tokens {
+            let result = rotate_refresh_token(&store, TTL, &account_id, &token.to_string()).await;
+            assert!(matches!(result, Err(RotateRefreshTokenError::InvalidToken)));
+        }
+    }
+
+    #[tokio::test]
+    async fn revoked_sessions_stop_working() {
+        let store = InMemoryRefreshTokenStore::default();
+        let account_id = Uuid::new_v4();
+        let revoked = issue_refresh_token(&store, TTL, &account_id, None, None).await.unwrap();
+        let kept = issue_refresh_token(&store, TTL, &account_id, Some("curl/7.79.1".to_owned()), None)
+            .await
+            .unwrap();
+
+        store.delete_family(&revoked.family_id).await.unwrap();
+
+        let sessions = store.list_account_families(&account_id).await.unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].family_id, kept.family_id);
+        assert_eq!(sessions[0].user_agent.as_deref(), Some("curl/7.79.1"));
+        let result = rotate_refresh_token(&store, TTL, &account_id, &revoked.token_id.to_string()).await;
+        assert!(matches!(result, Err(RotateRefreshTokenError::InvalidToken)));
+    }
+
+    #[tokio::test]
+    async fn sessions_rotated_past_their_first_expiry_are_revoked() {
+        let store = InMemoryRefreshTokenStore::default();
+        let account_id = Uuid::new_v4();
+        let token = issue_refresh_token(&store, Duration::from_secs(2), &account_id, None, None)
+            .await
+            .unwrap();
+        let rotated = rotate_refresh_token(&store, TTL, &account_id, &token.token_id.to_string())
+            .await
+            .unwrap();
+
+        tokio::time::sleep(Duration::from_millis(2100)).await;
+
+        assert_eq!(store.list_account_families(&account_id).await.unwrap().len(), 1);
+        assert_eq!(revoke_refresh_tokens(&store, &account_id).await.unwrap(), 1);
+        let result = rotate_refresh_token(&store, TTL, &account_id, &rotated.token_id.to_string()).await;
+        assert!(matches!(result, Err(RotateRefreshTokenError::InvalidToken)));
+    }
+
+    #[tokio::test]
+    async fn families_revoked_while_rotating_stay_revoked() {
+        let store = RevokingStore::default();
+        let account_id = Uuid::new_v4();
+        let token = issue_refresh_token(&store, TTL, &account_id, None, None).await.unwrap();
+
+        let result = rotate_refresh_token(&store, TTL, &account_id, &token.token_id.to_string()).await;
+
+        assert!(matches!(result, Err(RotateRefreshTokenError::InvalidToken)));
+        assert_eq!(store.0.get_family(&token.family_id).await.unwrap(), None);
+        assert!(store.list_account_families(&account_id).await.unwrap().is_empty());
+    }
+}
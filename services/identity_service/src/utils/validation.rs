@@ -1,13 +1,35 @@
+use service_core::resource_access::graphql_interop::schema::SchemaInfo;
+use service_core::resource_access::graphql_interop::validation::validate_path;
 use service_core::resource_access::string_interop::compiler::from_string;
 
 use crate::user_account::RenderedPolicyStatement;
+use crate::utils::permissions::is_known_variable;
 
+/// Checks that the paths of the statements parse and only use known policy variables. When the
+/// GraphQL schema is given, the paths must also match it under the root of their access kind.
+///
+/// On failure, returns the index of the statement and of the path within it.
 pub(crate) fn validate_resource_paths<'a>(
+    schema_info: Option<&SchemaInfo>,
     statements: impl IntoIterator<Item = &'a RenderedPolicyStatement>,
 ) -> Result<(), (usize, usize)> {
     for (stmt_idx, stmt) in statements.into_iter().enumerate() {
         for (path_idx, path) in stmt.paths.iter().enumerate() {
-            from_string(&path).map_err(|_| (stmt_idx, path_idx))?;
+            let path_set = from_string(&path).map_err(|_| (stmt_idx, path_idx))?;
+            if !path_set.variable_names().into_iter().all(is_known_variable) {
+                return Err((stmt_idx, path_idx));
+            }
+
+            if let Some(schema_info) = schema_info {
+                path_set
+                    .paths()
+                    .into_iter()
+                    .try_for_each(|node| validate_path(schema_info, stmt.access_kind, node))
+                    .map_err(|e| {
+                        log::info!("Resource path {} does not match the GraphQL schema: {}", path, e);
+                        (stmt_idx, path_idx)
+                    })?;
+            }
         }
     }
 